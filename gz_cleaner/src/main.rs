@@ -6,15 +6,30 @@
 //! 2. Removes all null and empty fields from each JSON object
 //! 3. Compresses the cleaned data back to gz
 //! 4. Writes to the output folder (gz_cleaned)
+//!
+//! With `--normalise FILE`, `--transform FILE` and/or `--pii FILE`, each object
+//! first goes through those `record_transform` stages: value normalisation
+//! (emails, phones, social URLs, countries), the configured rename/move/derive/
+//! cast/drop/keep steps, then PII policies (drop, redact, HMAC, truncate, mask).
+//!
+//! Lines are read with `record_transform::line_reader`: BOMs and CRLF endings
+//! are stripped, invalid UTF-8 is rejected, repaired or passed through
+//! unchanged (`--utf8 reject|lossy|passthrough`; passthrough is refused when
+//! any stage is configured), and lines longer than `--max-line-bytes` are
+//! skipped. With `--quarantine-dir DIR`, rejected and
+//! over-long lines go to `DIR/<file name>`.
 
 use crossbeam_channel::{bounded, Sender, Receiver};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use record_transform::line_reader::{Line, LineCounts, LineOptions, LineReader, Utf8Mode};
+use record_transform::stages::{StageCounts, Stages};
 use serde_json::{Map, Value};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
+use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
 
@@ -24,6 +39,7 @@ const NUM_WORKERS: usize = 8;
 struct FileTask {
     input_path: String,
     output_path: String,
+    quarantine_path: Option<String>,
 }
 
 /// Statistics for a processed file
@@ -31,6 +47,8 @@ struct FileResult {
     file_name: String,
     rows_processed: u64,
     fields_removed: u64,
+    stage_counts: StageCounts,
+    line_counts: LineCounts,
     duration_secs: f64,
     success: bool,
     error_msg: Option<String>,
@@ -108,13 +126,13 @@ fn count_null_empty_fields(value: &Value) -> u64 {
             }
             count
         }
-        Value::String(s) => if s.is_empty() { 1 } else { 0 },
+        Value::String(s) if s.is_empty() => 1,
         _ => 0,
     }
 }
 
 /// Process a single gz file: read, clean, compress, write
-fn process_file(task: &FileTask) -> FileResult {
+fn process_file(task: &FileTask, stages: &Stages, line_options: LineOptions) -> FileResult {
     let start = Instant::now();
     let file_name = Path::new(&task.input_path)
         .file_name()
@@ -124,6 +142,8 @@ fn process_file(task: &FileTask) -> FileResult {
 
     let mut rows_processed = 0u64;
     let mut fields_removed = 0u64;
+    let mut stage_counts = StageCounts::default();
+    let mut read_error = None;
 
     // Open input file
     let input_file = match File::open(&task.input_path) {
@@ -133,6 +153,8 @@ fn process_file(task: &FileTask) -> FileResult {
                 file_name,
                 rows_processed: 0,
                 fields_removed: 0,
+                stage_counts: StageCounts::default(),
+                line_counts: LineCounts::default(),
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to open input file: {}", e)),
@@ -148,6 +170,8 @@ fn process_file(task: &FileTask) -> FileResult {
                 file_name,
                 rows_processed: 0,
                 fields_removed: 0,
+                stage_counts: StageCounts::default(),
+                line_counts: LineCounts::default(),
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to create output file: {}", e)),
@@ -155,21 +179,51 @@ fn process_file(task: &FileTask) -> FileResult {
         }
     };
 
+    // Rejected and over-long lines, gzip-compressed like the output
+    let quarantine = match &task.quarantine_path {
+        Some(path) => match File::create(path) {
+            Ok(f) => Some(BufWriter::new(GzEncoder::new(f, Compression::default()))),
+            Err(e) => {
+                return FileResult {
+                    file_name,
+                    rows_processed: 0,
+                    fields_removed: 0,
+                    stage_counts: StageCounts::default(),
+                    line_counts: LineCounts::default(),
+                    duration_secs: start.elapsed().as_secs_f64(),
+                    success: false,
+                    error_msg: Some(format!("Failed to create quarantine file: {}", e)),
+                };
+            }
+        },
+        None => None,
+    };
+
     // Setup gz decoder and encoder
     let decoder = GzDecoder::new(input_file);
     let reader = BufReader::with_capacity(1024 * 1024, decoder); // 1MB buffer
+    let mut lines = LineReader::with_quarantine(reader, line_options, quarantine);
 
     let encoder = GzEncoder::new(output_file, Compression::default());
     let mut writer = BufWriter::with_capacity(1024 * 1024, encoder); // 1MB buffer
 
     // Process line by line
-    for line_result in reader.lines() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(e) => {
-                eprintln!("⚠️  Warning: Failed to read line in {}: {}", file_name, e);
+    loop {
+        let line = match lines.next_line() {
+            Ok(Some(Line::Text(l))) => l,
+            Ok(Some(Line::Bytes(raw))) => {
+                // Invalid UTF-8 in passthrough mode, which parse_args only allows without
+                // stages: copied as it is, not cleaned
+                if let Err(e) = writer.write_all(raw).and_then(|_| writer.write_all(b"\n")) {
+                    eprintln!("⚠️  Warning: Failed to write line in {}: {}", file_name, e);
+                }
                 continue;
             }
+            Ok(None) => break,
+            Err(e) => {
+                read_error = Some(format!("Failed to read line {}: {}", lines.counts().lines + 1, e));
+                break;
+            }
         };
 
         // Skip empty lines
@@ -178,7 +232,7 @@ fn process_file(task: &FileTask) -> FileResult {
         }
 
         // Parse JSON
-        let value: Value = match serde_json::from_str(&line) {
+        let mut value: Value = match serde_json::from_str(line) {
             Ok(v) => v,
             Err(e) => {
                 eprintln!("⚠️  Warning: Failed to parse JSON in {}: {}", file_name, e);
@@ -186,6 +240,11 @@ fn process_file(task: &FileTask) -> FileResult {
             }
         };
 
+        // Transform before cleaning, so dropped fields aren't counted as removed nulls
+        if let Value::Object(obj) = &mut value {
+            stages.apply(obj, &mut stage_counts);
+        }
+
         // Count fields to be removed
         fields_removed += count_null_empty_fields(&value);
 
@@ -210,17 +269,37 @@ fn process_file(task: &FileTask) -> FileResult {
         rows_processed += 1;
 
         // Progress indicator every 100k rows
-        if rows_processed % 100_000 == 0 {
+        if rows_processed.is_multiple_of(100_000) {
             println!("   📄 {} - Processed {} rows...", file_name, rows_processed);
         }
     }
 
+    let line_counts = *lines.counts();
+    let finished_quarantine = match lines.into_quarantine() {
+        Some(quarantine) => quarantine.into_inner().map_err(|e| e.into_error()).and_then(|gz| gz.finish()).map(|_| ()),
+        None => Ok(()),
+    };
+    if let Some(msg) = read_error.or(finished_quarantine.err().map(|e| format!("Failed to finish quarantine file: {}", e))) {
+        return FileResult {
+            file_name,
+            rows_processed,
+            fields_removed,
+            stage_counts,
+            line_counts,
+            duration_secs: start.elapsed().as_secs_f64(),
+            success: false,
+            error_msg: Some(msg),
+        };
+    }
+
     // Flush and finish compression
     if let Err(e) = writer.flush() {
         return FileResult {
             file_name,
             rows_processed,
             fields_removed,
+            stage_counts,
+            line_counts,
             duration_secs: start.elapsed().as_secs_f64(),
             success: false,
             error_msg: Some(format!("Failed to flush writer: {}", e)),
@@ -235,6 +314,8 @@ fn process_file(task: &FileTask) -> FileResult {
                 file_name,
                 rows_processed,
                 fields_removed,
+                stage_counts,
+                line_counts,
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to get encoder: {}", e)),
@@ -247,6 +328,8 @@ fn process_file(task: &FileTask) -> FileResult {
             file_name,
             rows_processed,
             fields_removed,
+            stage_counts,
+            line_counts,
             duration_secs: start.elapsed().as_secs_f64(),
             success: false,
             error_msg: Some(format!("Failed to finish compression: {}", e)),
@@ -257,25 +340,77 @@ fn process_file(task: &FileTask) -> FileResult {
         file_name,
         rows_processed,
         fields_removed,
+        stage_counts,
+        line_counts,
         duration_secs: start.elapsed().as_secs_f64(),
         success: true,
         error_msg: None,
     }
 }
 
+/// `[--normalise FILE] [--transform FILE] [--pii FILE] [--utf8 MODE]
+/// [--max-line-bytes N] [--quarantine-dir DIR]`
+#[derive(Default)]
+struct Args {
+    normalise: Option<String>,
+    transform: Option<String>,
+    pii: Option<String>,
+    line_options: LineOptions,
+    quarantine_dir: Option<String>,
+}
+
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    while let Some(arg) = argv.next() {
+        let mut value = |name: &str| argv.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--normalise" => args.normalise = Some(value("--normalise")?),
+            "--transform" => args.transform = Some(value("--transform")?),
+            "--pii" => args.pii = Some(value("--pii")?),
+            "--utf8" => args.line_options.utf8 = Utf8Mode::parse(&value("--utf8")?)?,
+            "--max-line-bytes" => {
+                let n = value("--max-line-bytes")?;
+                args.line_options.max_line_bytes = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(format!("Invalid --max-line-bytes {}", n))?;
+            }
+            "--quarantine-dir" => args.quarantine_dir = Some(value("--quarantine-dir")?),
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+    // Passed-through lines are copied without being parsed, so they would
+    // skip every stage, PII policies included
+    let has_stages = args.normalise.is_some() || args.transform.is_some() || args.pii.is_some();
+    if args.line_options.utf8 == Utf8Mode::Passthrough && has_stages {
+        return Err("--utf8 passthrough can't be used with --normalise, --transform or --pii; use reject or lossy".to_string());
+    }
+    Ok(args)
+}
+
 /// Worker function that processes files from the channel
-fn worker(id: usize, receiver: Receiver<FileTask>, result_sender: Sender<FileResult>) {
+fn worker(
+    id: usize,
+    receiver: Receiver<FileTask>,
+    result_sender: Sender<FileResult>,
+    stages: Arc<Stages>,
+    line_options: LineOptions,
+) {
     println!("🔧 Worker {} started", id);
     
     while let Ok(task) = receiver.recv() {
         println!("🚀 Worker {} processing: {}", id, task.input_path);
-        let result = process_file(&task);
+        let result = process_file(&task, &stages, line_options);
         
         if result.success {
             println!(
                 "✅ Worker {} completed: {} ({} rows, {} fields removed, {:.2}s)",
                 id, result.file_name, result.rows_processed, result.fields_removed, result.duration_secs
             );
+            for event in result.line_counts.describe() {
+                println!("   🧾 {}: {}", result.file_name, event);
+            }
         } else {
             println!(
                 "❌ Worker {} failed: {} - {}",
@@ -299,6 +434,23 @@ fn main() {
     println!("╚════════════════════════════════════════════════════════════════╝");
     println!();
 
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+
+    // Optional transform stages: `--normalise FILE`, `--transform FILE`, `--pii FILE`
+    let stages = match Stages::load(args.normalise.as_deref(), args.transform.as_deref(), args.pii.as_deref()) {
+        Ok(stages) => Arc::new(stages),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+
     // Input files to process
     let files = vec![
         "/media/tamil-07/1220581A2058075F/gz/gz/part-00000.gz",
@@ -317,10 +469,22 @@ fn main() {
         eprintln!("❌ Failed to create output directory: {}", e);
         return;
     }
+    if let Some(dir) = &args.quarantine_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("❌ Failed to create quarantine directory: {}", e);
+            return;
+        }
+    }
 
     println!("📁 Input files: {}", files.len());
     println!("📁 Output directory: {}", output_dir);
     println!("👷 Workers: {}", NUM_WORKERS);
+    if !stages.is_empty() {
+        println!("🔒 Transforms: {}", stages.names().join(" → "));
+    }
+    if let Some(dir) = &args.quarantine_dir {
+        println!("📁 Quarantine directory: {}", dir);
+    }
     println!();
 
     // Create channels for task distribution and result collection
@@ -332,8 +496,10 @@ fn main() {
     for id in 0..NUM_WORKERS {
         let receiver = task_receiver.clone();
         let sender = result_sender.clone();
+        let stages = Arc::clone(&stages);
+        let line_options = args.line_options;
         handles.push(thread::spawn(move || {
-            worker(id, receiver, sender);
+            worker(id, receiver, sender, stages, line_options);
         }));
     }
 
@@ -354,6 +520,7 @@ fn main() {
         let task = FileTask {
             input_path: input_path.to_string(),
             output_path,
+            quarantine_path: args.quarantine_dir.as_ref().map(|dir| format!("{}/{}", dir, file_name)),
         };
         
         if task_sender.send(task).is_err() {
@@ -367,6 +534,8 @@ fn main() {
     // Collect results
     let mut total_rows = 0u64;
     let mut total_fields_removed = 0u64;
+    let mut total_transformed = StageCounts::default();
+    let mut total_line_counts = LineCounts::default();
     let mut successful = 0usize;
     let mut failed = 0usize;
 
@@ -376,9 +545,11 @@ fn main() {
                 successful += 1;
                 total_rows += result.rows_processed;
                 total_fields_removed += result.fields_removed;
+                total_transformed.merge(&result.stage_counts);
             } else {
                 failed += 1;
             }
+            total_line_counts.merge(&result.line_counts);
         }
     }
 
@@ -398,6 +569,19 @@ fn main() {
     println!("❌ Files failed: {}", failed);
     println!("📝 Total rows processed: {}", total_rows);
     println!("🧹 Total null/empty fields removed: {}", total_fields_removed);
+    if !stages.is_empty() {
+        println!("🔒 Values transformed:");
+        for line in stages.describe(&total_transformed) {
+            println!("   {}", line);
+        }
+    }
+    let line_events = total_line_counts.describe();
+    if !line_events.is_empty() {
+        println!("🧾 Line integrity:");
+        for line in line_events {
+            println!("   {}", line);
+        }
+    }
     println!("⏱️  Total time: {:.2}s", total_duration);
     println!("⚡ Throughput: {:.2} rows/sec", total_rows as f64 / total_duration);
     println!();
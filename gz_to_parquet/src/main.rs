@@ -7,16 +7,39 @@
 //!
 //! Flow:
 //! 400 gz files → 8 workers → 400 parquet files
+//!
+//! Parquet writer options (codec, level, row groups, dictionary, bloom
+//! filters) come from an optional JSON config passed as the first argument.
+//! `--normalise FILE`, `--transform FILE` and `--pii FILE` run those
+//! `record_transform` stages on every record before it is loaded.
+//!
+//! Lines are read with `record_transform::line_reader` (BOM and CRLF
+//! stripping, `--utf8 reject|lossy`, `--max-line-bytes N`); rejected and
+//! over-long lines go to `--quarantine-dir DIR` when given. `passthrough` is
+//! refused: a line that isn't valid UTF-8 can't be loaded as it is.
+//!
+//! Every written file is reopened and verified against the rows that were
+//! loaded; a mismatch marks the file as failed.
+
+mod verify;
+mod writer_config;
 
 use crossbeam_channel::{bounded, Receiver, Sender};
-use duckdb::{Connection, Result as DuckResult, Appender};
+use duckdb::{Connection, Result as DuckResult};
 use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use record_transform::line_reader::{Line, LineCounts, LineOptions, LineReader, Utf8Mode};
+use record_transform::stages::{StageCounts, Stages};
 use serde_json::Value;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use std::io::{BufReader, BufWriter};
 use std::path::Path;
+use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
+use verify::RowCounts;
+use writer_config::WriterConfig;
 
 const NUM_WORKERS: usize = 8;
 
@@ -24,24 +47,26 @@ const NUM_WORKERS: usize = 8;
 struct FileTask {
     input_path: String,
     output_path: String,
+    quarantine_path: Option<String>,
 }
 
 /// Result from processing a file
 struct FileResult {
     file_name: String,
     rows_processed: u64,
+    stage_counts: StageCounts,
+    line_counts: LineCounts,
     duration_secs: f64,
     success: bool,
     error_msg: Option<String>,
 }
 
 /// Create the DuckDB table with full schema
-fn create_table(conn: &Connection) -> DuckResult<()> {
+fn create_table(conn: &Connection, writer_config: &WriterConfig, scope: &str) -> DuckResult<()> {
+    conn.execute_batch("PRAGMA threads=1;")?;
+    conn.execute_batch(&writer_config.pragmas(scope))?;
     conn.execute_batch(
         r#"
-        PRAGMA threads=1;
-        PRAGMA memory_limit='2GB';
-        
         CREATE TABLE people (
             id TEXT,
             full_name TEXT,
@@ -126,51 +151,13 @@ fn create_table(conn: &Connection) -> DuckResult<()> {
     )
 }
 
-/// Parse a JSON line and append to DuckDB using fast Appender API
-#[inline]
-fn parse_and_append(line: &str, appender: &mut Appender) -> Result<(), Box<dyn std::error::Error>> {
-    let obj: Value = serde_json::from_str(line)?;
-    let obj = match obj.as_object() {
-        Some(o) => o,
-        None => return Ok(()),
-    };
-
-    // Helper macros for extracting values
-    macro_rules! get_str {
-        ($field:expr) => {
-            obj.get($field).and_then(|v| v.as_str()).map(String::from)
-        };
-    }
-    
-    macro_rules! get_i32 {
-        ($field:expr) => {
-            obj.get($field).and_then(|v| v.as_i64()).map(|n| n as i32)
-        };
-    }
-    
-    macro_rules! get_json {
-        ($field:expr) => {
-            obj.get($field).map(|v| v.to_string())
-        };
-    }
-
-    appender.append_row([
-        get_str!("id"),
-        get_str!("full_name"),
-        get_str!("first_name"),
-        get_str!("middle_initial"),
-        get_str!("middle_name"),
-        get_str!("last_name"),
-        get_str!("gender"),
-    ])?;
-
-    // Since DuckDB Appender doesn't support mixed types easily,
-    // let's use a prepared statement approach instead
-    Ok(())
-}
-
 /// Process a single gz file and write to parquet
-fn process_file(task: &FileTask) -> FileResult {
+fn process_file(
+    task: &FileTask,
+    writer_config: &WriterConfig,
+    stages: &Stages,
+    line_options: LineOptions,
+) -> FileResult {
     let start = Instant::now();
     let file_name = Path::new(&task.input_path)
         .file_name()
@@ -185,6 +172,8 @@ fn process_file(task: &FileTask) -> FileResult {
             return FileResult {
                 file_name,
                 rows_processed: 0,
+                stage_counts: StageCounts::default(),
+                line_counts: LineCounts::default(),
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to open input file: {}", e)),
@@ -199,6 +188,8 @@ fn process_file(task: &FileTask) -> FileResult {
             return FileResult {
                 file_name,
                 rows_processed: 0,
+                stage_counts: StageCounts::default(),
+                line_counts: LineCounts::default(),
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to create DuckDB connection: {}", e)),
@@ -207,10 +198,12 @@ fn process_file(task: &FileTask) -> FileResult {
     };
 
     // Create table
-    if let Err(e) = create_table(&conn) {
+    if let Err(e) = create_table(&conn, writer_config, &file_name) {
         return FileResult {
             file_name,
             rows_processed: 0,
+            stage_counts: StageCounts::default(),
+            line_counts: LineCounts::default(),
             duration_secs: start.elapsed().as_secs_f64(),
             success: false,
             error_msg: Some(format!("Failed to create table: {}", e)),
@@ -226,6 +219,8 @@ fn process_file(task: &FileTask) -> FileResult {
             return FileResult {
                 file_name,
                 rows_processed: 0,
+                stage_counts: StageCounts::default(),
+                line_counts: LineCounts::default(),
                 duration_secs: start.elapsed().as_secs_f64(),
                 success: false,
                 error_msg: Some(format!("Failed to prepare statement: {}", e)),
@@ -233,17 +228,44 @@ fn process_file(task: &FileTask) -> FileResult {
         }
     };
 
+    // Rejected and over-long lines, gzip-compressed like the input
+    let quarantine = match &task.quarantine_path {
+        Some(path) => match File::create(path) {
+            Ok(f) => Some(BufWriter::new(GzEncoder::new(f, Compression::default()))),
+            Err(e) => {
+                return FileResult {
+                    file_name,
+                    rows_processed: 0,
+                    stage_counts: StageCounts::default(),
+                    line_counts: LineCounts::default(),
+                    duration_secs: start.elapsed().as_secs_f64(),
+                    success: false,
+                    error_msg: Some(format!("Failed to create quarantine file: {}", e)),
+                };
+            }
+        },
+        None => None,
+    };
+
     // Setup gz decoder
     let decoder = GzDecoder::new(input_file);
     let reader = BufReader::with_capacity(8 * 1024 * 1024, decoder); // 8MB buffer
+    let mut lines = LineReader::with_quarantine(reader, line_options, quarantine);
 
-    let mut rows_processed = 0u64;
+    let mut parsed = RowCounts::new(stmt.parameter_count());
+    let mut stage_counts = StageCounts::default();
+    let mut read_error = None;
 
     // Process line by line
-    for line_result in reader.lines() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue,
+    loop {
+        let line = match lines.next_line() {
+            Ok(Some(Line::Text(l))) => l,
+            Ok(Some(Line::Bytes(_))) => unreachable!("parse_args refuses --utf8 passthrough"),
+            Ok(None) => break,
+            Err(e) => {
+                read_error = Some(format!("Failed to read line {}: {}", lines.counts().lines + 1, e));
+                break;
+            }
         };
 
         // Skip empty lines
@@ -252,11 +274,15 @@ fn process_file(task: &FileTask) -> FileResult {
         }
 
         // Parse JSON
-        let obj: Value = match serde_json::from_str(&line) {
+        let mut obj: Value = match serde_json::from_str(line) {
             Ok(v) => v,
             Err(_) => continue,
         };
-        
+
+        if let Value::Object(o) = &mut obj {
+            stages.apply(o, &mut stage_counts);
+        }
+
         let obj = match obj.as_object() {
             Some(o) => o,
             None => continue,
@@ -343,7 +369,7 @@ fn process_file(task: &FileTask) -> FileResult {
         let version_status = obj.get("version_status").map(|v| v.to_string());
 
         // Execute prepared statement
-        if stmt.execute(duckdb::params![
+        let params = duckdb::params![
             id, full_name, first_name, middle_initial, middle_name, last_name, gender,
             birth_year, birth_date, linkedin_url, linkedin_username, linkedin_id,
             facebook_url, facebook_username, facebook_id, twitter_url, twitter_username,
@@ -363,53 +389,142 @@ fn process_file(task: &FileTask) -> FileResult {
             phone_numbers, emails, interests, skills, location_names, regions, countries,
             street_addresses, experience, education, profiles, certifications, languages,
             version_status
-        ]).is_ok() {
-            rows_processed += 1;
+        ];
+        if stmt.execute(params).is_ok() {
+            parsed.record(params);
         }
 
         // Progress indicator every 100k rows
-        if rows_processed % 100_000 == 0 && rows_processed > 0 {
+        if parsed.rows.is_multiple_of(100_000) && parsed.rows > 0 {
             let elapsed = start.elapsed().as_secs_f64();
-            let rate = rows_processed as f64 / elapsed;
-            println!("   📄 {} - {} rows ({:.0} rows/sec)", file_name, rows_processed, rate);
+            let rate = parsed.rows as f64 / elapsed;
+            println!("   📄 {} - {} rows ({:.0} rows/sec)", file_name, parsed.rows, rate);
         }
     }
 
     // Drop statement before using conn again
     drop(stmt);
+    let rows_processed = parsed.rows;
+
+    let line_counts = *lines.counts();
+    let finished_quarantine = match lines.into_quarantine() {
+        Some(quarantine) => quarantine.into_inner().map_err(|e| e.into_error()).and_then(|gz| gz.finish()).map(|_| ()),
+        None => Ok(()),
+    };
+    if let Some(msg) = read_error.or(finished_quarantine.err().map(|e| format!("Failed to finish quarantine file: {}", e))) {
+        return FileResult {
+            file_name,
+            rows_processed,
+            stage_counts,
+            line_counts,
+            duration_secs: start.elapsed().as_secs_f64(),
+            success: false,
+            error_msg: Some(msg),
+        };
+    }
 
     // Write to Parquet
     let parquet_sql = format!(
-        "COPY people TO '{}' (FORMAT PARQUET, COMPRESSION ZSTD);",
-        task.output_path
+        "COPY {} TO '{}' ({});",
+        writer_config.copy_source("people"),
+        task.output_path,
+        writer_config.copy_options()
     );
 
     if let Err(e) = conn.execute(&parquet_sql, []) {
         return FileResult {
             file_name,
             rows_processed,
+            stage_counts,
+            line_counts,
             duration_secs: start.elapsed().as_secs_f64(),
             success: false,
             error_msg: Some(format!("Failed to write Parquet: {}", e)),
         };
     }
 
+    // Reopen the written file and check it against what was loaded
+    if writer_config.verify {
+        if let Err(e) = verify::verify_parquet(&conn, "people", &task.output_path, &parsed) {
+            return FileResult {
+                file_name,
+                rows_processed,
+                stage_counts,
+                line_counts,
+                duration_secs: start.elapsed().as_secs_f64(),
+                success: false,
+                error_msg: Some(format!("Verification failed: {}", e)),
+            };
+        }
+    }
+
     FileResult {
         file_name,
         rows_processed,
+        stage_counts,
+        line_counts,
         duration_secs: start.elapsed().as_secs_f64(),
         success: true,
         error_msg: None,
     }
 }
 
+/// Config paths and line handling from the command line:
+/// `[WRITER_CONFIG] [--normalise FILE] [--transform FILE] [--pii FILE]
+/// [--utf8 MODE] [--max-line-bytes N] [--quarantine-dir DIR]`
+#[derive(Default)]
+struct Args {
+    writer_config: Option<String>,
+    normalise: Option<String>,
+    transform: Option<String>,
+    pii: Option<String>,
+    line_options: LineOptions,
+    quarantine_dir: Option<String>,
+}
+
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    while let Some(arg) = argv.next() {
+        let mut value = |name: &str| argv.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--normalise" => args.normalise = Some(value("--normalise")?),
+            "--transform" => args.transform = Some(value("--transform")?),
+            "--pii" => args.pii = Some(value("--pii")?),
+            "--utf8" => args.line_options.utf8 = Utf8Mode::parse(&value("--utf8")?)?,
+            "--max-line-bytes" => {
+                let n = value("--max-line-bytes")?;
+                args.line_options.max_line_bytes = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(format!("Invalid --max-line-bytes {}", n))?;
+            }
+            "--quarantine-dir" => args.quarantine_dir = Some(value("--quarantine-dir")?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            _ if args.writer_config.is_none() => args.writer_config = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    if args.line_options.utf8 == Utf8Mode::Passthrough {
+        return Err("--utf8 passthrough can't be used, invalid UTF-8 can't be loaded into Parquet; use reject or lossy".to_string());
+    }
+    Ok(args)
+}
+
 /// Worker function that processes files from the channel
-fn worker(id: usize, receiver: Receiver<FileTask>, result_sender: Sender<FileResult>) {
+fn worker(
+    id: usize,
+    receiver: Receiver<FileTask>,
+    result_sender: Sender<FileResult>,
+    writer_config: Arc<WriterConfig>,
+    stages: Arc<Stages>,
+    line_options: LineOptions,
+) {
     println!("🔧 Worker {} started", id);
 
     while let Ok(task) = receiver.recv() {
         println!("🚀 Worker {} processing: {}", id, task.input_path);
-        let result = process_file(&task);
+        let result = process_file(&task, &writer_config, &stages, line_options);
 
         if result.success {
             println!(
@@ -417,6 +532,9 @@ fn worker(id: usize, receiver: Receiver<FileTask>, result_sender: Sender<FileRes
                 id, result.file_name, result.rows_processed, result.duration_secs,
                 result.rows_processed as f64 / result.duration_secs
             );
+            for event in result.line_counts.describe() {
+                println!("   🧾 {}: {}", result.file_name, event);
+            }
         } else {
             println!(
                 "❌ Worker {} failed: {} - {}",
@@ -440,6 +558,35 @@ fn main() {
     println!("╚════════════════════════════════════════════════════════════════╝");
     println!();
 
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+
+    // Optional writer config path as the first argument, defaults otherwise
+    let writer_config = match args.writer_config {
+        Some(path) => match WriterConfig::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return;
+            }
+        },
+        None => WriterConfig::default(),
+    };
+    let writer_config = Arc::new(writer_config);
+
+    let stages = match Stages::load(args.normalise.as_deref(), args.transform.as_deref(), args.pii.as_deref()) {
+        Ok(stages) => Arc::new(stages),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+
     // Input files to process (add your 400 files here or use glob)
     let files = vec![
         "/media/tamil-07/1220581A2058075F/gz/gz/part-00000.gz",
@@ -458,10 +605,26 @@ fn main() {
         eprintln!("❌ Failed to create output directory: {}", e);
         return;
     }
+    if let Some(dir) = &args.quarantine_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("❌ Failed to create quarantine directory: {}", e);
+            return;
+        }
+    }
 
     println!("📁 Input files: {}", files.len());
     println!("📁 Output directory: {}", output_dir);
     println!("👷 Workers: {}", NUM_WORKERS);
+    println!("🗜️  Parquet options: {}", writer_config.copy_options());
+    if !writer_config.sort_by.is_empty() {
+        println!("🔀 Sorted by: {}", writer_config.sort_by.join(", "));
+    }
+    if !stages.is_empty() {
+        println!("🔒 Transforms: {}", stages.names().join(" → "));
+    }
+    if let Some(dir) = &args.quarantine_dir {
+        println!("📁 Quarantine directory: {}", dir);
+    }
     println!();
 
     // Create channels for task distribution and result collection
@@ -473,8 +636,11 @@ fn main() {
     for id in 0..NUM_WORKERS {
         let receiver = task_receiver.clone();
         let sender = result_sender.clone();
+        let writer_config = Arc::clone(&writer_config);
+        let stages = Arc::clone(&stages);
+        let line_options = args.line_options;
         handles.push(thread::spawn(move || {
-            worker(id, receiver, sender);
+            worker(id, receiver, sender, writer_config, stages, line_options);
         }));
     }
 
@@ -484,17 +650,19 @@ fn main() {
 
     // Send tasks to workers
     for input_path in &files {
-        let file_name = Path::new(input_path)
+        let input_name = Path::new(input_path)
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
-            .replace(".gz", ".parquet");
+            .to_string();
+        let file_name = input_name.replace(".gz", ".parquet");
 
         let output_path = format!("{}/{}", output_dir, file_name);
 
         let task = FileTask {
             input_path: input_path.to_string(),
             output_path,
+            quarantine_path: args.quarantine_dir.as_ref().map(|dir| format!("{}/{}", dir, input_name)),
         };
 
         if task_sender.send(task).is_err() {
@@ -507,6 +675,8 @@ fn main() {
 
     // Collect results
     let mut total_rows = 0u64;
+    let mut total_transformed = StageCounts::default();
+    let mut total_line_counts = LineCounts::default();
     let mut successful = 0usize;
     let mut failed = 0usize;
 
@@ -515,9 +685,11 @@ fn main() {
             if result.success {
                 successful += 1;
                 total_rows += result.rows_processed;
+                total_transformed.merge(&result.stage_counts);
             } else {
                 failed += 1;
             }
+            total_line_counts.merge(&result.line_counts);
         }
     }
 
@@ -536,9 +708,22 @@ fn main() {
     println!("📊 Files processed successfully: {}", successful);
     println!("❌ Files failed: {}", failed);
     println!("📝 Total rows processed: {}", total_rows);
+    if !stages.is_empty() {
+        println!("🔒 Values transformed:");
+        for line in stages.describe(&total_transformed) {
+            println!("   {}", line);
+        }
+    }
+    let line_events = total_line_counts.describe();
+    if !line_events.is_empty() {
+        println!("🧾 Line integrity:");
+        for line in line_events {
+            println!("   {}", line);
+        }
+    }
     println!("⏱️  Total time: {:.2}s", total_duration);
     println!("⚡ Throughput: {:.2} rows/sec", total_rows as f64 / total_duration);
     println!();
     println!("📦 Parquet files written to: {}", output_dir);
-    println!("   Each file is a column-oriented, ZSTD compressed Parquet file.");
+    println!("   Each file is a column-oriented Parquet file ({:?}).", writer_config.compression);
 }
@@ -0,0 +1,133 @@
+//! Post-write verification of a Parquet file against the table it was copied from
+//! and the rows that were parsed.
+//!
+//! The written file is reopened through `read_parquet` and fully scanned, so a
+//! truncated or corrupted file on a flaky disk fails here instead of later:
+//! - the schema (column names and types, in order) must match the table
+//! - the row count must equal the number of accepted input rows
+//! - every column's null count must match the NULLs bound while parsing
+//!
+//! The expected counts are taken as each row's parameters are bound, not from
+//! the loaded table, so a row lost or nulled between parsing and the written
+//! file is caught as well.
+
+use duckdb::types::{ToSqlOutput, Value, ValueRef};
+use duckdb::{Connection, Result as DuckResult, ToSql};
+
+/// Rows inserted and the NULLs bound per column for them
+pub struct RowCounts {
+    pub rows: u64,
+    pub nulls: Vec<u64>,
+}
+
+impl RowCounts {
+    pub fn new(columns: usize) -> Self {
+        RowCounts {
+            rows: 0,
+            nulls: vec![0; columns],
+        }
+    }
+
+    /// Count one inserted row from the parameters it was bound with
+    pub fn record(&mut self, params: &[&dyn ToSql]) {
+        self.rows += 1;
+        for (total, param) in self.nulls.iter_mut().zip(params) {
+            let null = matches!(
+                param.to_sql(),
+                Ok(ToSqlOutput::Owned(Value::Null) | ToSqlOutput::Borrowed(ValueRef::Null))
+            );
+            *total += u64::from(null);
+        }
+    }
+}
+
+/// Column names and types as DuckDB reports them for a relation
+fn describe(conn: &Connection, relation: &str) -> DuckResult<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(&format!("DESCRIBE {}", relation))?;
+    let mut rows = stmt.query([])?;
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next()? {
+        columns.push((row.get::<_, String>(0)?, row.get::<_, String>(1)?));
+    }
+    Ok(columns)
+}
+
+/// Row count followed by the null count of every column, from one scan
+fn null_counts(conn: &Connection, relation: &str, columns: &[(String, String)]) -> DuckResult<Vec<i64>> {
+    let mut select = vec!["COUNT(*)".to_string()];
+    for (name, _) in columns {
+        select.push(format!("COUNT(*) - COUNT(\"{}\")", name));
+    }
+    let sql = format!("SELECT {} FROM {}", select.join(", "), relation);
+
+    conn.query_row(&sql, [], |row| {
+        (0..select.len()).map(|i| row.get::<_, i64>(i)).collect()
+    })
+}
+
+/// Check `path` against the schema of `table` and the `parsed` counts; returns a
+/// description of every mismatch found
+pub fn verify_parquet(conn: &Connection, table: &str, path: &str, parsed: &RowCounts) -> Result<(), String> {
+    let written = format!("read_parquet('{}')", path.replace('\'', "''"));
+
+    let expected_schema = describe(conn, table)
+        .map_err(|e| format!("Failed to describe table {}: {}", table, e))?;
+    let actual_schema = describe(conn, &format!("SELECT * FROM {}", written))
+        .map_err(|e| format!("Failed to reopen {}: {}", path, e))?;
+
+    if expected_schema != actual_schema {
+        let mut problems = Vec::new();
+        if expected_schema.len() != actual_schema.len() {
+            problems.push(format!(
+                "{} columns written, {} expected",
+                actual_schema.len(),
+                expected_schema.len()
+            ));
+        }
+        for (expected, actual) in expected_schema.iter().zip(&actual_schema) {
+            if expected != actual {
+                problems.push(format!(
+                    "column {} {} written as {} {}",
+                    expected.0, expected.1, actual.0, actual.1
+                ));
+            }
+        }
+        return Err(format!("Schema mismatch: {}", problems.join("; ")));
+    }
+
+    if parsed.nulls.len() != expected_schema.len() {
+        return Err(format!(
+            "{} columns bound while parsing, table {} has {}",
+            parsed.nulls.len(),
+            table,
+            expected_schema.len()
+        ));
+    }
+
+    let actual = null_counts(conn, &written, &expected_schema)
+        .map_err(|e| format!("Failed to scan {}: {}", path, e))?;
+
+    let mut problems = Vec::new();
+    if actual[0] != parsed.rows as i64 {
+        problems.push(format!(
+            "{} rows written, {} rows accepted",
+            actual[0], parsed.rows
+        ));
+    }
+    for (idx, (name, _)) in expected_schema.iter().enumerate() {
+        if actual[idx + 1] != parsed.nulls[idx] as i64 {
+            problems.push(format!(
+                "{} has {} nulls, expected {}",
+                name,
+                actual[idx + 1],
+                parsed.nulls[idx]
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Content mismatch: {}", problems.join("; ")))
+    }
+}
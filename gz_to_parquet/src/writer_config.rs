@@ -0,0 +1,203 @@
+//! Parquet writer tuning, loaded from a JSON config file and rendered into
+//! the option list of DuckDB's `COPY ... TO ... (FORMAT PARQUET, ...)`.
+//!
+//! DuckDB applies these options to the whole file; it has no per-column
+//! codec, dictionary or statistics switches. Bloom filters are written for
+//! every dictionary-encoded column when `bloom_filter` is enabled.
+//!
+//! ```json
+//! {
+//!   "compression": "zstd",
+//!   "compression_level": 9,
+//!   "row_group_size": 500000,
+//!   "bloom_filter_fpp": 0.01,
+//!   "sort_by": ["location_country", "job_title_role"],
+//!   "memory_limit": "2GB",
+//!   "temp_directory": "/media/tamil-07/1220581A2058075F/gz/duckdb_tmp"
+//! }
+//! ```
+//!
+//! With `sort_by` set, each file is written from `SELECT * ... ORDER BY` so
+//! row-group min/max statistics can prune. DuckDB's sort is external: once
+//! `memory_limit` is reached it spills to `temp_directory` instead of failing.
+//!
+//! `verify` (on by default) reopens each written file and checks it; see `verify.rs`.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Brotli,
+    Lz4,
+    Lz4Raw,
+    Zstd,
+}
+
+impl Codec {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Codec::Uncompressed => "UNCOMPRESSED",
+            Codec::Snappy => "SNAPPY",
+            Codec::Gzip => "GZIP",
+            Codec::Brotli => "BROTLI",
+            Codec::Lz4 => "LZ4",
+            Codec::Lz4Raw => "LZ4_RAW",
+            Codec::Zstd => "ZSTD",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ParquetVersion {
+    V1,
+    V2,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriterConfig {
+    pub compression: Codec,
+    pub compression_level: Option<i32>,
+    pub row_group_size: Option<u64>,
+    pub row_group_size_bytes: Option<u64>,
+    pub dictionary: bool,
+    pub dictionary_size_limit: Option<u64>,
+    pub string_dictionary_page_size_limit: Option<u64>,
+    pub bloom_filter: bool,
+    pub bloom_filter_fpp: Option<f64>,
+    pub parquet_version: Option<ParquetVersion>,
+    pub sort_by: Vec<String>,
+    pub memory_limit: String,
+    pub temp_directory: Option<String>,
+    pub verify: bool,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        WriterConfig {
+            compression: Codec::Zstd,
+            compression_level: None,
+            row_group_size: None,
+            row_group_size_bytes: None,
+            dictionary: true,
+            dictionary_size_limit: None,
+            string_dictionary_page_size_limit: None,
+            bloom_filter: true,
+            bloom_filter_fpp: None,
+            parquet_version: None,
+            sort_by: Vec::new(),
+            memory_limit: "2GB".to_string(),
+            temp_directory: None,
+            verify: true,
+        }
+    }
+}
+
+impl WriterConfig {
+    /// Read a config file; keys missing from the file keep their defaults
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read writer config {}: {}", path, e))?;
+        let config: WriterConfig = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid writer config {}: {}", path, e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.row_group_size == Some(0) {
+            return Err("row_group_size must be greater than 0".into());
+        }
+        if self.string_dictionary_page_size_limit == Some(0) {
+            return Err("string_dictionary_page_size_limit must be greater than 0".into());
+        }
+        if let Some(fpp) = self.bloom_filter_fpp {
+            if !(fpp > 0.0 && fpp < 1.0) {
+                return Err("bloom_filter_fpp must be between 0 and 1".into());
+            }
+        }
+        // These end up inside SQL text, so keep them to plain identifiers and sizes
+        for column in &self.sort_by {
+            if column.is_empty() || !column.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("sort_by column {:?} is not a valid column name", column).into());
+            }
+        }
+        if !self.memory_limit.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+            return Err(format!("memory_limit {:?} is not a valid size", self.memory_limit).into());
+        }
+        if let Some(dir) = &self.temp_directory {
+            if dir.contains('\'') {
+                return Err("temp_directory must not contain quotes".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Session settings applied before loading rows. Each connection spills
+    /// into its own `scope` subdirectory so concurrent workers never share files.
+    pub fn pragmas(&self, scope: &str) -> String {
+        let mut pragmas = format!("PRAGMA memory_limit='{}';\n", self.memory_limit);
+        if let Some(dir) = &self.temp_directory {
+            let scope = scope.replace('\'', "_");
+            pragmas.push_str(&format!("PRAGMA temp_directory='{}/{}';\n", dir, scope));
+        }
+        pragmas
+    }
+
+    /// Source of the `COPY` statement: the table itself, or the table in key order
+    pub fn copy_source(&self, table: &str) -> String {
+        if self.sort_by.is_empty() {
+            return table.to_string();
+        }
+        let keys: Vec<String> = self
+            .sort_by
+            .iter()
+            .map(|column| format!("\"{}\" NULLS FIRST", column))
+            .collect();
+        format!("(SELECT * FROM {} ORDER BY {})", table, keys.join(", "))
+    }
+
+    /// Options for the parenthesised list of a `COPY ... TO` statement
+    pub fn copy_options(&self) -> String {
+        let mut options = vec![
+            "FORMAT PARQUET".to_string(),
+            format!("COMPRESSION {}", self.compression.as_sql()),
+        ];
+
+        if let Some(level) = self.compression_level {
+            options.push(format!("COMPRESSION_LEVEL {}", level));
+        }
+        if let Some(rows) = self.row_group_size {
+            options.push(format!("ROW_GROUP_SIZE {}", rows));
+        }
+        if let Some(bytes) = self.row_group_size_bytes {
+            options.push(format!("ROW_GROUP_SIZE_BYTES {}", bytes));
+        }
+
+        // A dictionary size limit of 0 disables dictionary encoding entirely
+        if !self.dictionary {
+            options.push("DICTIONARY_SIZE_LIMIT 0".to_string());
+        } else if let Some(limit) = self.dictionary_size_limit {
+            options.push(format!("DICTIONARY_SIZE_LIMIT {}", limit));
+        }
+        if let Some(limit) = self.string_dictionary_page_size_limit {
+            options.push(format!("STRING_DICTIONARY_PAGE_SIZE_LIMIT {}", limit));
+        }
+
+        options.push(format!("WRITE_BLOOM_FILTER {}", self.bloom_filter));
+        if let Some(fpp) = self.bloom_filter_fpp {
+            options.push(format!("BLOOM_FILTER_FALSE_POSITIVE_RATIO {}", fpp));
+        }
+        if let Some(version) = self.parquet_version {
+            options.push(format!("PARQUET_VERSION {:?}", version));
+        }
+
+        options.join(", ")
+    }
+}
@@ -0,0 +1,333 @@
+//! Typed Arrow column builders driven by the schema config.
+//!
+//! One `ColumnBuilder` is built per output column and fed the matching JSON
+//! value of every record in a batch. Nested lists and structs recurse into
+//! child builders, so arbitrarily nested types come out as native Arrow arrays.
+
+use crate::schema_config::{list_item_field, ColumnDef, ColumnType};
+use arrow::array::{
+    ArrayRef, BooleanBuilder, Date32Builder, Float64Builder, Int32Builder, Int64Builder,
+    ListArray, StringBuilder, StructArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer, ScalarBuffer};
+use arrow::datatypes::Fields;
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+const UNIX_EPOCH: NaiveDate = match NaiveDate::from_ymd_opt(1970, 1, 1) {
+    Some(date) => date,
+    None => panic!("invalid epoch"),
+};
+
+/// What `append` did with one value
+#[derive(Clone, Copy, Default)]
+pub struct Appended {
+    /// The row got a NULL in this column: the value was missing, null or didn't fit
+    pub null: bool,
+    /// Values (this one or nested ones) that were present but did not fit the
+    /// declared type and became NULL
+    pub mismatches: u64,
+}
+
+pub enum ColumnBuilder {
+    Utf8(StringBuilder),
+    Json(StringBuilder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Boolean(BooleanBuilder),
+    Date32(Date32Builder),
+    List {
+        item: ColumnType,
+        offsets: Vec<i32>,
+        validity: Vec<bool>,
+        values: Box<ColumnBuilder>,
+    },
+    Struct {
+        fields: Fields,
+        children: Vec<(String, ColumnBuilder)>,
+        validity: Vec<bool>,
+    },
+}
+
+impl ColumnBuilder {
+    pub fn new(column_type: &ColumnType, capacity: usize) -> Self {
+        match column_type {
+            ColumnType::String => ColumnBuilder::Utf8(StringBuilder::with_capacity(capacity, capacity * 16)),
+            ColumnType::Json => ColumnBuilder::Json(StringBuilder::with_capacity(capacity, capacity * 64)),
+            ColumnType::Int32 => ColumnBuilder::Int32(Int32Builder::with_capacity(capacity)),
+            ColumnType::Int64 => ColumnBuilder::Int64(Int64Builder::with_capacity(capacity)),
+            ColumnType::Float64 => ColumnBuilder::Float64(Float64Builder::with_capacity(capacity)),
+            ColumnType::Boolean => ColumnBuilder::Boolean(BooleanBuilder::with_capacity(capacity)),
+            ColumnType::Date32 => ColumnBuilder::Date32(Date32Builder::with_capacity(capacity)),
+            ColumnType::List(item) => {
+                let mut offsets = Vec::with_capacity(capacity + 1);
+                offsets.push(0);
+                ColumnBuilder::List {
+                    item: (**item).clone(),
+                    offsets,
+                    validity: Vec::with_capacity(capacity),
+                    values: Box::new(ColumnBuilder::new(item, capacity)),
+                }
+            }
+            ColumnType::Struct(children) => ColumnBuilder::Struct {
+                fields: children.iter().map(ColumnDef::field).collect(),
+                children: children
+                    .iter()
+                    .map(|c| (c.name.clone(), ColumnBuilder::new(&c.column_type, capacity)))
+                    .collect(),
+                validity: Vec::with_capacity(capacity),
+            },
+        }
+    }
+
+    /// Append one value
+    pub fn append(&mut self, value: Option<&Value>) -> Appended {
+        let value = value.filter(|v| !v.is_null());
+        let Some(coerced) = value.map(|v| coerce(self.shape(), v)) else {
+            self.append_null();
+            return Appended {
+                null: true,
+                mismatches: 0,
+            };
+        };
+        let Some(coerced) = coerced else {
+            self.append_null();
+            return Appended {
+                null: true,
+                mismatches: 1,
+            };
+        };
+
+        match (self, coerced) {
+            (ColumnBuilder::Utf8(b), Coerced::Text(Value::String(s))) => b.append_value(s),
+            (ColumnBuilder::Utf8(b), Coerced::Text(other)) => b.append_value(other.to_string()),
+            (ColumnBuilder::Json(b), Coerced::Json(v)) => b.append_value(v.to_string()),
+            (ColumnBuilder::Int32(b), Coerced::Int32(n)) => b.append_value(n),
+            (ColumnBuilder::Int64(b), Coerced::Int64(n)) => b.append_value(n),
+            (ColumnBuilder::Float64(b), Coerced::Float64(n)) => b.append_value(n),
+            (ColumnBuilder::Boolean(b), Coerced::Boolean(flag)) => b.append_value(flag),
+            (ColumnBuilder::Date32(b), Coerced::Date32(days)) => b.append_value(days),
+            (
+                ColumnBuilder::List {
+                    offsets,
+                    validity,
+                    values,
+                    ..
+                },
+                Coerced::List(items),
+            ) => {
+                let mut mismatches = 0;
+                for item in items {
+                    mismatches += values.append(Some(item)).mismatches;
+                }
+                let last = *offsets.last().unwrap_or(&0);
+                offsets.push(last + items.len() as i32);
+                validity.push(true);
+                return Appended {
+                    null: false,
+                    mismatches,
+                };
+            }
+            (
+                ColumnBuilder::Struct {
+                    children, validity, ..
+                },
+                Coerced::Struct(obj),
+            ) => {
+                let mut mismatches = 0;
+                for (name, child) in children.iter_mut() {
+                    mismatches += child.append(obj.get(name)).mismatches;
+                }
+                validity.push(true);
+                return Appended {
+                    null: false,
+                    mismatches,
+                };
+            }
+            _ => unreachable!("coerce returns the shape it was given"),
+        }
+        Appended::default()
+    }
+
+    fn shape(&self) -> Shape {
+        match self {
+            ColumnBuilder::Utf8(_) => Shape::Text,
+            ColumnBuilder::Json(_) => Shape::Json,
+            ColumnBuilder::Int32(_) => Shape::Int32,
+            ColumnBuilder::Int64(_) => Shape::Int64,
+            ColumnBuilder::Float64(_) => Shape::Float64,
+            ColumnBuilder::Boolean(_) => Shape::Boolean,
+            ColumnBuilder::Date32(_) => Shape::Date32,
+            ColumnBuilder::List { .. } => Shape::List,
+            ColumnBuilder::Struct { .. } => Shape::Struct,
+        }
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            ColumnBuilder::Utf8(b) | ColumnBuilder::Json(b) => b.append_null(),
+            ColumnBuilder::Int32(b) => b.append_null(),
+            ColumnBuilder::Int64(b) => b.append_null(),
+            ColumnBuilder::Float64(b) => b.append_null(),
+            ColumnBuilder::Boolean(b) => b.append_null(),
+            ColumnBuilder::Date32(b) => b.append_null(),
+            ColumnBuilder::List {
+                offsets, validity, ..
+            } => {
+                let last = *offsets.last().unwrap_or(&0);
+                offsets.push(last);
+                validity.push(false);
+            }
+            ColumnBuilder::Struct {
+                children, validity, ..
+            } => {
+                // Struct children must stay the same length as the struct itself
+                for (_, child) in children.iter_mut() {
+                    child.append_null();
+                }
+                validity.push(false);
+            }
+        }
+    }
+
+    pub fn finish(self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Utf8(mut b) | ColumnBuilder::Json(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Int32(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Float64(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Boolean(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Date32(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::List {
+                item,
+                offsets,
+                validity,
+                values,
+            } => Arc::new(ListArray::new(
+                Arc::new(list_item_field(&item)),
+                OffsetBuffer::new(ScalarBuffer::from(offsets)),
+                values.finish(),
+                Some(NullBuffer::from(validity)),
+            )),
+            ColumnBuilder::Struct {
+                fields,
+                children,
+                validity,
+            } => Arc::new(StructArray::new(
+                fields,
+                children.into_iter().map(|(_, child)| child.finish()).collect(),
+                Some(NullBuffer::from(validity)),
+            )),
+        }
+    }
+}
+
+/// What a column accepts at its own level; nested items are checked against
+/// their own column type
+#[derive(Clone, Copy)]
+enum Shape {
+    Text,
+    Json,
+    Int32,
+    Int64,
+    Float64,
+    Boolean,
+    Date32,
+    List,
+    Struct,
+}
+
+impl Shape {
+    fn of(column_type: &ColumnType) -> Shape {
+        match column_type {
+            ColumnType::String => Shape::Text,
+            ColumnType::Json => Shape::Json,
+            ColumnType::Int32 => Shape::Int32,
+            ColumnType::Int64 => Shape::Int64,
+            ColumnType::Float64 => Shape::Float64,
+            ColumnType::Boolean => Shape::Boolean,
+            ColumnType::Date32 => Shape::Date32,
+            ColumnType::List(_) => Shape::List,
+            ColumnType::Struct(_) => Shape::Struct,
+        }
+    }
+}
+
+/// A present value converted for a column of some shape
+enum Coerced<'a> {
+    /// A string, or a number or bool stored as its JSON text
+    Text(&'a Value),
+    Json(&'a Value),
+    Int32(i32),
+    Int64(i64),
+    Float64(f64),
+    Boolean(bool),
+    Date32(i32),
+    List(&'a [Value]),
+    Struct(&'a Map<String, Value>),
+}
+
+/// The type rules, shared by `append` (which stores the result) and `fits`
+/// (which only checks it); None means the value becomes NULL
+fn coerce(shape: Shape, value: &Value) -> Option<Coerced<'_>> {
+    match shape {
+        Shape::Text => {
+            matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_)).then_some(Coerced::Text(value))
+        }
+        Shape::Json => Some(Coerced::Json(value)),
+        Shape::Int32 => value.as_i64().and_then(|n| i32::try_from(n).ok()).map(Coerced::Int32),
+        Shape::Int64 => value.as_i64().map(Coerced::Int64),
+        Shape::Float64 => value.as_f64().map(Coerced::Float64),
+        Shape::Boolean => value.as_bool().map(Coerced::Boolean),
+        Shape::Date32 => value.as_str().and_then(parse_date).map(Coerced::Date32),
+        Shape::List => value.as_array().map(|items| Coerced::List(items)),
+        Shape::Struct => value.as_object().map(Coerced::Struct),
+    }
+}
+
+/// True when `append` would take the value without turning it, or anything
+/// nested in it, into NULL; missing and null values always fit
+pub fn fits(column_type: &ColumnType, value: Option<&Value>) -> bool {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return true;
+    };
+    match (column_type, coerce(Shape::of(column_type), value)) {
+        (_, None) => false,
+        (ColumnType::List(item), Some(Coerced::List(items))) => items.iter().all(|v| fits(item, Some(v))),
+        (ColumnType::Struct(children), Some(Coerced::Struct(obj))) => {
+            children.iter().all(|c| fits(&c.column_type, obj.get(&c.name)))
+        }
+        _ => true,
+    }
+}
+
+/// The value as the column stores it, so rows can be ordered the way a Parquet
+/// reader sees them: NULL when missing, null or not fitting the type, dates as
+/// days since the epoch and JSON columns as their text
+pub fn stored_value(column_type: &ColumnType, value: Option<&Value>) -> Value {
+    let coerced = value.filter(|v| !v.is_null()).and_then(|v| coerce(Shape::of(column_type), v));
+    match coerced {
+        None => Value::Null,
+        Some(Coerced::Text(Value::String(s))) => Value::String(s.clone()),
+        Some(Coerced::Text(other) | Coerced::Json(other)) => Value::String(other.to_string()),
+        Some(Coerced::Int32(n) | Coerced::Date32(n)) => Value::from(n),
+        Some(Coerced::Int64(n)) => Value::from(n),
+        Some(Coerced::Float64(n)) => Value::from(n),
+        Some(Coerced::Boolean(flag)) => Value::Bool(flag),
+        Some(Coerced::List(items)) => Value::Array(items.to_vec()),
+        Some(Coerced::Struct(obj)) => Value::Object(obj.clone()),
+    }
+}
+
+/// Days since the Unix epoch for `YYYY-MM-DD`, `YYYY-MM` or `YYYY`
+pub fn parse_date(s: &str) -> Option<i32> {
+    let date = match s.len() {
+        10 => NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?,
+        7 => NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d").ok()?,
+        4 => NaiveDate::from_ymd_opt(s.parse().ok()?, 1, 1)?,
+        _ => return None,
+    };
+    Some((date - UNIX_EPOCH).num_days() as i32)
+}
@@ -0,0 +1,161 @@
+//! `count` subcommand: value frequencies of one field (the old `main.py`).
+//!
+//! Usage:
+//!   ndjson_parallel count --field PATH [--top N] [INPUT ...]
+//!
+//! `PATH` is a JSON path, so `status`, `version_status.status` and
+//! `emails[].type` all work; a path crossing an array counts every element.
+//! Strings are counted as-is, other values by their JSON text; nulls and
+//! missing fields are reported separately.
+
+use crate::input_lines::read_lines;
+use crate::json_path::JsonPath;
+use crate::{CHUNK_SIZE, DEFAULT_INPUT};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::{collections::HashMap, error::Error, io, time::Instant};
+
+struct CountArgs {
+    field: JsonPath,
+    top: Option<usize>,
+    inputs: Vec<String>,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<CountArgs, Box<dyn Error>> {
+    let mut field = None;
+    let mut top = None;
+    let mut inputs = Vec::new();
+
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--field" => field = Some(JsonPath::parse(&value("--field")?)?),
+            "--top" => {
+                let n = value("--top")?;
+                top = Some(n.parse().map_err(|_| format!("Invalid --top {}", n))?);
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => inputs.push(arg),
+        }
+    }
+
+    if inputs.is_empty() {
+        inputs.push(DEFAULT_INPUT.to_string());
+    }
+    Ok(CountArgs {
+        field: field.ok_or("count needs --field")?,
+        top,
+        inputs,
+    })
+}
+
+#[derive(Default)]
+struct ValueCounts {
+    rows: u64,
+    missing: u64,
+    skipped_lines: u64,
+    counts: HashMap<String, u64>,
+}
+
+impl ValueCounts {
+    fn merge(mut self, other: ValueCounts) -> ValueCounts {
+        self.rows += other.rows;
+        self.missing += other.missing;
+        self.skipped_lines += other.skipped_lines;
+        for (value, n) in other.counts {
+            *self.counts.entry(value).or_insert(0) += n;
+        }
+        self
+    }
+}
+
+fn count_file(input: &str, field: &JsonPath) -> Result<ValueCounts, Box<dyn Error>> {
+    let mut total = ValueCounts::default();
+    let mut lines = read_lines(input)?.peekable();
+
+    while lines.peek().is_some() {
+        let chunk = lines.by_ref().take(CHUNK_SIZE).collect::<io::Result<Vec<_>>>()?;
+
+        let counts = chunk
+            .par_iter()
+            .filter(|line| !line.trim().is_empty())
+            .fold(ValueCounts::default, |mut acc, line| {
+                let Ok(record) = serde_json::from_str::<Value>(line) else {
+                    acc.skipped_lines += 1;
+                    return acc;
+                };
+                acc.rows += 1;
+
+                let values: Vec<&Value> = field.resolve(&record).into_iter().filter(|v| !v.is_null()).collect();
+                if values.is_empty() {
+                    acc.missing += 1;
+                }
+                for value in values {
+                    let key = match value {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    *acc.counts.entry(key).or_insert(0) += 1;
+                }
+                acc
+            })
+            .reduce(ValueCounts::default, ValueCounts::merge);
+        total = total.merge(counts);
+    }
+
+    Ok(total)
+}
+
+pub fn run(argv: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(argv)?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Input files: {}", args.inputs.len());
+    println!("🔢 Counting values of {}", args.field.as_str());
+    println!();
+
+    let mut total = ValueCounts::default();
+    let mut failed = 0usize;
+
+    for input in &args.inputs {
+        println!("📖 Counting {}", input);
+        match count_file(input, &args.field) {
+            Ok(counts) => {
+                println!(
+                    "✅ {} rows, {} distinct values, {} without a value, {} unparseable lines",
+                    counts.rows,
+                    counts.counts.len(),
+                    counts.missing,
+                    counts.skipped_lines
+                );
+                total = total.merge(counts);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("❌ Failed to count {}: {}", input, e);
+            }
+        }
+    }
+
+    let mut sorted: Vec<(String, u64)> = total.counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let shown = args.top.unwrap_or(sorted.len()).min(sorted.len());
+
+    println!("\n📊 {} ({} rows, {} distinct values):", args.field.as_str(), total.rows, sorted.len());
+    for (value, n) in &sorted[..shown] {
+        println!("{:>12}  {}", n, value);
+    }
+    if shown < sorted.len() {
+        println!("{:>12}  ... {} more values", "", sorted.len() - shown);
+    }
+    println!("{:>12}  (no value)", total.missing);
+
+    println!("\n🎉 Done in {:.2}s", start.elapsed().as_secs_f64());
+
+    if failed > 0 {
+        return Err(format!("{} of {} files failed", failed, args.inputs.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Building blocks for the `dedup` subcommand: duplicate keys, and the small
+//! entry (location, timestamp, id) spilled per keyed record so duplicates can
+//! be grouped one partition at a time (see `partition_spill.rs`).
+
+use crate::json_path::JsonPath;
+use crate::partition_spill::{PartitionSpill, Partitions};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+
+/// An exact duplicate key: one or more single-valued paths (`id`, or
+/// `full_name,birth_year` for a composite key)
+pub struct KeySpec {
+    pub label: String,
+    paths: Vec<JsonPath>,
+    ignore_case: bool,
+}
+
+impl KeySpec {
+    pub fn parse(spec: &str, ignore_case: bool) -> Result<KeySpec, String> {
+        let paths = spec
+            .split(',')
+            .map(|p| JsonPath::parse(p.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(p) = paths.iter().find(|p| p.is_multi()) {
+            return Err(format!("Key path {} must be single-valued (no [] or [*])", p.as_str()));
+        }
+        Ok(KeySpec {
+            label: spec.to_string(),
+            paths,
+            ignore_case,
+        })
+    }
+
+    /// The key text, or None when any part is missing, null or blank
+    pub fn extract(&self, record: &Value) -> Option<String> {
+        let mut parts = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            let text = match path.resolve(record).into_iter().next()? {
+                Value::Null => return None,
+                Value::String(s) => s.trim().to_string(),
+                other => other.to_string(),
+            };
+            if text.is_empty() {
+                return None;
+            }
+            parts.push(if self.ignore_case { text.to_lowercase() } else { text });
+        }
+        if parts.len() == 1 {
+            parts.pop()
+        } else {
+            serde_json::to_string(&parts).ok()
+        }
+    }
+}
+
+/// Company suffixes and filler words ignored when comparing company names
+const COMPANY_NOISE: &[&str] = &[
+    "the", "inc", "llc", "ltd", "limited", "corp", "corporation", "co", "company", "gmbh", "plc", "sa", "ag",
+];
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Near-duplicate identity: `full_name` with its words sorted (so "Smith,
+/// John" matches "John Smith") plus `job_company_name` without punctuation
+/// and legal suffixes. None unless both are present.
+pub fn near_dup_key(record: &Value) -> Option<String> {
+    let mut name = words(record.get("full_name")?.as_str()?);
+    name.sort();
+    let company: Vec<String> = words(record.get("job_company_name")?.as_str()?)
+        .into_iter()
+        .filter(|w| !COMPANY_NOISE.contains(&w.as_str()))
+        .collect();
+    if name.is_empty() || company.is_empty() {
+        return None;
+    }
+    Some(format!("{}|{}", name.join(" "), company.join(" ")))
+}
+
+/// Where a keyed record lives, plus what is needed to pick and report it
+pub struct Entry {
+    pub file: u32,
+    pub line: u64,
+    pub timestamp: Value,
+    pub id: Value,
+}
+
+/// Spilled form of an [`Entry`] under its key: `[kind, key, file, line, timestamp, id]`
+type SpilledEntry = (usize, String, u32, u64, Value, Value);
+
+pub fn spill_entry(spill: &mut PartitionSpill, kind: usize, key: &str, entry: &Entry) -> io::Result<()> {
+    spill.push(&(kind, key), &(kind, key, entry.file, entry.line, &entry.timestamp, &entry.id))
+}
+
+/// (kind, key) → entries in input order
+pub type Groups = HashMap<(usize, String), Vec<Entry>>;
+
+pub fn read_groups(partitions: &Partitions, partition: usize) -> io::Result<Groups> {
+    let mut groups: Groups = HashMap::new();
+    for (kind, key, file, line, timestamp, id) in partitions.read::<SpilledEntry>(partition)? {
+        groups.entry((kind, key)).or_default().push(Entry {
+            file,
+            line,
+            timestamp,
+            id,
+        });
+    }
+    Ok(groups)
+}
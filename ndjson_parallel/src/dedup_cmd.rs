@@ -0,0 +1,384 @@
+//! `dedup` subcommand: find duplicate records across all inputs, and
+//! optionally write a deduplicated copy.
+//!
+//! Usage:
+//!   ndjson_parallel dedup [--key PATH[,PATH...]]... [--ignore-case] [--near-dup]
+//!       [--report FILE] [--samples N] [--output OUT [--keep latest|first] [--latest-by PATH]]
+//!       [--partitions N] [--spill-dir DIR] [INPUT ...]
+//!
+//! Each `--key` (default `id`) is checked on its own; a comma-separated key is
+//! composite. `--near-dup` also groups records by normalised `full_name` plus
+//! `job_company_name` (see `dedup.rs`). Keys are spilled to `--partitions`
+//! hash partitions on disk, so memory holds one partition at a time; the
+//! positions of the duplicates to drop are spilled the same way, as one sorted
+//! run per partition, and merged back in input order while writing.
+//!
+//! `--output` writes every record except the duplicates of the first key,
+//! keeping per group the record with the latest `--latest-by` value
+//! (`job_last_updated` by default; ties keep the earliest record) or, with
+//! `--keep first`, the first one seen. Records without the key are kept.
+
+use crate::dedup::{near_dup_key, read_groups, spill_entry, Entry, KeySpec};
+use crate::external_sort::compare_values;
+use crate::input_lines::read_lines;
+use crate::json_path::JsonPath;
+use crate::line_writer::LineWriter;
+use crate::partition_spill::PartitionSpill;
+use crate::position_runs::{Position, PositionRuns};
+use crate::{CHUNK_SIZE, DEFAULT_INPUT};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::{
+    cmp::{Ordering, Reverse},
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::Instant,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Keep {
+    Latest,
+    First,
+}
+
+struct DedupArgs {
+    keys: Vec<KeySpec>,
+    near_dup: bool,
+    report: String,
+    samples: usize,
+    output: Option<String>,
+    keep: Keep,
+    latest_by: JsonPath,
+    partitions: usize,
+    spill_dir: PathBuf,
+    inputs: Vec<String>,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<DedupArgs, Box<dyn Error>> {
+    let mut key_specs = Vec::new();
+    let mut ignore_case = false;
+    let mut args = DedupArgs {
+        keys: Vec::new(),
+        near_dup: false,
+        report: "dedup_report.json".to_string(),
+        samples: 20,
+        output: None,
+        keep: Keep::Latest,
+        latest_by: JsonPath::parse("job_last_updated")?,
+        partitions: 64,
+        spill_dir: std::env::temp_dir(),
+        inputs: Vec::new(),
+    };
+
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--key" => key_specs.push(value("--key")?),
+            "--ignore-case" => ignore_case = true,
+            "--near-dup" => args.near_dup = true,
+            "--report" => args.report = value("--report")?,
+            "--samples" => {
+                let n = value("--samples")?;
+                args.samples = n.parse().map_err(|_| format!("Invalid --samples {}", n))?;
+            }
+            "--output" => args.output = Some(value("--output")?),
+            "--keep" => {
+                args.keep = match value("--keep")?.as_str() {
+                    "latest" => Keep::Latest,
+                    "first" => Keep::First,
+                    other => return Err(format!("Unknown --keep {} (latest, first)", other).into()),
+                }
+            }
+            "--latest-by" => args.latest_by = JsonPath::parse(&value("--latest-by")?)?,
+            "--partitions" => {
+                let n = value("--partitions")?;
+                args.partitions = n
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or(format!("Invalid --partitions {}", n))?;
+            }
+            "--spill-dir" => args.spill_dir = PathBuf::from(value("--spill-dir")?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => args.inputs.push(arg),
+        }
+    }
+
+    if key_specs.is_empty() {
+        key_specs.push("id".to_string());
+    }
+    args.keys = key_specs
+        .iter()
+        .map(|spec| KeySpec::parse(spec, ignore_case))
+        .collect::<Result<_, _>>()?;
+    if args.inputs.is_empty() {
+        args.inputs.push(DEFAULT_INPUT.to_string());
+    }
+    Ok(args)
+}
+
+/// Duplicate counts for one key (or the near-duplicate identity)
+#[derive(Default)]
+struct KeyReport {
+    with_key: u64,
+    without_key: u64,
+    distinct: u64,
+    duplicate_groups: u64,
+    duplicate_records: u64,
+    largest_group: usize,
+    /// Largest groups seen, as (group size, report entry)
+    samples: Vec<(usize, Value)>,
+}
+
+impl KeyReport {
+    fn to_json(&self, label: &str) -> Value {
+        json!({
+            "key": label,
+            "records_with_key": self.with_key,
+            "records_without_key": self.without_key,
+            "distinct_keys": self.distinct,
+            "duplicate_groups": self.duplicate_groups,
+            "duplicate_records": self.duplicate_records,
+            "largest_group": self.largest_group,
+            "samples": self.samples.iter().map(|(_, v)| v).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Pass 1: spill every key of every record; returns (records, unparseable lines)
+fn spill_keys(
+    args: &DedupArgs,
+    spill: &mut PartitionSpill,
+    reports: &mut [KeyReport],
+) -> Result<(u64, u64), Box<dyn Error>> {
+    let mut records = 0u64;
+    let mut skipped_lines = 0u64;
+
+    for (file_idx, input) in args.inputs.iter().enumerate() {
+        println!("📖 Scanning {}", input);
+        let mut lines = read_lines(input)?.enumerate().peekable();
+        let mut chunk: Vec<(u64, String)> = Vec::with_capacity(CHUNK_SIZE);
+
+        while lines.peek().is_some() {
+            chunk.clear();
+            for (line_no, line) in lines.by_ref().take(CHUNK_SIZE) {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    chunk.push((line_no as u64, line));
+                }
+            }
+
+            // None = unparseable; otherwise the keys per kind, timestamp and id
+            type Keyed = (Vec<Option<String>>, Value, Value);
+            let keyed: Vec<Option<Keyed>> = chunk
+                .par_iter()
+                .map(|(_, line)| {
+                    let record = serde_json::from_str::<Value>(line).ok()?;
+                    let mut keys: Vec<Option<String>> = args.keys.iter().map(|k| k.extract(&record)).collect();
+                    if args.near_dup {
+                        keys.push(near_dup_key(&record));
+                    }
+                    let timestamp = args.latest_by.resolve(&record).into_iter().next().cloned();
+                    let id = record.get("id").cloned().unwrap_or(Value::Null);
+                    Some((keys, timestamp.unwrap_or(Value::Null), id))
+                })
+                .collect();
+
+            for ((line_no, _), keyed) in chunk.iter().zip(keyed) {
+                let Some((keys, timestamp, id)) = keyed else {
+                    skipped_lines += 1;
+                    continue;
+                };
+                records += 1;
+                let entry = Entry {
+                    file: file_idx as u32,
+                    line: *line_no,
+                    timestamp,
+                    id,
+                };
+                for (kind, key) in keys.iter().enumerate() {
+                    match key {
+                        Some(key) => {
+                            reports[kind].with_key += 1;
+                            spill_entry(spill, kind, key, &entry)?;
+                        }
+                        None => reports[kind].without_key += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((records, skipped_lines))
+}
+
+/// Index of the record to keep within a duplicate group (entries in input order)
+fn winner(entries: &[Entry], keep: Keep) -> usize {
+    if keep == Keep::First {
+        return 0;
+    }
+    let mut best = 0;
+    for (i, entry) in entries.iter().enumerate().skip(1) {
+        if compare_values(&entry.timestamp, &entries[best].timestamp) == Ordering::Greater {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Pass 3: copy every record that is not a dropped duplicate, in input order
+fn write_output(inputs: &[String], output: &str, dropped: &PositionRuns) -> Result<(u64, u64), Box<dyn Error>> {
+    let mut out = LineWriter::create(output)?;
+    let mut written = 0u64;
+    let mut removed = 0u64;
+    let mut dropped = dropped.merge()?;
+    let mut next_dropped = dropped.next_position()?;
+
+    for (file_idx, input) in inputs.iter().enumerate() {
+        println!("✍️  Writing {}", input);
+        for (line_no, line) in read_lines(input)?.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if next_dropped == Some((file_idx as u32, line_no as u64)) {
+                removed += 1;
+                next_dropped = dropped.next_position()?;
+            } else {
+                out.write_line(&line)?;
+                written += 1;
+            }
+        }
+    }
+    out.finish()?;
+    Ok((written, removed))
+}
+
+pub fn run(argv: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(argv)?;
+    let mut labels: Vec<String> = args.keys.iter().map(|k| k.label.clone()).collect();
+    if args.near_dup {
+        labels.push("near-duplicate: full_name + job_company_name".to_string());
+    }
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Input files: {}", args.inputs.len());
+    println!("🔑 Keys: {}", labels.join(", "));
+    println!("💾 Spilling to {} partitions in {}", args.partitions, args.spill_dir.display());
+    println!();
+
+    let mut reports: Vec<KeyReport> = labels.iter().map(|_| KeyReport::default()).collect();
+    let mut spill = PartitionSpill::create(&args.spill_dir, "dedup", args.partitions)?;
+    let (records, skipped_lines) = spill_keys(&args, &mut spill, &mut reports)?;
+    let partitions = spill.finish()?;
+
+    // Pass 2: group each partition; losers of the first key are dropped from the output
+    println!("\n🔎 Grouping {} partitions", partitions.count());
+    let mut dropped = PositionRuns::new(&args.spill_dir);
+    for partition in 0..partitions.count() {
+        let mut losers: Vec<Position> = Vec::new();
+        for ((kind, key), entries) in read_groups(&partitions, partition)? {
+            let report = &mut reports[kind];
+            report.distinct += 1;
+            if entries.len() < 2 {
+                continue;
+            }
+            report.duplicate_groups += 1;
+            report.duplicate_records += entries.len() as u64 - 1;
+            report.largest_group = report.largest_group.max(entries.len());
+
+            let keep = winner(&entries, args.keep);
+            if kind == 0 && args.output.is_some() {
+                for (i, entry) in entries.iter().enumerate() {
+                    if i != keep {
+                        losers.push((entry.file, entry.line));
+                    }
+                }
+            }
+
+            if args.samples > 0 {
+                let sample_records: Vec<Value> = entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| {
+                        json!({
+                            "id": e.id,
+                            "file": args.inputs[e.file as usize],
+                            "line": e.line + 1,
+                            "latest_by": e.timestamp,
+                            "kept": i == keep,
+                        })
+                    })
+                    .collect();
+                let sample = json!({ "key": key, "count": entries.len(), "records": sample_records });
+                report.samples.push((entries.len(), sample));
+                if report.samples.len() >= args.samples * 2 {
+                    report.samples.sort_by_key(|s| Reverse(s.0));
+                    report.samples.truncate(args.samples);
+                }
+            }
+        }
+        dropped.push_run(losers)?;
+    }
+    for report in &mut reports {
+        report.samples.sort_by_key(|s| Reverse(s.0));
+        report.samples.truncate(args.samples);
+    }
+    drop(partitions);
+
+    println!("\n📊 {} records, {} unparseable lines", records, skipped_lines);
+    for (label, report) in labels.iter().zip(&reports) {
+        println!(
+            "   {}: {} distinct, {} duplicate groups, {} duplicate records (largest group {}), {} without key",
+            label,
+            report.distinct,
+            report.duplicate_groups,
+            report.duplicate_records,
+            report.largest_group,
+            report.without_key
+        );
+    }
+
+    let mut report = json!({
+        "inputs": args.inputs,
+        "records": records,
+        "unparseable_lines": skipped_lines,
+        "keys": labels[..args.keys.len()]
+            .iter()
+            .zip(&reports)
+            .map(|(label, r)| r.to_json(label))
+            .collect::<Vec<_>>(),
+    });
+    if args.near_dup {
+        report["near_duplicates"] = reports[args.keys.len()].to_json(&labels[args.keys.len()]);
+    }
+
+    if let Some(output) = &args.output {
+        println!();
+        let (written, removed) = write_output(&args.inputs, output, &dropped)?;
+        println!(
+            "✅ {} records written to {}, {} duplicates of {} removed",
+            written, output, removed, labels[0]
+        );
+        report["output"] = json!({
+            "path": output,
+            "key": labels[0],
+            "keep": if args.keep == Keep::Latest { "latest" } else { "first" },
+            "latest_by": args.latest_by.as_str(),
+            "written": written,
+            "removed": removed,
+        });
+    }
+
+    let mut w = BufWriter::new(File::create(&args.report)?);
+    serde_json::to_writer_pretty(&mut w, &report)?;
+    w.flush()?;
+    println!("📝 Report written to {}", args.report);
+
+    println!("\n🎉 Done in {:.2}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,418 @@
+//! `diff` subcommand: compare two versions of a dataset record by record.
+//!
+//! Usage:
+//!   ndjson_parallel diff --old FILE... --new FILE... [--key PATH[,PATH...]]
+//!       [--ids ID[,ID...]] [--ids-file FILE] [--samples N] [--report FILE]
+//!       [--schema FILE] [--partitions N] [--spill-dir DIR]
+//!
+//! Inputs are NDJSON/`.gz` or Parquet (see `record_source.rs`). Records are
+//! matched on `--key` (default `id`). Instead of whole records, each side
+//! spills one hash per top-level field to hash partitions on disk (see
+//! `partition_spill.rs`), so any dataset size can be compared.
+//!
+//! Reports added and removed records, changed records, and per field how many
+//! matched records changed, gained or lost it. Full before/after values are
+//! printed for the `--ids` records and for `--samples` changed records (the
+//! latter costs one more read of both sides). Within one side, only the first
+//! record of a repeated key is compared.
+
+use crate::dedup::KeySpec;
+use crate::partition_spill::PartitionSpill;
+use crate::record_source::open_lines;
+use crate::schema_config::SchemaConfig;
+use crate::CHUNK_SIZE;
+use rayon::prelude::*;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::Instant,
+};
+
+const OLD: usize = 0;
+const NEW: usize = 1;
+const SIDES: [&str; 2] = ["old", "new"];
+
+struct DiffArgs {
+    inputs: [Vec<String>; 2],
+    key: KeySpec,
+    ids: HashSet<String>,
+    samples: usize,
+    report: String,
+    schema: SchemaConfig,
+    partitions: usize,
+    spill_dir: PathBuf,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<DiffArgs, Box<dyn Error>> {
+    let mut inputs: [Vec<String>; 2] = [Vec::new(), Vec::new()];
+    let mut key = "id".to_string();
+    let mut ids = HashSet::new();
+    let mut samples = 0;
+    let mut report = "diff_report.json".to_string();
+    let mut schema = SchemaConfig::default();
+    let mut partitions = 64;
+    let mut spill_dir = std::env::temp_dir();
+
+    // `--old` and `--new` switch where the following file arguments go
+    let mut side: Option<usize> = None;
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--old" => {
+                inputs[OLD].push(value("--old")?);
+                side = Some(OLD);
+            }
+            "--new" => {
+                inputs[NEW].push(value("--new")?);
+                side = Some(NEW);
+            }
+            "--key" => key = value("--key")?,
+            "--ids" => ids.extend(value("--ids")?.split(',').map(|id| id.trim().to_string())),
+            "--ids-file" => {
+                let path = value("--ids-file")?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read ids {}: {}", path, e))?;
+                ids.extend(text.lines().map(str::trim).filter(|id| !id.is_empty()).map(str::to_string));
+            }
+            "--samples" => {
+                let n = value("--samples")?;
+                samples = n.parse().map_err(|_| format!("Invalid --samples {}", n))?;
+            }
+            "--report" => report = value("--report")?,
+            "--schema" => schema = SchemaConfig::load(&value("--schema")?)?,
+            "--partitions" => {
+                let n = value("--partitions")?;
+                partitions = n
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or(format!("Invalid --partitions {}", n))?;
+            }
+            "--spill-dir" => spill_dir = PathBuf::from(value("--spill-dir")?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => match side {
+                Some(side) => inputs[side].push(arg),
+                None => return Err(format!("{} must follow --old or --new", arg).into()),
+            },
+        }
+    }
+
+    if inputs.iter().any(Vec::is_empty) {
+        return Err("diff needs --old and --new".into());
+    }
+    ids.remove("");
+    Ok(DiffArgs {
+        inputs,
+        key: KeySpec::parse(&key, false)?,
+        ids,
+        samples,
+        report,
+        schema,
+        partitions,
+        spill_dir,
+    })
+}
+
+/// Hash of every top-level field; serde_json maps are key-sorted, so equal
+/// values always serialize (and hash) the same
+fn field_hashes(record: &Map<String, Value>) -> BTreeMap<String, u64> {
+    record
+        .iter()
+        .map(|(field, value)| {
+            let mut hasher = DefaultHasher::new();
+            value.to_string().hash(&mut hasher);
+            (field.clone(), hasher.finish())
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct SideCounts {
+    records: u64,
+    without_key: u64,
+    duplicate_keys: u64,
+    skipped_lines: u64,
+}
+
+/// Matched records that changed, gained or lost one field
+#[derive(Default, Clone, Copy)]
+struct FieldDiff {
+    changed: u64,
+    added: u64,
+    removed: u64,
+}
+
+impl FieldDiff {
+    fn total(&self) -> u64 {
+        self.changed + self.added + self.removed
+    }
+}
+
+/// Read every input of one side, spilling field hashes and keeping the
+/// records whose key is in `wanted`
+fn scan_side(
+    args: &DiffArgs,
+    side: usize,
+    spill: &mut Option<&mut PartitionSpill>,
+    wanted: &HashSet<String>,
+    found: &mut HashMap<String, [Option<Value>; 2]>,
+) -> Result<SideCounts, Box<dyn Error>> {
+    let mut counts = SideCounts::default();
+    let hash_fields = spill.is_some();
+
+    for input in &args.inputs[side] {
+        println!("📖 Reading {} {}", SIDES[side], input);
+        let mut lines = open_lines(input, &args.schema)?.peekable();
+        let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
+
+        while lines.peek().is_some() {
+            chunk.clear();
+            for line in lines.by_ref().take(CHUNK_SIZE) {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    chunk.push(line);
+                }
+            }
+
+            // None = unparseable; Some(None) = no key
+            type Keyed = Option<(String, BTreeMap<String, u64>, Option<Value>)>;
+            let keyed: Vec<Option<Keyed>> = chunk
+                .par_iter()
+                .map(|line| {
+                    let record = serde_json::from_str::<Value>(line).ok().filter(Value::is_object)?;
+                    let Some(key) = args.key.extract(&record) else {
+                        return Some(None);
+                    };
+                    let hashes = match record.as_object() {
+                        Some(obj) if hash_fields => field_hashes(obj),
+                        _ => BTreeMap::new(),
+                    };
+                    let keep = wanted.contains(&key).then_some(record);
+                    Some(Some((key, hashes, keep)))
+                })
+                .collect();
+
+            for entry in keyed {
+                match entry {
+                    None => counts.skipped_lines += 1,
+                    Some(None) => {
+                        counts.records += 1;
+                        counts.without_key += 1;
+                    }
+                    Some(Some((key, hashes, record))) => {
+                        counts.records += 1;
+                        if let Some(spill) = spill.as_mut() {
+                            spill.push(&key, &(side, &key, hashes))?;
+                        }
+                        if let Some(record) = record {
+                            let slot = &mut found.entry(key).or_default()[side];
+                            if slot.is_none() {
+                                *slot = Some(record);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(counts)
+}
+
+/// Field → (old, new) for every field that differs; missing is `null`
+fn record_diff(old: Option<&Value>, new: Option<&Value>) -> BTreeMap<String, (Value, Value)> {
+    let empty = Map::new();
+    let old = old.and_then(Value::as_object).unwrap_or(&empty);
+    let new = new.and_then(Value::as_object).unwrap_or(&empty);
+    old.keys()
+        .chain(new.keys())
+        .filter(|field| old.get(*field) != new.get(*field))
+        .map(|field| {
+            let get = |record: &Map<String, Value>| record.get(field).cloned().unwrap_or(Value::Null);
+            (field.clone(), (get(old), get(new)))
+        })
+        .collect()
+}
+
+fn shorten(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(120) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+pub fn run(argv: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(argv)?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Old files: {}, new files: {}", args.inputs[OLD].len(), args.inputs[NEW].len());
+    println!("🔑 Key: {}", args.key.label);
+    println!();
+
+    // Pass 1: field hashes of both sides, plus the records asked for by id
+    let mut spill = PartitionSpill::create(&args.spill_dir, "diff", args.partitions)?;
+    let mut found: HashMap<String, [Option<Value>; 2]> = HashMap::new();
+    let mut counts = Vec::new();
+    for side in [OLD, NEW] {
+        counts.push(scan_side(&args, side, &mut Some(&mut spill), &args.ids, &mut found)?);
+    }
+    let partitions = spill.finish()?;
+
+    // Pass 2: compare both sides key by key, one partition at a time
+    println!("\n🔎 Comparing {} partitions", partitions.count());
+    let mut added = 0u64;
+    let mut removed = 0u64;
+    let mut changed = 0u64;
+    let mut identical = 0u64;
+    let mut fields: HashMap<String, FieldDiff> = HashMap::new();
+    let mut sample_ids: Vec<String> = Vec::new();
+
+    for partition in 0..partitions.count() {
+        let mut by_key: HashMap<String, [Option<BTreeMap<String, u64>>; 2]> = HashMap::new();
+        for (side, key, hashes) in partitions.read::<(usize, String, BTreeMap<String, u64>)>(partition)? {
+            let slot = &mut by_key.entry(key).or_default()[side];
+            if slot.is_some() {
+                counts[side].duplicate_keys += 1;
+            } else {
+                *slot = Some(hashes);
+            }
+        }
+
+        for (key, [old, new]) in by_key {
+            let (old, new) = match (old, new) {
+                (Some(old), Some(new)) => (old, new),
+                (Some(_), None) => {
+                    removed += 1;
+                    continue;
+                }
+                _ => {
+                    added += 1;
+                    continue;
+                }
+            };
+
+            let mut differs = false;
+            for (field, hash) in &old {
+                match new.get(field) {
+                    Some(h) if h == hash => {}
+                    Some(_) => {
+                        fields.entry(field.clone()).or_default().changed += 1;
+                        differs = true;
+                    }
+                    None => {
+                        fields.entry(field.clone()).or_default().removed += 1;
+                        differs = true;
+                    }
+                }
+            }
+            for field in new.keys().filter(|f| !old.contains_key(*f)) {
+                fields.entry(field.clone()).or_default().added += 1;
+                differs = true;
+            }
+
+            if differs {
+                changed += 1;
+                if sample_ids.len() < args.samples && !args.ids.contains(&key) {
+                    sample_ids.push(key);
+                }
+            } else {
+                identical += 1;
+            }
+        }
+    }
+    drop(partitions);
+
+    // Pass 3, only for --samples: fetch the sampled records from both sides
+    if !sample_ids.is_empty() {
+        println!("\n📖 Fetching {} sample records", sample_ids.len());
+        let wanted: HashSet<String> = sample_ids.iter().cloned().collect();
+        for side in [OLD, NEW] {
+            scan_side(&args, side, &mut None, &wanted, &mut found)?;
+        }
+    }
+
+    println!("\n📊 Diff summary");
+    for side in [OLD, NEW] {
+        let c = &counts[side];
+        println!(
+            "   {}: {} records, {} without key, {} repeated keys, {} unparseable lines",
+            SIDES[side], c.records, c.without_key, c.duplicate_keys, c.skipped_lines
+        );
+    }
+    println!("   Added     : {}", added);
+    println!("   Removed   : {}", removed);
+    println!("   Changed   : {}", changed);
+    println!("   Identical : {}", identical);
+
+    let mut field_rows: Vec<(&String, &FieldDiff)> = fields.iter().collect();
+    field_rows.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(b.0)));
+    if !field_rows.is_empty() {
+        println!("\n{:>12} {:>12} {:>12}  field", "changed", "added", "removed");
+        for (field, d) in &field_rows {
+            println!("{:>12} {:>12} {:>12}  {}", d.changed, d.added, d.removed, field);
+        }
+    }
+
+    // Explicit ids first, in sorted order, then the samples
+    let mut shown: Vec<&String> = args.ids.iter().collect();
+    shown.sort();
+    shown.extend(&sample_ids);
+    let mut samples = Vec::new();
+    for id in shown {
+        let [old, new] = found.get(id).cloned().unwrap_or_default();
+        let status = match (&old, &new) {
+            (None, None) => "missing",
+            (Some(_), None) => "removed",
+            (None, Some(_)) => "added",
+            _ => "matched",
+        };
+        let diff = record_diff(old.as_ref(), new.as_ref());
+        println!("\n🔍 {} ({}, {} fields differ)", id, status, diff.len());
+        for (field, (before, after)) in &diff {
+            println!("   {}: {} → {}", field, shorten(before), shorten(after));
+        }
+        let changes: Map<String, Value> = diff
+            .into_iter()
+            .map(|(field, (before, after))| (field, json!({ "old": before, "new": after })))
+            .collect();
+        samples.push(json!({ "id": id, "status": status, "changes": changes }));
+    }
+
+    let side_json = |side: usize| {
+        let c = &counts[side];
+        json!({
+            "files": args.inputs[side],
+            "records": c.records,
+            "without_key": c.without_key,
+            "repeated_keys": c.duplicate_keys,
+            "unparseable_lines": c.skipped_lines,
+        })
+    };
+    let report = json!({
+        "key": args.key.label,
+        "old": side_json(OLD),
+        "new": side_json(NEW),
+        "added": added,
+        "removed": removed,
+        "changed": changed,
+        "identical": identical,
+        "fields": field_rows
+            .iter()
+            .map(|(field, d)| json!({ "field": field, "changed": d.changed, "added": d.added, "removed": d.removed }))
+            .collect::<Vec<_>>(),
+        "samples": samples,
+    });
+    let mut w = BufWriter::new(File::create(&args.report)?);
+    serde_json::to_writer_pretty(&mut w, &report)?;
+    w.flush()?;
+    println!("\n📝 Report written to {}", args.report);
+
+    println!("\n🎉 Done in {:.2}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
@@ -1,13 +1,33 @@
-use serde_json::Value;
-
-pub fn matches_email(v: &Value) -> bool {
-    if let Some(emails) = v.get("emails").and_then(|e| e.as_array()) {
-        return emails.iter().any(|e| {
-            e.get("address")
-                .and_then(|a| a.as_str())
-                .map(|addr| addr.ends_with("@google.com"))
-                .unwrap_or(false)
-        });
+//! Named filter presets for the `filter` subcommand.
+
+use crate::filter::Filter;
+use serde_json::json;
+
+/// People with at least one `@google.com` address in `emails`
+pub fn google_email_filter() -> Filter {
+    Filter::parse(&json!({ "path": "emails[].address", "ends_with": "@google.com" }))
+        .expect("google-email preset is a valid filter")
+}
+
+/// People with a usable phone: a non-blank `mobile_phone` string, or at least
+/// one non-blank string in `phone_numbers` (the old `app.js` extract)
+pub fn has_phone_filter() -> Filter {
+    Filter::parse(&json!({ "or": [
+        { "path": "mobile_phone", "type": "string", "non_empty": true },
+        { "and": [
+            { "path": "phone_numbers", "type": "array" },
+            { "any": "phone_numbers", "where": { "path": "", "type": "string", "non_empty": true } }
+        ] }
+    ] }))
+    .expect("has-phone preset is a valid filter")
+}
+
+pub fn preset(name: &str) -> Option<Filter> {
+    match name {
+        "google-email" => Some(google_email_filter()),
+        "has-phone" => Some(has_phone_filter()),
+        _ => None,
     }
-    false
 }
+
+pub const PRESETS: &[&str] = &["google-email", "has-phone"];
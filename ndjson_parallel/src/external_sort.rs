@@ -0,0 +1,263 @@
+//! External merge sort of NDJSON lines by keys the caller extracts.
+//!
+//! The caller builds each line's `SortKey` from the record as it will be written
+//! (after the transform stages and type coercion), so the order matches what the
+//! Parquet file's sorting columns claim. Lines are buffered until the memory budget is reached, then the buffer is
+//! sorted and spilled to a run file. Once the input is exhausted the runs are
+//! merged back with a k-way heap merge, so only one line per run is held in
+//! memory. Ties keep their input order.
+
+use rayon::prelude::*;
+use serde_json::Value;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Key values of one line, compared column by column
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey(pub Vec<Value>);
+
+impl Eq for SortKey {}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.0.iter().zip(&other.0) {
+            let ord = compare_values(a, b);
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// Nulls first, then booleans, numbers, strings, and anything else as JSON text
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) | Value::Object(_) => 4,
+        }
+    }
+
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Number(x), Value::Number(y)) => {
+            let x = x.as_f64().unwrap_or(f64::NAN);
+            let y = y.as_f64().unwrap_or(f64::NAN);
+            x.total_cmp(&y)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(_), Value::Array(_))
+        | (Value::Object(_), Value::Object(_))
+        | (Value::Array(_), Value::Object(_))
+        | (Value::Object(_), Value::Array(_)) => a.to_string().cmp(&b.to_string()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+pub struct ExternalSorter {
+    memory_limit: usize,
+    spill_dir: PathBuf,
+    buffer: Vec<(SortKey, String)>,
+    buffered_bytes: usize,
+    runs: Vec<PathBuf>,
+}
+
+impl ExternalSorter {
+    pub fn new(memory_limit: usize, spill_dir: PathBuf) -> Self {
+        ExternalSorter {
+            memory_limit,
+            spill_dir,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Add a chunk of lines with their keys
+    pub fn push_chunk(&mut self, keyed: Vec<(SortKey, String)>) -> io::Result<()> {
+        for (key, line) in keyed {
+            // Rough footprint: the line itself, its key, and per-entry overhead
+            self.buffered_bytes += line.len() * 2 + 64;
+            self.buffer.push((key, line));
+
+            if self.buffered_bytes >= self.memory_limit {
+                self.spill()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sort the buffer and write it out as a run of alternating key/line pairs
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.par_sort_by(|a, b| a.0.cmp(&b.0));
+
+        fs::create_dir_all(&self.spill_dir)?;
+        let path = self.spill_dir.join(format!(
+            "ndjson_sort_{}_{}.run",
+            std::process::id(),
+            self.runs.len()
+        ));
+        let mut writer = BufWriter::with_capacity(1024 * 1024, File::create(&path)?);
+        for (key, line) in self.buffer.drain(..) {
+            serde_json::to_writer(&mut writer, &key.0)?;
+            writer.write_all(b"\n")?;
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        self.runs.push(path);
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Number of runs written to disk so far
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Finish input and return the lines in key order
+    pub fn finish(mut self) -> io::Result<SortedLines> {
+        if self.runs.is_empty() {
+            self.buffer.par_sort_by(|a, b| a.0.cmp(&b.0));
+            let lines: Vec<String> = self.buffer.drain(..).map(|(_, line)| line).collect();
+            return Ok(SortedLines::Memory(lines.into_iter()));
+        }
+
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        let mut readers = Vec::with_capacity(self.runs.len());
+        let mut heap = BinaryHeap::with_capacity(self.runs.len());
+        for (idx, path) in self.runs.iter().enumerate() {
+            let mut reader = RunReader {
+                path: path.clone(),
+                inner: BufReader::with_capacity(256 * 1024, File::open(path)?),
+            };
+            if let Some((key, line)) = reader.next_entry()? {
+                heap.push(Reverse(HeapEntry { key, run: idx, line }));
+            }
+            readers.push(reader);
+        }
+        self.runs.clear();
+
+        Ok(SortedLines::Merge(MergeIter { readers, heap }))
+    }
+}
+
+impl Drop for ExternalSorter {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+struct RunReader {
+    path: PathBuf,
+    inner: BufReader<File>,
+}
+
+impl RunReader {
+    fn next_entry(&mut self) -> io::Result<Option<(SortKey, String)>> {
+        let mut key_line = String::new();
+        if self.inner.read_line(&mut key_line)? == 0 {
+            return Ok(None);
+        }
+        let mut line = String::new();
+        self.inner.read_line(&mut line)?;
+        if line.ends_with('\n') {
+            line.pop();
+        }
+
+        let key = serde_json::from_str(&key_line)?;
+        Ok(Some((SortKey(key), line)))
+    }
+}
+
+impl Drop for RunReader {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct HeapEntry {
+    key: SortKey,
+    run: usize,
+    line: String,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    // Earlier runs win ties so equal keys keep their input order
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then(self.run.cmp(&other.run))
+    }
+}
+
+pub struct MergeIter {
+    readers: Vec<RunReader>,
+    heap: BinaryHeap<Reverse<HeapEntry>>,
+}
+
+impl Iterator for MergeIter {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(entry) = self.heap.pop()?;
+        match self.readers[entry.run].next_entry() {
+            Ok(Some((key, line))) => self.heap.push(Reverse(HeapEntry {
+                key,
+                run: entry.run,
+                line,
+            })),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(entry.line))
+    }
+}
+
+/// Sorted output: straight from memory when nothing spilled, merged otherwise
+pub enum SortedLines {
+    Memory(std::vec::IntoIter<String>),
+    Merge(MergeIter),
+}
+
+impl Iterator for SortedLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SortedLines::Memory(lines) => lines.next().map(Ok),
+            SortedLines::Merge(merge) => merge.next(),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Record filter expressions over JSON paths.
+//!
+//! Filters are written as JSON. A leaf tests the values at a path; a path that
+//! crosses an array (`emails[].address`) matches when any of its values pass:
+//!
+//! ```json
+//! { "path": "emails[].address", "ends_with": "@google.com" }
+//! { "path": "job_title", "regex": "(?i)^(senior|staff) engineer" }
+//! { "path": "mobile_phone", "non_empty": true }
+//! { "path": "birth_year", "gte": 1980, "lt": 1990 }
+//! { "path": "gender", "in": ["male", "female"] }
+//! ```
+//!
+//! Leaf operators: `eq`, `ne`, `in`, `starts_with`, `ends_with`, `contains`,
+//! `regex`, `exists`, `non_empty`, `type` (`string`, `number`, `integer`,
+//! `boolean`, `array`, `object`, `null`), `gt`, `gte`, `lt`, `lte`. Several operators
+//! in one leaf must all pass on the same value, so on `emails[].address`
+//! `{"contains": "x", "ends_with": ".com"}` needs one address doing both. The
+//! negative ones (`ne`, `"exists": false`, `"non_empty": false`) hold when no
+//! value at the path matches. `"ignore_case": true` applies to the string ones.
+//!
+//! Combinators:
+//!
+//! ```json
+//! { "and": [ ... ] }
+//! { "or": [ ... ] }
+//! { "not": { ... } }
+//! { "any": "experience", "where": { "path": "company.name", "eq": "google" } }
+//! { "all": "emails", "where": { "path": "type", "eq": "professional" } }
+//! ```
+//!
+//! `any`/`all` evaluate `where` against each array element, with paths relative
+//! to the element. `all` over a missing or empty array is true.
+
+use crate::json_path::JsonPath;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy)]
+pub enum StrOp {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CmpOp {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// A single check applied to each value a leaf path resolves to
+#[derive(Debug, Clone)]
+pub enum Test {
+    Eq(Value, bool),
+    In(Vec<Value>, bool),
+    Str(StrOp, String, bool),
+    Regex(Regex),
+    Exists,
+    NonEmpty,
+    Type(String),
+    Cmp(CmpOp, f64),
+}
+
+#[derive(Debug, Clone)]
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Any(JsonPath, Box<Filter>),
+    All(JsonPath, Box<Filter>),
+    /// Passes when one value at the path passes every test
+    Leaf(JsonPath, Vec<Test>),
+}
+
+impl Filter {
+    /// Compile a filter from its JSON form
+    pub fn parse(expr: &Value) -> Result<Filter, String> {
+        let obj = expr
+            .as_object()
+            .ok_or_else(|| format!("Filter must be an object, got {}", expr))?;
+
+        if let Some(items) = obj.get("and") {
+            return Ok(Filter::And(parse_list("and", items)?));
+        }
+        if let Some(items) = obj.get("or") {
+            return Ok(Filter::Or(parse_list("or", items)?));
+        }
+        if let Some(inner) = obj.get("not") {
+            return Ok(Filter::Not(Box::new(Filter::parse(inner)?)));
+        }
+        for (key, quantifier) in [("any", true), ("all", false)] {
+            if let Some(path) = obj.get(key) {
+                let path = path
+                    .as_str()
+                    .ok_or_else(|| format!("\"{}\" takes a path string", key))?;
+                let cond = obj
+                    .get("where")
+                    .ok_or_else(|| format!("\"{}\" needs a \"where\" filter", key))?;
+                let path = JsonPath::parse(path)?;
+                let cond = Box::new(Filter::parse(cond)?);
+                return Ok(if quantifier {
+                    Filter::Any(path, cond)
+                } else {
+                    Filter::All(path, cond)
+                });
+            }
+        }
+
+        parse_leaf(obj)
+    }
+
+    pub fn matches(&self, record: &Value) -> bool {
+        match self {
+            Filter::And(items) => items.iter().all(|f| f.matches(record)),
+            Filter::Or(items) => items.iter().any(|f| f.matches(record)),
+            Filter::Not(inner) => !inner.matches(record),
+            Filter::Any(path, cond) => elements(path, record).any(|e| cond.matches(e)),
+            Filter::All(path, cond) => elements(path, record).all(|e| cond.matches(e)),
+            Filter::Leaf(path, tests) => path.resolve(record).iter().any(|v| tests.iter().all(|t| t.passes(v))),
+        }
+    }
+}
+
+fn parse_list(key: &str, items: &Value) -> Result<Vec<Filter>, String> {
+    let items = items
+        .as_array()
+        .ok_or_else(|| format!("\"{}\" takes a list of filters", key))?;
+    if items.is_empty() {
+        return Err(format!("\"{}\" needs at least one filter", key));
+    }
+    items.iter().map(Filter::parse).collect()
+}
+
+fn parse_leaf(obj: &Map<String, Value>) -> Result<Filter, String> {
+    let path = obj
+        .get("path")
+        .and_then(|p| p.as_str())
+        .ok_or_else(|| format!("Filter {} has no \"path\" or combinator", Value::Object(obj.clone())))?;
+    let path = JsonPath::parse(path)?;
+    let ignore_case = obj.get("ignore_case").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    // Positive tests run together on each value; negated ones are per path
+    let mut tests = Vec::new();
+    let mut negated = Vec::new();
+    for (op, arg) in obj {
+        let test = match op.as_str() {
+            "path" | "ignore_case" => continue,
+            "eq" => Test::Eq(arg.clone(), ignore_case),
+            // No value at the path may equal the operand
+            "ne" => {
+                let leaf = Filter::Leaf(path.clone(), vec![Test::Eq(arg.clone(), ignore_case)]);
+                negated.push(Filter::Not(Box::new(leaf)));
+                continue;
+            }
+            "in" => {
+                let options = arg.as_array().ok_or("\"in\" takes a list of values")?;
+                Test::In(options.clone(), ignore_case)
+            }
+            "starts_with" | "ends_with" | "contains" => {
+                let needle = arg
+                    .as_str()
+                    .ok_or_else(|| format!("\"{}\" takes a string", op))?;
+                let str_op = match op.as_str() {
+                    "starts_with" => StrOp::StartsWith,
+                    "ends_with" => StrOp::EndsWith,
+                    _ => StrOp::Contains,
+                };
+                let needle = if ignore_case { needle.to_lowercase() } else { needle.to_string() };
+                Test::Str(str_op, needle, ignore_case)
+            }
+            "regex" => {
+                let pattern = arg.as_str().ok_or("\"regex\" takes a string")?;
+                let pattern = if ignore_case { format!("(?i){}", pattern) } else { pattern.to_string() };
+                let re = Regex::new(&pattern).map_err(|e| format!("Invalid regex: {}", e))?;
+                Test::Regex(re)
+            }
+            "exists" | "non_empty" => {
+                let wanted = arg
+                    .as_bool()
+                    .ok_or_else(|| format!("\"{}\" takes true or false", op))?;
+                let test = if op == "exists" { Test::Exists } else { Test::NonEmpty };
+                if !wanted {
+                    negated.push(Filter::Not(Box::new(Filter::Leaf(path.clone(), vec![test]))));
+                    continue;
+                }
+                test
+            }
+            "type" => {
+                let name = arg.as_str().ok_or("\"type\" takes a type name")?;
+                if !TYPE_NAMES.contains(&name) {
+                    return Err(format!("Unknown type {:?} (expected one of {})", name, TYPE_NAMES.join(", ")));
+                }
+                Test::Type(name.to_string())
+            }
+            "gt" | "gte" | "lt" | "lte" => {
+                let bound = arg
+                    .as_f64()
+                    .ok_or_else(|| format!("\"{}\" takes a number", op))?;
+                let cmp = match op.as_str() {
+                    "gt" => CmpOp::Gt,
+                    "gte" => CmpOp::Gte,
+                    "lt" => CmpOp::Lt,
+                    _ => CmpOp::Lte,
+                };
+                Test::Cmp(cmp, bound)
+            }
+            other => return Err(format!("Unknown filter operator {:?}", other)),
+        };
+        tests.push(test);
+    }
+
+    let mut filters = negated;
+    if !tests.is_empty() {
+        filters.insert(0, Filter::Leaf(path.clone(), tests));
+    }
+    match filters.len() {
+        0 => Err(format!("Filter on {:?} has no operator", path.as_str())),
+        1 => Ok(filters.remove(0)),
+        _ => Ok(Filter::And(filters)),
+    }
+}
+
+const TYPE_NAMES: &[&str] = &["string", "number", "integer", "boolean", "array", "object", "null"];
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => value.is_null(),
+    }
+}
+
+/// Elements for `any`/`all`: values at the path, with arrays expanded one level
+fn elements<'a>(path: &JsonPath, record: &'a Value) -> impl Iterator<Item = &'a Value> {
+    path.resolve(record).into_iter().flat_map(|v| match v {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        other => vec![other],
+    })
+}
+
+fn equals(value: &Value, expected: &Value, ignore_case: bool) -> bool {
+    match (value, expected) {
+        (Value::String(a), Value::String(b)) if ignore_case => a.to_lowercase() == b.to_lowercase(),
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => value == expected,
+    }
+}
+
+fn is_non_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(items) => items.iter().any(is_non_empty),
+        Value::Object(obj) => !obj.is_empty(),
+        _ => true,
+    }
+}
+
+impl Test {
+    fn passes(&self, value: &Value) -> bool {
+        match self {
+            Test::Eq(expected, ignore_case) => equals(value, expected, *ignore_case),
+            Test::In(options, ignore_case) => options.iter().any(|o| equals(value, o, *ignore_case)),
+            Test::Str(op, needle, ignore_case) => {
+                let Some(s) = value.as_str() else {
+                    return false;
+                };
+                let lowered;
+                let s = if *ignore_case {
+                    lowered = s.to_lowercase();
+                    lowered.as_str()
+                } else {
+                    s
+                };
+                match op {
+                    StrOp::StartsWith => s.starts_with(needle.as_str()),
+                    StrOp::EndsWith => s.ends_with(needle.as_str()),
+                    StrOp::Contains => s.contains(needle.as_str()),
+                }
+            }
+            Test::Regex(re) => value.as_str().is_some_and(|s| re.is_match(s)),
+            Test::Exists => !value.is_null(),
+            Test::NonEmpty => is_non_empty(value),
+            Test::Type(name) => has_type(value, name),
+            Test::Cmp(op, bound) => value.as_f64().is_some_and(|n| match op {
+                CmpOp::Gt => n > *bound,
+                CmpOp::Gte => n >= *bound,
+                CmpOp::Lt => n < *bound,
+                CmpOp::Lte => n <= *bound,
+            }),
+        }
+    }
+}
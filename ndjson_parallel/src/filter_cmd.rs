@@ -0,0 +1,208 @@
+//! `filter` subcommand: keep the records matching a filter expression.
+//!
+//! Usage:
+//!   ndjson_parallel filter (--where JSON | --where-file FILE | --preset NAME)
+//!       [--min-completeness X [--completeness-weights FILE]] --output OUT [INPUT ...]
+//!
+//! Matching lines from every input are written unchanged, in input order, to a
+//! single NDJSON output (gzip-compressed when `OUT` ends in `.gz`). See
+//! `filter.rs` for the expression syntax. `--min-completeness` also drops
+//! records scoring below `X` (scored as in null_analyser, see the
+//! `record_completeness` crate); it can be used on its own.
+
+use crate::email_match;
+use crate::filter::Filter;
+use crate::input_lines::read_lines;
+use crate::line_writer::LineWriter;
+use crate::{CHUNK_SIZE, DEFAULT_INPUT};
+use rayon::prelude::*;
+use record_completeness::CompletenessConfig;
+use serde_json::Value;
+use std::{error::Error, fs, time::Instant};
+
+struct FilterArgs {
+    filter: Option<Filter>,
+    min_completeness: Option<(f64, CompletenessConfig)>,
+    output: String,
+    inputs: Vec<String>,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<FilterArgs, Box<dyn Error>> {
+    let mut filter = None;
+    let mut min_completeness = None;
+    let mut weights = None;
+    let mut output = None;
+    let mut inputs = Vec::new();
+
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--where" => {
+                let text = value("--where")?;
+                let expr: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid --where JSON: {}", e))?;
+                filter = Some(Filter::parse(&expr)?);
+            }
+            "--where-file" => {
+                let path = value("--where-file")?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read filter {}: {}", path, e))?;
+                let expr: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid filter {}: {}", path, e))?;
+                filter = Some(Filter::parse(&expr).map_err(|e| format!("Invalid filter {}: {}", path, e))?);
+            }
+            "--preset" => {
+                let name = value("--preset")?;
+                filter = Some(email_match::preset(&name).ok_or(format!(
+                    "Unknown preset {} (available: {})",
+                    name,
+                    email_match::PRESETS.join(", ")
+                ))?);
+            }
+            "--min-completeness" => {
+                let x = value("--min-completeness")?;
+                min_completeness = Some(
+                    x.parse::<f64>()
+                        .ok()
+                        .filter(|x| (0.0..=1.0).contains(x))
+                        .ok_or(format!("Invalid --min-completeness {} (0 <= X <= 1)", x))?,
+                );
+            }
+            "--completeness-weights" => weights = Some(record_completeness::load_weights(&value("--completeness-weights")?)?),
+            "--output" => output = Some(value("--output")?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => inputs.push(arg),
+        }
+    }
+
+    if inputs.is_empty() {
+        inputs.push(DEFAULT_INPUT.to_string());
+    }
+    if filter.is_none() && min_completeness.is_none() {
+        return Err("filter needs --where, --where-file, --preset or --min-completeness".into());
+    }
+    if weights.is_some() && min_completeness.is_none() {
+        return Err("--completeness-weights needs --min-completeness".into());
+    }
+    Ok(FilterArgs {
+        filter,
+        min_completeness: min_completeness.map(|x| {
+            let config = CompletenessConfig {
+                weights: weights.unwrap_or_default(),
+                ..CompletenessConfig::default()
+            };
+            (x, config)
+        }),
+        output: output.ok_or("filter needs --output")?,
+        inputs,
+    })
+}
+
+/// Per-file counts
+struct FilterSummary {
+    total: usize,
+    matched: usize,
+    skipped_lines: usize,
+}
+
+impl FilterArgs {
+    fn matches(&self, record: &Value) -> bool {
+        self.filter.as_ref().is_none_or(|f| f.matches(record))
+            && self
+                .min_completeness
+                .as_ref()
+                .is_none_or(|(min, config)| completeness(config, record) >= *min)
+    }
+}
+
+/// Records with nothing to score, or that aren't objects, score 0
+fn completeness(config: &CompletenessConfig, record: &Value) -> f64 {
+    record.as_object().and_then(|obj| config.score(obj)).unwrap_or(0.0)
+}
+
+fn filter_file(input: &str, args: &FilterArgs, out: &mut LineWriter) -> Result<FilterSummary, Box<dyn Error>> {
+    let mut summary = FilterSummary {
+        total: 0,
+        matched: 0,
+        skipped_lines: 0,
+    };
+    let mut lines = read_lines(input)?.peekable();
+    let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
+
+    while lines.peek().is_some() {
+        chunk.clear();
+        for line in lines.by_ref().take(CHUNK_SIZE) {
+            let line = line?;
+            if !line.trim().is_empty() {
+                chunk.push(line);
+            }
+        }
+
+        // None = unparseable, Some(keep) otherwise; collected in input order
+        let verdicts: Vec<Option<bool>> = chunk
+            .par_iter()
+            .map(|line| serde_json::from_str::<Value>(line).ok().map(|v| args.matches(&v)))
+            .collect();
+
+        for (line, verdict) in chunk.iter().zip(&verdicts) {
+            match verdict {
+                Some(true) => {
+                    out.write_line(line)?;
+                    summary.matched += 1;
+                    summary.total += 1;
+                }
+                Some(false) => summary.total += 1,
+                None => summary.skipped_lines += 1,
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(args)?;
+    let mut out = LineWriter::create(&args.output)?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Input files: {}", args.inputs.len());
+    println!("📁 Output: {}", args.output);
+    println!();
+
+    let mut total = 0usize;
+    let mut matched = 0usize;
+    let mut failed = 0usize;
+
+    for input in &args.inputs {
+        println!("📖 Filtering {}", input);
+        match filter_file(input, &args, &mut out) {
+            Ok(summary) => {
+                total += summary.total;
+                matched += summary.matched;
+                println!(
+                    "✅ {} of {} records matched, {} unparseable lines",
+                    summary.matched, summary.total, summary.skipped_lines
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("❌ Failed to filter {}: {}", input, e);
+            }
+        }
+    }
+
+    out.finish()?;
+
+    let secs = start.elapsed().as_secs_f64();
+    println!(
+        "\n🎉 {} of {} records matched in {:.2}s ({:.0} records/sec)",
+        matched,
+        total,
+        secs,
+        total as f64 / secs
+    );
+
+    if failed > 0 {
+        return Err(format!("{} of {} files failed", failed, args.inputs.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! NDJSON input lines for every subcommand, read through
+//! `record_transform::line_reader`: BOMs and CRLF endings are stripped, and
+//! lines with invalid UTF-8 or over `DEFAULT_MAX_LINE_BYTES` are skipped
+//! instead of aborting the file. What was skipped is printed per input once
+//! it is exhausted; a read error still fails the file.
+//!
+//! `validate` reads through `checked_lines` instead, which hands those lines
+//! over with their line numbers so they count as failures.
+
+use crate::line_writer::LineWriter;
+use crate::open_input;
+use record_transform::line_reader::{Line, LineOptions, LineReader, Utf8Mode};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+pub struct InputLines {
+    path: String,
+    reader: LineReader<Box<dyn BufRead + Send>>,
+    done: bool,
+}
+
+pub fn read_lines(path: &str) -> io::Result<InputLines> {
+    Ok(InputLines {
+        path: path.to_string(),
+        reader: LineReader::new(open_input(path)?, LineOptions::default()),
+        done: false,
+    })
+}
+
+impl Iterator for InputLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match self.reader.next_line() {
+                Ok(Some(Line::Text(text))) => return Some(Ok(text.to_string())),
+                // Only in passthrough mode, which isn't used here
+                Ok(Some(Line::Bytes(_))) => continue,
+                Ok(None) => {
+                    self.done = true;
+                    for event in self.reader.counts().describe() {
+                        println!("   🧾 {}: {}", self.path, event);
+                    }
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// One physical line as `checked_lines` sees it
+pub enum CheckedLine {
+    Text(String),
+    /// Invalid UTF-8, as read
+    InvalidUtf8(Vec<u8>),
+    /// Over `DEFAULT_MAX_LINE_BYTES`; never buffered, so the reader has already
+    /// streamed it to the quarantine, if there is one
+    Oversized,
+}
+
+/// Every non-empty physical line with its 1-based line number, including the
+/// ones `read_lines` would skip
+pub struct CheckedLines {
+    reader: LineReader<Box<dyn BufRead + Send>, LineWriter>,
+    oversized_seen: u64,
+    pending: VecDeque<(u64, CheckedLine)>,
+}
+
+/// Open `path`, then take `quarantine` for over-long lines; give it back with
+/// `into_quarantine` once the file is done
+pub fn checked_lines(path: &str, quarantine: &mut Option<LineWriter>) -> io::Result<CheckedLines> {
+    let options = LineOptions {
+        utf8: Utf8Mode::Passthrough,
+        ..LineOptions::default()
+    };
+    let input = open_input(path)?;
+    Ok(CheckedLines {
+        reader: LineReader::with_quarantine(input, options, quarantine.take()),
+        oversized_seen: 0,
+        pending: VecDeque::new(),
+    })
+}
+
+impl CheckedLines {
+    pub fn quarantine_mut(&mut self) -> Option<&mut LineWriter> {
+        self.reader.quarantine_mut()
+    }
+
+    pub fn into_quarantine(self) -> Option<LineWriter> {
+        self.reader.into_quarantine()
+    }
+}
+
+impl Iterator for CheckedLines {
+    type Item = io::Result<(u64, CheckedLine)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entry) = self.pending.pop_front() {
+            return Some(Ok(entry));
+        }
+        let line = match self.reader.next_line() {
+            Ok(Some(Line::Text(text))) => Some(CheckedLine::Text(text.to_string())),
+            Ok(Some(Line::Bytes(bytes))) => Some(CheckedLine::InvalidUtf8(bytes.to_vec())),
+            Ok(None) => None,
+            Err(e) => return Some(Err(e)),
+        };
+
+        // Over-long lines skipped on the way are the ones right before the line returned
+        let counts = self.reader.counts();
+        let skipped = counts.oversized - self.oversized_seen;
+        self.oversized_seen = counts.oversized;
+        let last_skipped = counts.lines - u64::from(line.is_some());
+        for line_no in (last_skipped + 1 - skipped)..=last_skipped {
+            self.pending.push_back((line_no, CheckedLine::Oversized));
+        }
+        if let Some(line) = line {
+            self.pending.push_back((counts.lines, line));
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
@@ -0,0 +1,110 @@
+//! Dotted JSON paths with array steps.
+//!
+//! - `version_status.status` walks nested objects
+//! - `emails[].address` (or `emails[*].address`) fans out over every array element
+//! - `emails[0].address` picks a single element
+//!
+//! A path can resolve to several values once it crosses an array; missing keys
+//! and out-of-range indexes simply resolve to nothing.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Each,
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    text: String,
+    steps: Vec<Step>,
+}
+
+impl JsonPath {
+    /// Parse a path; an empty path (or `.`) refers to the value itself
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut steps = Vec::new();
+        let trimmed = text.trim();
+
+        if !trimmed.is_empty() && trimmed != "." {
+            for segment in trimmed.split('.') {
+                let (key, mut rest) = match segment.find('[') {
+                    Some(idx) => (&segment[..idx], &segment[idx..]),
+                    None => (segment, ""),
+                };
+                if !key.is_empty() {
+                    steps.push(Step::Key(key.to_string()));
+                } else if rest.is_empty() {
+                    return Err(format!("Empty segment in path {:?}", text));
+                }
+
+                while !rest.is_empty() {
+                    let close = rest
+                        .find(']')
+                        .ok_or_else(|| format!("Unclosed [ in path {:?}", text))?;
+                    let inner = rest[1..close].trim();
+                    if inner.is_empty() || inner == "*" {
+                        steps.push(Step::Each);
+                    } else {
+                        let idx = inner
+                            .parse()
+                            .map_err(|_| format!("Invalid array index {:?} in path {:?}", inner, text))?;
+                        steps.push(Step::Index(idx));
+                    }
+                    rest = &rest[close + 1..];
+                    if !rest.is_empty() && !rest.starts_with('[') {
+                        return Err(format!("Unexpected {:?} after ] in path {:?}", rest, text));
+                    }
+                }
+            }
+        }
+
+        Ok(JsonPath {
+            text: text.to_string(),
+            steps,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// True when the path fans out over arrays and may yield several values
+    pub fn is_multi(&self) -> bool {
+        self.steps.contains(&Step::Each)
+    }
+
+    /// Every value the path points at, in document order
+    pub fn resolve<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in &self.steps {
+            let mut next = Vec::with_capacity(current.len());
+            for value in current {
+                match step {
+                    Step::Key(key) => {
+                        if let Some(child) = value.get(key) {
+                            next.push(child);
+                        }
+                    }
+                    Step::Index(idx) => {
+                        if let Some(child) = value.get(*idx) {
+                            next.push(child);
+                        }
+                    }
+                    Step::Each => {
+                        if let Value::Array(items) = value {
+                            next.extend(items.iter());
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                return next;
+            }
+            current = next;
+        }
+        current
+    }
+}
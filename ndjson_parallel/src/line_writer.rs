@@ -0,0 +1,84 @@
+//! NDJSON line output, gzip-compressed when the path ends in `.gz`, or
+//! converted to Parquet (see `parquet_output.rs`).
+
+use crate::parquet_output::ParquetOutput;
+use crate::schema_config::SchemaConfig;
+use crate::writer_config::WriterConfig;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+pub enum LineWriter {
+    Gz(GzEncoder<BufWriter<File>>),
+    Plain(BufWriter<File>),
+    Parquet(Box<ParquetOutput>),
+}
+
+impl LineWriter {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let file = BufWriter::with_capacity(8 * 1024 * 1024, File::create(path)?);
+        if path.ends_with(".gz") {
+            Ok(LineWriter::Gz(GzEncoder::new(file, Compression::default())))
+        } else {
+            Ok(LineWriter::Plain(file))
+        }
+    }
+
+    /// Lines converted to Parquet with the schema and writer configs
+    pub fn parquet(
+        path: &str,
+        schema_config: &SchemaConfig,
+        writer_config: &WriterConfig,
+    ) -> Result<Self, Box<dyn Error>> {
+        let output = ParquetOutput::create(path, schema_config, writer_config)?;
+        Ok(LineWriter::Parquet(Box::new(output)))
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let out: &mut dyn Write = match self {
+            LineWriter::Gz(w) => w,
+            LineWriter::Plain(w) => w,
+            LineWriter::Parquet(p) => return p.write_line(line),
+        };
+        out.write_all(line.as_bytes())?;
+        out.write_all(b"\n")
+    }
+
+    /// Flush everything, including the gzip trailer
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            LineWriter::Gz(w) => w.finish()?.flush(),
+            LineWriter::Plain(mut w) => w.flush(),
+            LineWriter::Parquet(p) => p.finish(),
+        }
+    }
+
+    /// Values written as NULL because they didn't fit their Parquet column type
+    pub fn type_mismatches(&self) -> u64 {
+        match self {
+            LineWriter::Parquet(p) => p.type_mismatches(),
+            _ => 0,
+        }
+    }
+}
+
+/// Raw bytes, for quarantining lines that aren't valid UTF-8; Parquet output only takes whole lines
+impl Write for LineWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            LineWriter::Gz(w) => w.write(buf),
+            LineWriter::Plain(w) => w.write(buf),
+            LineWriter::Parquet(_) => Err(io::Error::other("Parquet output only takes NDJSON lines")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            LineWriter::Gz(w) => w.flush(),
+            LineWriter::Plain(w) => w.flush(),
+            LineWriter::Parquet(_) => Ok(()),
+        }
+    }
+}
@@ -1,184 +1,443 @@
-// Add to Cargo.toml:
-// arrow = "53"
-// parquet = "53"
+//! NDJSON → Parquet converter built on arrow-rs, no DuckDB required.
+//!
+//! Usage:
+//!   ndjson_parallel [--writer-config FILE] [--schema FILE] [--normalise FILE] [--transform FILE]
+//!                   [--pii FILE] [--output-dir DIR] [INPUT ...]
+//!   ndjson_parallel filter ...    (see `filter_cmd.rs`)
+//!   ndjson_parallel count ...     (see `count_cmd.rs`)
+//!   ndjson_parallel dedup ...     (see `dedup_cmd.rs`)
+//!   ndjson_parallel merge ...     (see `merge_cmd.rs`)
+//!   ndjson_parallel diff ...      (see `diff_cmd.rs`)
+//!   ndjson_parallel project ...   (see `project_cmd.rs`)
+//!   ndjson_parallel validate ...  (see `validate_cmd.rs`)
+//!
+//! Each input (`.gz` or plain NDJSON) becomes `<output-dir>/<name>.parquet`.
+//! Lines are read in chunks of `CHUNK_SIZE`, parsed in parallel, converted into
+//! typed Arrow columns per the schema config and written as one record batch,
+//! so memory stays bounded regardless of file size. Every subcommand reads
+//! NDJSON through `input_lines.rs`, which skips lines with invalid UTF-8. With `--normalise`,
+//! `--transform` and `--pii`, records go through those `record_transform`
+//! stages before they are converted.
 
-use arrow::array::{ArrayRef, StringArray, RecordBatch};
-use arrow::datatypes::{DataType, Field, Schema};
+mod column_builder;
+mod count_cmd;
+mod dedup;
+mod dedup_cmd;
+mod diff_cmd;
+mod email_match;
+mod external_sort;
+mod filter;
+mod filter_cmd;
+mod input_lines;
+mod json_path;
+mod line_writer;
+mod merge_cmd;
+mod parquet_output;
+mod partition_spill;
+mod position_runs;
+mod project_cmd;
+mod record_source;
+mod schema_config;
+mod validate_cmd;
+mod validation;
+mod verify;
+mod writer_config;
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use column_builder::ColumnBuilder;
+use external_sort::{ExternalSorter, SortKey, SortedLines};
 use flate2::read::GzDecoder;
 use parquet::arrow::ArrowWriter;
-use parquet::file::properties::WriterProperties;
 use rayon::prelude::*;
-use serde_json::Value;
+use record_transform::stages::{StageCounts, Stages};
+use schema_config::SchemaConfig;
+use serde_json::{Map, Value};
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    error::Error,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::Path,
     sync::Arc,
     time::Instant,
 };
+use verify::RowCounts;
+use writer_config::WriterConfig;
 
 const CHUNK_SIZE: usize = 50_000;
+const DEFAULT_INPUT: &str = "/media/tamil-07/New Volume1/torrents/gz/part-00001.gz";
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input = "/media/tamil-07/New Volume1/torrents/gz/part-00001.gz";
-    let output = "part-00001.parquet";
+struct Args {
+    writer_config: Option<String>,
+    schema: Option<String>,
+    normalise: Option<String>,
+    transform: Option<String>,
+    pii: Option<String>,
+    output_dir: String,
+    inputs: Vec<String>,
+}
 
-    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
-    println!("📖 Reading and parsing...\n");
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args {
+        writer_config: None,
+        schema: None,
+        normalise: None,
+        transform: None,
+        pii: None,
+        output_dir: ".".to_string(),
+        inputs: Vec::new(),
+    };
 
-    let start = Instant::now();
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--writer-config" => args.writer_config = Some(value("--writer-config")?),
+            "--schema" => args.schema = Some(value("--schema")?),
+            "--normalise" => args.normalise = Some(value("--normalise")?),
+            "--transform" => args.transform = Some(value("--transform")?),
+            "--pii" => args.pii = Some(value("--pii")?),
+            "--output-dir" => args.output_dir = value("--output-dir")?,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            _ => args.inputs.push(arg),
+        }
+    }
+
+    if args.inputs.is_empty() {
+        args.inputs.push(DEFAULT_INPUT.to_string());
+    }
+    Ok(args)
+}
+
+/// Outcome of converting a single input file
+struct FileSummary {
+    rows: usize,
+    skipped_lines: usize,
+    type_mismatches: u64,
+    stage_counts: StageCounts,
+    duration_secs: f64,
+}
+
+fn open_input(path: &str) -> io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    if path.ends_with(".gz") {
+        Ok(Box::new(BufReader::with_capacity(16 * 1024 * 1024, GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::with_capacity(16 * 1024 * 1024, file)))
+    }
+}
+
+/// `part-00001.gz` / `part-00001.ndjson.gz` → `<output_dir>/part-00001.parquet`
+fn output_path(input: &str, output_dir: &str) -> String {
+    let mut name = Path::new(input)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    for ext in [".gz", ".ndjson", ".jsonl", ".json"] {
+        if let Some(stripped) = name.strip_suffix(ext) {
+            name = stripped.to_string();
+        }
+    }
+    format!("{}/{}.parquet", output_dir, name)
+}
 
-    // Read entire file into memory (300MB is fine)
-    let file = File::open(input)?;
-    let decoder = GzDecoder::new(file);
-    let reader = BufReader::with_capacity(16 * 1024 * 1024, decoder);
-    
-    let lines: Vec<String> = reader.lines().flatten().collect();
-    println!("✅ Read {} lines in {:.2}s", lines.len(), start.elapsed().as_secs_f64());
-
-    // Parse in parallel using all cores
-    let parse_start = Instant::now();
-    let records: Vec<_> = lines
-        .par_chunks(CHUNK_SIZE)
-        .flat_map(|chunk| {
-            chunk.par_iter().filter_map(|line| parse_json(line))
+/// Parse a chunk of lines in parallel and run the transform stages, when any
+/// are enabled, over the records, counting into `stage_counts`.
+/// Returns the records and the number of unparseable lines.
+fn parse_chunk(
+    stages: &Stages,
+    lines: &[String],
+    stage_counts: &mut StageCounts,
+) -> (Vec<Map<String, Value>>, usize) {
+    let parsed: Vec<Option<Map<String, Value>>> = lines
+        .par_iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skipped = parsed.iter().filter(|r| r.is_none()).count();
+    let mut records: Vec<Map<String, Value>> = parsed.into_iter().flatten().collect();
+
+    if !stages.is_empty() {
+        let counts = records
+            .par_iter_mut()
+            .fold(StageCounts::default, |mut counts, record| {
+                stages.apply(record, &mut counts);
+                counts
+            })
+            .reduce(StageCounts::default, |mut a, b| {
+                a.merge(&b);
+                a
+            });
+        stage_counts.merge(&counts);
+    }
+    (records, skipped)
+}
+
+/// Build one typed record batch from parsed records.
+/// Returns the batch, the accepted rows and the NULLs appended per column while
+/// building it, and the number of type mismatches.
+fn build_batch(
+    schema_config: &SchemaConfig,
+    schema: &Arc<Schema>,
+    records: &[Map<String, Value>],
+) -> Result<(RecordBatch, RowCounts, u64), Box<dyn Error>> {
+    // Columns are independent, so each one is built on its own rayon task
+    let built: Vec<_> = schema_config
+        .columns
+        .par_iter()
+        .map(|column| {
+            let mut builder = ColumnBuilder::new(&column.column_type, records.len());
+            let mut mismatches = 0;
+            let mut nulls = 0;
+            for record in records {
+                let appended = builder.append(record.get(&column.name));
+                mismatches += appended.mismatches;
+                nulls += usize::from(appended.null);
+            }
+            (builder.finish(), nulls, mismatches)
         })
         .collect();
 
-    println!("✅ Parsed {} records in {:.2}s", records.len(), parse_start.elapsed().as_secs_f64());
-
-    // Define schema
-    let schema = Arc::new(Schema::new(vec![
-        Field::new("id", DataType::Utf8, true),
-        Field::new("full_name", DataType::Utf8, true),
-        Field::new("gender", DataType::Utf8, true),
-        Field::new("job_title", DataType::Utf8, true),
-        Field::new("location_country", DataType::Utf8, true),
-        Field::new("location_region", DataType::Utf8, true),
-        Field::new("location_continent", DataType::Utf8, true),
-        Field::new("job_last_updated", DataType::Utf8, true),
-        Field::new("experience", DataType::Utf8, true),
-        Field::new("education", DataType::Utf8, true),
-        Field::new("profiles", DataType::Utf8, true),
-        Field::new("version_status", DataType::Utf8, true),
-    ]));
-
-    // Write to Parquet
-    let write_start = Instant::now();
-    let file = File::create(output)?;
-    let props = WriterProperties::builder()
-        .set_compression(parquet::basic::Compression::ZSTD(
-            parquet::basic::ZstdLevel::try_new(3)?,
-        ))
-        .build();
+    let parsed = RowCounts {
+        rows: records.len(),
+        nulls: built.iter().map(|(_, nulls, _)| *nulls).collect(),
+    };
+    let mismatches = built.iter().map(|(_, _, m)| m).sum();
+    let arrays = built.into_iter().map(|(array, _, _)| array).collect();
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+    Ok((batch, parsed, mismatches))
+}
+
+/// Parse and transform the whole input and externally sort the resulting records by
+/// `sort_by`. Keys are the values as their typed columns store them, so the
+/// order matches the sorting columns recorded in the file metadata (a value
+/// that doesn't fit its column sorts as NULL). Returns the transformed records
+/// as JSON lines in key order.
+fn sort_records(
+    input: &str,
+    schema_config: &SchemaConfig,
+    writer_config: &WriterConfig,
+    stages: &Stages,
+    skipped_lines: &mut usize,
+    stage_counts: &mut StageCounts,
+) -> Result<SortedLines, Box<dyn Error>> {
+    let key_columns = writer_config
+        .sort_by
+        .iter()
+        .map(|name| {
+            schema_config
+                .columns
+                .iter()
+                .find(|c| c.name == *name)
+                .map(|c| (name.as_str(), &c.column_type))
+                .ok_or(format!("sort_by column {} is not in the schema", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let start = Instant::now();
+    let mut sorter = ExternalSorter::new(writer_config.sort_memory_mb * 1024 * 1024, writer_config.spill_dir());
+    let mut lines = input_lines::read_lines(input)?.peekable();
+    while lines.peek().is_some() {
+        let chunk = lines.by_ref().take(CHUNK_SIZE).collect::<io::Result<Vec<_>>>()?;
+        let (records, skipped) = parse_chunk(stages, &chunk, stage_counts);
+        *skipped_lines += skipped;
+
+        let keyed = records
+            .into_par_iter()
+            .map(|record| {
+                let key = key_columns
+                    .iter()
+                    .map(|(name, column_type)| column_builder::stored_value(column_type, record.get(*name)))
+                    .collect();
+                Ok((SortKey(key), serde_json::to_string(&record)?))
+            })
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        sorter.push_chunk(keyed)?;
+    }
+    println!(
+        "   ✅ Sorted in {:.2}s ({} runs spilled to disk)",
+        start.elapsed().as_secs_f64(),
+        sorter.spilled_runs()
+    );
+    Ok(sorter.finish()?)
+}
+
+fn convert_file(
+    input: &str,
+    output: &str,
+    schema_config: &SchemaConfig,
+    writer_config: &WriterConfig,
+    stages: &Stages,
+) -> Result<FileSummary, Box<dyn Error>> {
+    let start = Instant::now();
+    let schema = schema_config.arrow_schema();
+    let props = writer_config.to_properties(&schema)?;
+
+    let mut skipped_lines = 0usize;
+    let mut type_mismatches = 0u64;
+    let mut stage_counts = StageCounts::default();
 
+    // Either stream lines straight through, or sort the transformed records first;
+    // sorted lines have already been through the stages
+    let sorted = !writer_config.sort_by.is_empty();
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = if sorted {
+        println!(
+            "   🔀 Sorting by {} (memory limit {} MB)...",
+            writer_config.sort_by.join(", "),
+            writer_config.sort_memory_mb
+        );
+        Box::new(sort_records(
+            input,
+            schema_config,
+            writer_config,
+            stages,
+            &mut skipped_lines,
+            &mut stage_counts,
+        )?)
+    } else {
+        Box::new(input_lines::read_lines(input)?)
+    };
+
+    // Parse and write one chunk at a time so memory stays bounded
+    let file = File::create(output)?;
     let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
 
-    // Write in batches
-    for batch_records in records.chunks(100_000) {
-        let batch = create_record_batch(&schema, batch_records)?;
+    let mut parsed = RowCounts::new(&schema);
+    let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
+    let mut lines = lines.peekable();
+
+    while lines.peek().is_some() {
+        chunk.clear();
+        for line in lines.by_ref().take(CHUNK_SIZE) {
+            chunk.push(line?);
+        }
+
+        let records = if sorted {
+            chunk
+                .par_iter()
+                .map(|line| serde_json::from_str(line))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            let (records, skipped) = parse_chunk(stages, &chunk, &mut stage_counts);
+            skipped_lines += skipped;
+            records
+        };
+
+        let (batch, chunk_counts, mismatches) = build_batch(schema_config, &schema, &records)?;
+        parsed.merge(&chunk_counts);
+        type_mismatches += mismatches;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+
         writer.write(&batch)?;
+
+        // Close the row group early once its encoded size hits the byte budget
+        if writer.in_progress_size() >= writer_config.max_row_group_bytes {
+            writer.flush()?;
+        }
+
+        println!(
+            "   📄 {} rows ({:.0} rows/sec)",
+            parsed.rows,
+            parsed.rows as f64 / start.elapsed().as_secs_f64()
+        );
     }
 
     writer.close()?;
-    println!("✅ Wrote Parquet in {:.2}s", write_start.elapsed().as_secs_f64());
 
-    let total = start.elapsed().as_secs_f64();
-    println!("\n🎉 Total time: {:.2}s ({:.0} rows/sec)", total, records.len() as f64 / total);
-
-    Ok(())
-}
+    // Reopen the written file and check it against what was parsed
+    if writer_config.verify {
+        verify::verify_parquet(output, &schema, &parsed)
+            .map_err(|e| format!("Verification failed for {}: {}", output, e))?;
+    }
 
-fn parse_json(line: &str) -> Option<ParsedRecord> {
-    let obj: serde_json::Map<String, Value> = serde_json::from_str(line).ok()?;
-    
-    Some(ParsedRecord {
-        id: obj.get("id").and_then(|v| v.as_str()).map(String::from),
-        full_name: obj.get("full_name").and_then(|v| v.as_str()).map(String::from),
-        gender: obj.get("gender").and_then(|v| v.as_str()).map(String::from),
-        job_title: obj.get("job_title").and_then(|v| v.as_str()).map(String::from),
-        location_country: obj.get("location_country").and_then(|v| v.as_str()).map(String::from),
-        location_region: obj.get("location_region").and_then(|v| v.as_str()).map(String::from),
-        location_continent: obj.get("location_continent").and_then(|v| v.as_str()).map(String::from),
-        job_last_updated: obj.get("job_last_updated").and_then(|v| v.as_str()).map(String::from),
-        experience: obj.get("experience").map(|v| v.to_string()),
-        education: obj.get("education").map(|v| v.to_string()),
-        profiles: obj.get("profiles").map(|v| v.to_string()),
-        version_status: obj.get("version_status").map(|v| v.to_string()),
+    Ok(FileSummary {
+        rows: parsed.rows,
+        skipped_lines,
+        type_mismatches,
+        stage_counts,
+        duration_secs: start.elapsed().as_secs_f64(),
     })
 }
 
-#[derive(Debug)]
-struct ParsedRecord {
-    id: Option<String>,
-    full_name: Option<String>,
-    gender: Option<String>,
-    job_title: Option<String>,
-    location_country: Option<String>,
-    location_region: Option<String>,
-    location_continent: Option<String>,
-    job_last_updated: Option<String>,
-    experience: Option<String>,
-    education: Option<String>,
-    profiles: Option<String>,
-    version_status: Option<String>,
-}
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut argv = std::env::args().skip(1).peekable();
+    match argv.peek().map(String::as_str) {
+        Some("filter") => return filter_cmd::run(argv.skip(1)),
+        Some("count") => return count_cmd::run(argv.skip(1)),
+        Some("dedup") => return dedup_cmd::run(argv.skip(1)),
+        Some("merge") => return merge_cmd::run(argv.skip(1)),
+        Some("diff") => return diff_cmd::run(argv.skip(1)),
+        Some("project") => return project_cmd::run(argv.skip(1)),
+        Some("validate") => return validate_cmd::run(argv.skip(1)),
+        _ => {}
+    }
 
-fn create_record_batch(
-    schema: &Arc<Schema>,
-    records: &[ParsedRecord],
-) -> Result<RecordBatch, Box<dyn std::error::Error>> {
-    let id: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.id.as_deref()).collect::<Vec<_>>()
-    ));
-    let full_name: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.full_name.as_deref()).collect::<Vec<_>>()
-    ));
-    let gender: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.gender.as_deref()).collect::<Vec<_>>()
-    ));
-    let job_title: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.job_title.as_deref()).collect::<Vec<_>>()
-    ));
-    let location_country: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.location_country.as_deref()).collect::<Vec<_>>()
-    ));
-    let location_region: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.location_region.as_deref()).collect::<Vec<_>>()
-    ));
-    let location_continent: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.location_continent.as_deref()).collect::<Vec<_>>()
-    ));
-    let job_last_updated: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.job_last_updated.as_deref()).collect::<Vec<_>>()
-    ));
-    let experience: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.experience.as_deref()).collect::<Vec<_>>()
-    ));
-    let education: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.education.as_deref()).collect::<Vec<_>>()
-    ));
-    let profiles: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.profiles.as_deref()).collect::<Vec<_>>()
-    ));
-    let version_status: ArrayRef = Arc::new(StringArray::from(
-        records.iter().map(|r| r.version_status.as_deref()).collect::<Vec<_>>()
-    ));
-
-    Ok(RecordBatch::try_new(
-        schema.clone(),
-        vec![
-            id,
-            full_name,
-            gender,
-            job_title,
-            location_country,
-            location_region,
-            location_continent,
-            job_last_updated,
-            experience,
-            education,
-            profiles,
-            version_status,
-        ],
-    )?)
-}
\ No newline at end of file
+    let total_start = Instant::now();
+    let args = parse_args(argv)?;
+
+    let writer_config = match &args.writer_config {
+        Some(path) => WriterConfig::load(path)?,
+        None => WriterConfig::default(),
+    };
+    let schema_config = match &args.schema {
+        Some(path) => SchemaConfig::load(path)?,
+        None => SchemaConfig::default(),
+    };
+    let stages = Stages::load(args.normalise.as_deref(), args.transform.as_deref(), args.pii.as_deref())?;
+    for name in writer_config.unknown_columns(&schema_config.arrow_schema()) {
+        eprintln!("⚠️  Writer config column {} is not in the schema, ignoring it", name);
+    }
+
+    fs::create_dir_all(&args.output_dir)?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Input files: {}", args.inputs.len());
+    println!("📁 Output directory: {}", args.output_dir);
+    println!("🧱 Columns: {}", schema_config.columns.len());
+    if !stages.is_empty() {
+        println!("🔒 Transforms: {}", stages.names().join(" → "));
+    }
+    println!();
+
+    let mut total_rows = 0usize;
+    let mut failed = 0usize;
+
+    for input in &args.inputs {
+        let output = output_path(input, &args.output_dir);
+        println!("📖 Converting {} → {}", input, output);
+
+        match convert_file(input, &output, &schema_config, &writer_config, &stages) {
+            Ok(summary) => {
+                total_rows += summary.rows;
+                println!(
+                    "✅ {} rows, {} unparseable lines, {} type mismatches written as NULL ({:.2}s)",
+                    summary.rows, summary.skipped_lines, summary.type_mismatches, summary.duration_secs
+                );
+                for line in stages.describe(&summary.stage_counts) {
+                    println!("   🔒 {}", line);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("❌ Failed to convert {}: {}", input, e);
+            }
+        }
+    }
+
+    let total = total_start.elapsed().as_secs_f64();
+    println!(
+        "\n🎉 {} files, {} rows in {:.2}s ({:.0} rows/sec)",
+        args.inputs.len() - failed,
+        total_rows,
+        total,
+        total_rows as f64 / total
+    );
+
+    if failed > 0 {
+        return Err(format!("{} of {} files failed", failed, args.inputs.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,459 @@
+//! `merge` subcommand: upsert a new delivery into an existing dataset.
+//!
+//! Usage:
+//!   ndjson_parallel merge --base FILE... --delta FILE... --output-dir DIR
+//!       [--key PATH] [--latest-by PATH] [--union FIELD[,FIELD...]] [--insert-unkeyed]
+//!       [--schema FILE] [--writer-config FILE] [--report FILE]
+//!
+//! Inputs are NDJSON/`.gz` or Parquet (`--schema` names the `json` columns,
+//! see `record_source.rs`). The delta is
+//! held in memory keyed by `--key` (default `id`) while each base file streams
+//! to `<DIR>/<name>.ndjson.gz` in its original order. Parquet base files are
+//! written back as `<DIR>/<name>.parquet` with the `--schema` columns and the
+//! `--writer-config` properties, so the output is a dataset in the same format.
+//! Delta records whose key is not in the base go to `<DIR>/inserted.ndjson.gz`
+//! (`inserted.parquet` when every base file is Parquet). Delta records without a
+//! key can't be matched; they go unchanged to `<DIR>/unkeyed.ndjson.gz`, or
+//! with `--insert-unkeyed` to the inserted records.
+//!
+//! Per record, the side with the later `--latest-by` value (default
+//! `job_last_updated`; ties go to the delta) wins field by field. Fields it
+//! lacks keep the other side's value, and `--union` arrays (default `emails`,
+//! `skills`, `phone_numbers`) combine both sides, newest elements first.
+
+use crate::external_sort::compare_values;
+use crate::json_path::JsonPath;
+use crate::line_writer::LineWriter;
+use crate::record_source::{is_parquet, open_lines};
+use crate::schema_config::SchemaConfig;
+use crate::writer_config::WriterConfig;
+use crate::CHUNK_SIZE;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+struct MergeArgs {
+    base: Vec<String>,
+    delta: Vec<String>,
+    output_dir: String,
+    key: JsonPath,
+    insert_unkeyed: bool,
+    rules: MergeRules,
+    schema: SchemaConfig,
+    writer_config: WriterConfig,
+    report: String,
+}
+
+struct MergeRules {
+    latest_by: JsonPath,
+    union: HashSet<String>,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<MergeArgs, Box<dyn Error>> {
+    let mut base = Vec::new();
+    let mut delta = Vec::new();
+    let mut output_dir = None;
+    let mut key = JsonPath::parse("id")?;
+    let mut latest_by = JsonPath::parse("job_last_updated")?;
+    let mut union: HashSet<String> = ["emails", "skills", "phone_numbers"].iter().map(|f| f.to_string()).collect();
+    let mut schema = SchemaConfig::default();
+    let mut writer_config = WriterConfig::default();
+    let mut report = "merge_report.json".to_string();
+    let mut insert_unkeyed = false;
+
+    // `--base` and `--delta` switch where the following file arguments go
+    let mut to_delta: Option<bool> = None;
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--base" => {
+                base.push(value("--base")?);
+                to_delta = Some(false);
+            }
+            "--delta" => {
+                delta.push(value("--delta")?);
+                to_delta = Some(true);
+            }
+            "--output-dir" => output_dir = Some(value("--output-dir")?),
+            "--key" => {
+                key = JsonPath::parse(&value("--key")?)?;
+                if key.is_multi() {
+                    return Err(format!("--key {} must be single-valued", key.as_str()).into());
+                }
+            }
+            "--latest-by" => latest_by = JsonPath::parse(&value("--latest-by")?)?,
+            "--union" => {
+                union = value("--union")?
+                    .split(',')
+                    .map(|f| f.trim().to_string())
+                    .filter(|f| !f.is_empty())
+                    .collect();
+            }
+            "--insert-unkeyed" => insert_unkeyed = true,
+            "--schema" => schema = SchemaConfig::load(&value("--schema")?)?,
+            "--writer-config" => writer_config = WriterConfig::load(&value("--writer-config")?)?,
+            "--report" => report = value("--report")?,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => match to_delta {
+                Some(false) => base.push(arg),
+                Some(true) => delta.push(arg),
+                None => return Err(format!("{} must follow --base or --delta", arg).into()),
+            },
+        }
+    }
+
+    if base.is_empty() || delta.is_empty() {
+        return Err("merge needs --base and --delta".into());
+    }
+    if !writer_config.sort_by.is_empty() {
+        return Err("merge keeps the base order, so the writer config can't set sort_by".into());
+    }
+    Ok(MergeArgs {
+        base,
+        delta,
+        output_dir: output_dir.ok_or("merge needs --output-dir")?,
+        key,
+        insert_unkeyed,
+        rules: MergeRules {
+            latest_by,
+            union,
+        },
+        schema,
+        writer_config,
+        report,
+    })
+}
+
+fn key_of(key: &JsonPath, record: &Value) -> Option<String> {
+    match key.resolve(record).into_iter().next()? {
+        Value::Null => None,
+        Value::String(s) if s.trim().is_empty() => None,
+        Value::String(s) => Some(s.trim().to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+impl MergeRules {
+    fn timestamp<'a>(&self, record: &'a Value) -> &'a Value {
+        self.latest_by.resolve(record).into_iter().next().unwrap_or(&Value::Null)
+    }
+
+    /// Merge `delta` into `base`; the later `latest_by` wins, ties go to the delta
+    fn merge(&self, base: &Value, delta: &Value) -> Value {
+        let delta_newer = compare_values(self.timestamp(delta), self.timestamp(base)) != Ordering::Less;
+        let (newer, older) = if delta_newer { (delta, base) } else { (base, delta) };
+        let (Some(newer), Some(older)) = (newer.as_object(), older.as_object()) else {
+            return delta.clone();
+        };
+
+        let mut merged = older.clone();
+        for (field, value) in newer {
+            match (value, older.get(field)) {
+                (Value::Array(new_items), Some(Value::Array(old_items))) if self.union.contains(field) => {
+                    let mut items = new_items.clone();
+                    for item in old_items {
+                        if !items.contains(item) {
+                            items.push(item.clone());
+                        }
+                    }
+                    merged.insert(field.clone(), Value::Array(items));
+                }
+                _ => {
+                    merged.insert(field.clone(), value.clone());
+                }
+            }
+        }
+        Value::Object(merged)
+    }
+}
+
+/// Delta records by key, duplicates within the delta already merged
+struct Delta {
+    records: HashMap<String, Value>,
+    /// Keys in the order they were first seen, so inserts are written reproducibly
+    order: Vec<String>,
+    total: u64,
+    without_key: u64,
+    skipped_lines: u64,
+}
+
+/// Load the delta; records without a key are written to `unkeyed` as they are read
+fn load_delta(args: &MergeArgs, unkeyed: &mut LineWriter) -> Result<Delta, Box<dyn Error>> {
+    let mut delta = Delta {
+        records: HashMap::new(),
+        order: Vec::new(),
+        total: 0,
+        without_key: 0,
+        skipped_lines: 0,
+    };
+
+    for input in &args.delta {
+        println!("📖 Loading delta {}", input);
+        for line in open_lines(input, &args.schema)? {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(record) = serde_json::from_str::<Value>(&line).ok().filter(Value::is_object) else {
+                delta.skipped_lines += 1;
+                continue;
+            };
+            delta.total += 1;
+            let Some(key) = key_of(&args.key, &record) else {
+                unkeyed.write_line(&line)?;
+                delta.without_key += 1;
+                continue;
+            };
+            let merged = match delta.records.get(&key) {
+                Some(earlier) => args.rules.merge(earlier, &record),
+                None => {
+                    delta.order.push(key.clone());
+                    record
+                }
+            };
+            delta.records.insert(key, merged);
+        }
+    }
+    Ok(delta)
+}
+
+#[derive(Default)]
+struct MergeCounts {
+    base_records: u64,
+    updated: u64,
+    unchanged: u64,
+    skipped_lines: u64,
+    type_mismatches: u64,
+}
+
+/// What happened to one base line
+enum Outcome {
+    /// Not valid JSON: copied as-is
+    Unparseable,
+    /// No delta record: copied as-is
+    Untouched,
+    Unchanged(String),
+    Updated(String, String),
+}
+
+/// `part-00001.gz` → `<output_dir>/part-00001.ndjson.gz`,
+/// `part-00001.parquet` → `<output_dir>/part-00001.parquet`
+fn output_path(input: &str, output_dir: &str) -> String {
+    let mut name = Path::new(input)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    for ext in [".gz", ".parquet", ".ndjson", ".jsonl", ".json"] {
+        if let Some(stripped) = name.strip_suffix(ext) {
+            name = stripped.to_string();
+        }
+    }
+    let ext = if is_parquet(input) { "parquet" } else { "ndjson.gz" };
+    format!("{}/{}.{}", output_dir, name, ext)
+}
+
+/// NDJSON output, or Parquet with the schema and writer configs when `parquet`
+fn create_output(path: &str, parquet: bool, args: &MergeArgs) -> Result<LineWriter, Box<dyn Error>> {
+    if parquet {
+        LineWriter::parquet(path, &args.schema, &args.writer_config)
+    } else {
+        Ok(LineWriter::create(path)?)
+    }
+}
+
+/// A Parquet base is written back with the `--schema` columns, so they must be its columns
+fn check_base_schema(input: &str, schema: &SchemaConfig) -> Result<(), Box<dyn Error>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(input)?)?;
+    if builder.schema().fields() != schema.arrow_schema().fields() {
+        return Err("its columns don't match the schema config; pass the --schema it was converted with".into());
+    }
+    Ok(())
+}
+
+fn merge_base_file(
+    input: &str,
+    output: &str,
+    args: &MergeArgs,
+    delta: &Delta,
+    matched: &mut HashSet<String>,
+) -> Result<MergeCounts, Box<dyn Error>> {
+    let mut counts = MergeCounts::default();
+    let mut out = create_output(output, is_parquet(input), args)?;
+    let mut lines = open_lines(input, &args.schema)?.peekable();
+    let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
+
+    while lines.peek().is_some() {
+        chunk.clear();
+        for line in lines.by_ref().take(CHUNK_SIZE) {
+            let line = line?;
+            if !line.trim().is_empty() {
+                chunk.push(line);
+            }
+        }
+
+        let outcomes: Vec<Outcome> = chunk
+            .par_iter()
+            .map(|line| {
+                let Some(record) = serde_json::from_str::<Value>(line).ok().filter(Value::is_object) else {
+                    return Outcome::Unparseable;
+                };
+                let Some((key, update)) = key_of(&args.key, &record).and_then(|k| delta.records.get_key_value(&k))
+                else {
+                    return Outcome::Untouched;
+                };
+                let merged = args.rules.merge(&record, update);
+                if merged == record {
+                    Outcome::Unchanged(key.clone())
+                } else {
+                    Outcome::Updated(key.clone(), merged.to_string())
+                }
+            })
+            .collect();
+
+        for (line, outcome) in chunk.iter().zip(outcomes) {
+            match outcome {
+                Outcome::Unparseable => {
+                    counts.skipped_lines += 1;
+                    out.write_line(line)?;
+                }
+                Outcome::Untouched => {
+                    counts.base_records += 1;
+                    out.write_line(line)?;
+                }
+                Outcome::Unchanged(key) => {
+                    counts.base_records += 1;
+                    counts.unchanged += 1;
+                    matched.insert(key);
+                    out.write_line(line)?;
+                }
+                Outcome::Updated(key, merged) => {
+                    counts.base_records += 1;
+                    counts.updated += 1;
+                    matched.insert(key);
+                    out.write_line(&merged)?;
+                }
+            }
+        }
+    }
+
+    counts.type_mismatches = out.type_mismatches();
+    out.finish()?;
+    Ok(counts)
+}
+
+pub fn run(argv: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(argv)?;
+    fs::create_dir_all(&args.output_dir)?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Base files: {}, delta files: {}", args.base.len(), args.delta.len());
+    println!("📁 Output directory: {}", args.output_dir);
+    println!("🔑 Key: {}, newest by: {}", args.key.as_str(), args.rules.latest_by.as_str());
+    println!();
+
+    for input in args.base.iter().filter(|input| is_parquet(input)) {
+        check_base_schema(input, &args.schema).map_err(|e| format!("Invalid base {}: {}", input, e))?;
+    }
+    let parquet_dataset = args.base.iter().all(|input| is_parquet(input));
+    let inserted_path = if parquet_dataset {
+        format!("{}/inserted.parquet", args.output_dir)
+    } else {
+        format!("{}/inserted.ndjson.gz", args.output_dir)
+    };
+    let mut inserted_out = create_output(&inserted_path, parquet_dataset, &args)?;
+    let (unkeyed_path, mut unkeyed_out) = if args.insert_unkeyed {
+        (inserted_path.clone(), None)
+    } else {
+        let path = format!("{}/unkeyed.ndjson.gz", args.output_dir);
+        let out = LineWriter::create(&path)?;
+        (path, Some(out))
+    };
+
+    let delta = load_delta(&args, unkeyed_out.as_mut().unwrap_or(&mut inserted_out))?;
+    if let Some(out) = unkeyed_out {
+        out.finish()?;
+    }
+    println!(
+        "✅ {} delta records, {} distinct keys, {} without key (→ {}), {} unparseable lines\n",
+        delta.total,
+        delta.records.len(),
+        delta.without_key,
+        unkeyed_path,
+        delta.skipped_lines
+    );
+
+    let mut totals = MergeCounts::default();
+    let mut matched: HashSet<String> = HashSet::new();
+    for input in &args.base {
+        let output = output_path(input, &args.output_dir);
+        println!("🔀 Merging {} → {}", input, output);
+        let counts = merge_base_file(input, &output, &args, &delta, &mut matched)
+            .map_err(|e| format!("Failed to merge {}: {}", input, e))?;
+        println!(
+            "✅ {} records, {} updated, {} unchanged, {} unparseable lines",
+            counts.base_records, counts.updated, counts.unchanged, counts.skipped_lines
+        );
+        if counts.type_mismatches > 0 {
+            println!("   ⚠️  {} type mismatches written as NULL", counts.type_mismatches);
+        }
+        totals.base_records += counts.base_records;
+        totals.updated += counts.updated;
+        totals.unchanged += counts.unchanged;
+        totals.skipped_lines += counts.skipped_lines;
+        totals.type_mismatches += counts.type_mismatches;
+    }
+
+    // Delta keys never seen in the base are new records, written in delta order
+    let mut inserted = if args.insert_unkeyed { delta.without_key } else { 0 };
+    for key in delta.order.iter().filter(|key| !matched.contains(*key)) {
+        inserted_out.write_line(&delta.records[key].to_string())?;
+        inserted += 1;
+    }
+    totals.type_mismatches += inserted_out.type_mismatches();
+    inserted_out.finish()?;
+
+    println!("\n📊 Merge summary");
+    println!("   Inserted  : {} (→ {})", inserted, inserted_path);
+    println!("   Unkeyed   : {} (→ {})", delta.without_key, unkeyed_path);
+    println!("   Updated   : {}", totals.updated);
+    println!("   Unchanged : {}", totals.unchanged);
+    println!("   Untouched : {}", totals.base_records - totals.updated - totals.unchanged);
+
+    let report = json!({
+        "base": args.base,
+        "delta": args.delta,
+        "key": args.key.as_str(),
+        "latest_by": args.rules.latest_by.as_str(),
+        "union": args.rules.union.iter().collect::<Vec<_>>(),
+        "base_records": totals.base_records,
+        "delta_records": delta.total,
+        "delta_distinct_keys": delta.records.len(),
+        "delta_without_key": delta.without_key,
+        "unkeyed_output": unkeyed_path,
+        "inserted": inserted,
+        "updated": totals.updated,
+        "unchanged": totals.unchanged,
+        "untouched": totals.base_records - totals.updated - totals.unchanged,
+        "unparseable_lines": totals.skipped_lines + delta.skipped_lines,
+        "type_mismatches": totals.type_mismatches,
+    });
+    let mut w = BufWriter::new(File::create(&args.report)?);
+    serde_json::to_writer_pretty(&mut w, &report)?;
+    w.flush()?;
+    println!("📝 Report written to {}", args.report);
+
+    println!("\n🎉 Done in {:.2}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
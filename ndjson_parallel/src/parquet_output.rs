@@ -0,0 +1,106 @@
+//! Parquet output fed NDJSON lines, for subcommands that write a dataset back
+//! in the format it was read in.
+//!
+//! Lines are buffered up to `CHUNK_SIZE`, parsed and converted with the same
+//! column builders and writer properties as the converter, so the output reads
+//! like a file `ndjson_parallel` wrote itself. Values that don't fit the schema
+//! become NULL and are counted; the finished file is verified unless the writer
+//! config turns that off.
+
+use crate::schema_config::SchemaConfig;
+use crate::verify::{self, RowCounts};
+use crate::writer_config::WriterConfig;
+use crate::{build_batch, CHUNK_SIZE};
+use arrow::datatypes::Schema;
+use parquet::arrow::ArrowWriter;
+use rayon::prelude::*;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::sync::Arc;
+
+pub struct ParquetOutput {
+    path: String,
+    schema_config: SchemaConfig,
+    schema: Arc<Schema>,
+    max_row_group_bytes: usize,
+    verify: bool,
+    writer: ArrowWriter<File>,
+    pending: Vec<String>,
+    written: RowCounts,
+    type_mismatches: u64,
+}
+
+impl ParquetOutput {
+    /// `writer_config` must not set `sort_by`: lines are written in the order
+    /// they arrive, so a recorded sort order would not hold
+    pub fn create(
+        path: &str,
+        schema_config: &SchemaConfig,
+        writer_config: &WriterConfig,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !writer_config.sort_by.is_empty() {
+            return Err(format!("{} is written in input order, so sort_by can't be used", path).into());
+        }
+        let schema = schema_config.arrow_schema();
+        let props = writer_config.to_properties(&schema)?;
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))?;
+        Ok(ParquetOutput {
+            path: path.to_string(),
+            schema_config: schema_config.clone(),
+            written: RowCounts::new(&schema),
+            schema,
+            max_row_group_bytes: writer_config.max_row_group_bytes,
+            verify: writer_config.verify,
+            writer,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+            type_mismatches: 0,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.pending.push(line.to_string());
+        if self.pending.len() >= CHUNK_SIZE {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Values written as NULL so far because they didn't fit their column type
+    pub fn type_mismatches(&self) -> u64 {
+        self.type_mismatches
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let records = self
+            .pending
+            .par_iter()
+            .map(|line| serde_json::from_str::<Map<String, Value>>(line))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.pending.clear();
+
+        let (batch, counts, mismatches) = build_batch(&self.schema_config, &self.schema, &records)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        self.written.merge(&counts);
+        self.type_mismatches += mismatches;
+        if batch.num_rows() > 0 {
+            self.writer.write(&batch)?;
+            if self.writer.in_progress_size() >= self.max_row_group_bytes {
+                self.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write what is left, close the file and verify it
+    pub fn finish(mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.writer.close()?;
+        if self.verify {
+            verify::verify_parquet(&self.path, &self.schema, &self.written)
+                .map_err(|e| io::Error::other(format!("Verification failed for {}: {}", self.path, e)))?;
+        }
+        Ok(())
+    }
+}
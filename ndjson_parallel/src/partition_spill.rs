@@ -0,0 +1,92 @@
+//! Hash-partitioned spill files, for grouping more keys than fit in memory.
+//!
+//! Each entry is written as one JSON line to one of N partition files chosen
+//! by the hash of its key. Equal keys always land in the same partition, so
+//! each partition can be grouped on its own with only 1/N of the entries in
+//! memory. Files are removed when the spill is dropped.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Partition files being written
+pub struct PartitionSpill {
+    paths: Vec<PathBuf>,
+    writers: Vec<BufWriter<File>>,
+}
+
+impl PartitionSpill {
+    /// `partitions` files named `ndjson_<name>_<pid>_<n>.part` in `dir`
+    pub fn create(dir: &Path, name: &str, partitions: usize) -> io::Result<PartitionSpill> {
+        fs::create_dir_all(dir)?;
+        let paths: Vec<PathBuf> = (0..partitions)
+            .map(|i| dir.join(format!("ndjson_{}_{}_{}.part", name, std::process::id(), i)))
+            .collect();
+        let writers = paths
+            .iter()
+            .map(|p| File::create(p).map(|f| BufWriter::with_capacity(256 * 1024, f)))
+            .collect::<io::Result<_>>()?;
+        Ok(PartitionSpill { paths, writers })
+    }
+
+    pub fn push<K: Hash + ?Sized, T: Serialize>(&mut self, key: &K, entry: &T) -> io::Result<()> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let partition = (hasher.finish() % self.writers.len() as u64) as usize;
+        let w = &mut self.writers[partition];
+        serde_json::to_writer(&mut *w, entry)?;
+        w.write_all(b"\n")
+    }
+
+    pub fn finish(mut self) -> io::Result<Partitions> {
+        for w in &mut self.writers {
+            w.flush()?;
+        }
+        Ok(Partitions {
+            paths: std::mem::take(&mut self.paths),
+        })
+    }
+}
+
+impl Drop for PartitionSpill {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Finished partition files
+pub struct Partitions {
+    paths: Vec<PathBuf>,
+}
+
+impl Partitions {
+    pub fn count(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Every entry of one partition, in the order it was pushed
+    pub fn read<T: DeserializeOwned>(&self, partition: usize) -> io::Result<Vec<T>> {
+        let reader = BufReader::with_capacity(256 * 1024, File::open(&self.paths[partition])?);
+        // Written by `push`, so always valid UTF-8 (unlike the inputs, see
+        // `input_lines.rs`); an error here is a real I/O failure, and skipping
+        // the line would silently drop an entry from its group
+        reader
+            .lines()
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+}
+
+impl Drop for Partitions {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
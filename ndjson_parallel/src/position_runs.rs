@@ -0,0 +1,105 @@
+//! Positions of dropped duplicates, spilled to disk as sorted runs.
+//!
+//! `dedup` finds the losers of its duplicate groups one partition at a time,
+//! in hash order. Each partition's positions are sorted and written as one
+//! run of `file line` lines; `merge` reads every run back as a single stream
+//! in input order (file, then line), so the output pass can skip them while
+//! copying without holding all positions in memory. Run files are removed
+//! when the runs are dropped.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Input file index and line number
+pub type Position = (u32, u64);
+
+pub struct PositionRuns {
+    dir: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+impl PositionRuns {
+    pub fn new(dir: &Path) -> PositionRuns {
+        PositionRuns {
+            dir: dir.to_path_buf(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Sort `positions` and write them out as one run
+    pub fn push_run(&mut self, mut positions: Vec<Position>) -> io::Result<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        positions.sort_unstable();
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "ndjson_dropped_{}_{}.run",
+            std::process::id(),
+            self.paths.len()
+        ));
+        // Recorded before writing so a failed run is still removed
+        self.paths.push(path.clone());
+        let mut writer = BufWriter::with_capacity(256 * 1024, File::create(&path)?);
+        for (file, line) in positions {
+            writeln!(writer, "{} {}", file, line)?;
+        }
+        writer.flush()
+    }
+
+    /// Every position of every run, in input order
+    pub fn merge(&self) -> io::Result<MergedPositions> {
+        let mut readers = Vec::with_capacity(self.paths.len());
+        let mut heap = BinaryHeap::with_capacity(self.paths.len());
+        for (run, path) in self.paths.iter().enumerate() {
+            let mut reader = BufReader::with_capacity(64 * 1024, File::open(path)?);
+            if let Some(position) = read_position(&mut reader)? {
+                heap.push(Reverse((position, run)));
+            }
+            readers.push(reader);
+        }
+        Ok(MergedPositions { readers, heap })
+    }
+}
+
+impl Drop for PositionRuns {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn read_position(reader: &mut BufReader<File>) -> io::Result<Option<Position>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid position run line {:?}", line));
+    let (file, line_no) = line.trim_end().split_once(' ').ok_or_else(invalid)?;
+    let file = file.parse().map_err(|_| invalid())?;
+    let line_no = line_no.parse().map_err(|_| invalid())?;
+    Ok(Some((file, line_no)))
+}
+
+pub struct MergedPositions {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(Position, usize)>>,
+}
+
+impl MergedPositions {
+    /// The next position in input order, or None once every run is exhausted
+    pub fn next_position(&mut self) -> io::Result<Option<Position>> {
+        let Some(Reverse((position, run))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        if let Some(next) = read_position(&mut self.readers[run])? {
+            self.heap.push(Reverse((next, run)));
+        }
+        Ok(Some(position))
+    }
+}
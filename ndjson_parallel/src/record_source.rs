@@ -0,0 +1,98 @@
+//! Input records as NDJSON lines, from NDJSON (plain or `.gz`) or Parquet.
+//!
+//! Parquet files are decoded batch by batch and each row is turned back into
+//! a JSON line (nulls omitted, dates as `YYYY-MM-DD`). Columns declared `json`
+//! in the schema config hold serialized JSON text and are parsed back into
+//! nested values, so a converted dataset reads like the NDJSON it came from.
+
+use crate::input_lines::read_lines;
+use crate::schema_config::{ColumnType, SchemaConfig};
+use arrow::json::LineDelimitedWriter;
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::File;
+use std::io;
+
+pub type Lines = Box<dyn Iterator<Item = io::Result<String>> + Send>;
+
+pub fn is_parquet(path: &str) -> bool {
+    path.ends_with(".parquet")
+}
+
+/// Lines of `path`; `schema` names the `json` columns of Parquet inputs
+pub fn open_lines(path: &str, schema: &SchemaConfig) -> Result<Lines, Box<dyn Error>> {
+    if !is_parquet(path) {
+        return Ok(Box::new(read_lines(path)?));
+    }
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+    let json_columns = schema
+        .columns
+        .iter()
+        .filter(|c| matches!(c.column_type, ColumnType::Json))
+        .map(|c| c.name.clone())
+        .collect();
+    Ok(Box::new(ParquetLines {
+        reader,
+        pending: VecDeque::new(),
+        json_columns,
+    }))
+}
+
+struct ParquetLines {
+    reader: ParquetRecordBatchReader,
+    pending: VecDeque<String>,
+    json_columns: Vec<String>,
+}
+
+impl ParquetLines {
+    /// Decode the next batch into `pending`; false once the file is exhausted
+    fn fill(&mut self) -> io::Result<bool> {
+        let Some(batch) = self.reader.next() else {
+            return Ok(false);
+        };
+        let batch = batch.map_err(io::Error::other)?;
+
+        let mut buf = Vec::new();
+        let mut writer = LineDelimitedWriter::new(&mut buf);
+        writer.write(&batch).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)?;
+
+        let text = String::from_utf8(buf).map_err(io::Error::other)?;
+        for line in text.lines() {
+            self.pending.push_back(self.expand_json_columns(line)?);
+        }
+        Ok(true)
+    }
+
+    fn expand_json_columns(&self, line: &str) -> io::Result<String> {
+        if self.json_columns.is_empty() {
+            return Ok(line.to_string());
+        }
+        let mut record: Map<String, Value> = serde_json::from_str(line)?;
+        for column in &self.json_columns {
+            if let Some(Value::String(text)) = record.get(column) {
+                if let Ok(value) = serde_json::from_str::<Value>(text) {
+                    record.insert(column.clone(), value);
+                }
+            }
+        }
+        Ok(serde_json::to_string(&record)?)
+    }
+}
+
+impl Iterator for ParquetLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            match self.fill() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
@@ -0,0 +1,164 @@
+//! Output schema, loaded from a JSON config file.
+//!
+//! Each column names a top-level JSON key and the Arrow type it is converted to.
+//! Values that don't fit the declared type are written as NULL and counted.
+//!
+//! ```json
+//! {
+//!   "columns": [
+//!     { "name": "id", "type": "string" },
+//!     { "name": "birth_year", "type": "int32" },
+//!     { "name": "job_last_updated", "type": "date32" },
+//!     { "name": "skills", "type": { "list": "string" } },
+//!     { "name": "version_status", "type": { "struct": [
+//!         { "name": "status", "type": "string" },
+//!         { "name": "contains", "type": { "list": "string" } }
+//!     ] } },
+//!     { "name": "experience", "type": "json" }
+//!   ]
+//! }
+//! ```
+//!
+//! `json` keeps the value as serialized JSON text, for deeply nested fields that
+//! are not worth typing.
+
+use arrow::datatypes::{DataType, Field, Fields, Schema};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::sync::Arc;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    String,
+    Int32,
+    Int64,
+    Float64,
+    Boolean,
+    /// `YYYY-MM-DD`, `YYYY-MM` or `YYYY`; partial dates map to the first day
+    Date32,
+    Json,
+    List(Box<ColumnType>),
+    Struct(Vec<ColumnDef>),
+}
+
+impl ColumnType {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::String | ColumnType::Json => DataType::Utf8,
+            ColumnType::Int32 => DataType::Int32,
+            ColumnType::Int64 => DataType::Int64,
+            ColumnType::Float64 => DataType::Float64,
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::Date32 => DataType::Date32,
+            ColumnType::List(item) => DataType::List(Arc::new(list_item_field(item))),
+            ColumnType::Struct(children) => {
+                DataType::Struct(children.iter().map(ColumnDef::field).collect::<Fields>())
+            }
+        }
+    }
+
+    fn validate(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            ColumnType::List(item) => item.validate(&format!("{}[]", path)),
+            ColumnType::Struct(children) => {
+                if children.is_empty() {
+                    return Err(format!("struct column {} has no fields", path).into());
+                }
+                for child in children {
+                    child.column_type.validate(&format!("{}.{}", path, child.name))?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn list_item_field(item: &ColumnType) -> Field {
+    Field::new("item", item.data_type(), true)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+}
+
+impl ColumnDef {
+    fn new(name: &str, column_type: ColumnType) -> Self {
+        ColumnDef {
+            name: name.to_string(),
+            column_type,
+        }
+    }
+
+    pub fn field(&self) -> Field {
+        Field::new(&self.name, self.column_type.data_type(), true)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SchemaConfig {
+    pub columns: Vec<ColumnDef>,
+}
+
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        let version_status = ColumnType::Struct(vec![
+            ColumnDef::new("status", ColumnType::String),
+            ColumnDef::new("contains", ColumnType::List(Box::new(ColumnType::String))),
+            ColumnDef::new("previous_version", ColumnType::String),
+            ColumnDef::new("current_version", ColumnType::String),
+        ]);
+
+        SchemaConfig {
+            columns: vec![
+                ColumnDef::new("id", ColumnType::String),
+                ColumnDef::new("full_name", ColumnType::String),
+                ColumnDef::new("gender", ColumnType::String),
+                ColumnDef::new("job_title", ColumnType::String),
+                ColumnDef::new("location_country", ColumnType::String),
+                ColumnDef::new("location_region", ColumnType::String),
+                ColumnDef::new("location_continent", ColumnType::String),
+                ColumnDef::new("job_last_updated", ColumnType::Date32),
+                ColumnDef::new("experience", ColumnType::Json),
+                ColumnDef::new("education", ColumnType::Json),
+                ColumnDef::new("profiles", ColumnType::Json),
+                ColumnDef::new("version_status", version_status),
+            ],
+        }
+    }
+}
+
+impl SchemaConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read schema config {}: {}", path, e))?;
+        let config: SchemaConfig = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid schema config {}: {}", path, e))?;
+
+        if config.columns.is_empty() {
+            return Err(format!("Schema config {} has no columns", path).into());
+        }
+        let mut seen = HashSet::new();
+        for column in &config.columns {
+            if !seen.insert(column.name.as_str()) {
+                return Err(format!("Column {} is declared twice in {}", column.name, path).into());
+            }
+            column.column_type.validate(&column.name)?;
+        }
+        Ok(config)
+    }
+
+    pub fn arrow_schema(&self) -> Arc<Schema> {
+        Arc::new(Schema::new(
+            self.columns.iter().map(ColumnDef::field).collect::<Vec<_>>(),
+        ))
+    }
+}
@@ -0,0 +1,293 @@
+//! `validate` subcommand: check every record before conversion.
+//!
+//! Usage:
+//!   ndjson_parallel validate [--schema FILE] [--rules FILE] [--json-schema FILE]
+//!       [--quarantine OUT] [--max-failure-rate R] [--samples N] [--report FILE]
+//!       [INPUT ...]
+//!
+//! See `validation.rs` for the rule sources. A shard passes when the share of
+//! failing records is at most `R` (default 0). Lines that can't be read as a
+//! record fail too: `(line) json` when they don't parse, `(line) utf8` for
+//! invalid UTF-8 and `(line) oversized` past the line reader's size limit.
+//! Failing lines are copied unchanged, in input order, to `--quarantine`
+//! (gzip when `OUT` ends in `.gz`); over-long lines are streamed there as they
+//! are read, so they can land ahead of failing lines earlier in the same chunk.
+//! Per-shard results, violation counts by rule and the first `--samples`
+//! (default 20) failures, with physical line numbers, are written to
+//! `validation_report.json` (`--report`); the command exits with an error
+//! when any shard fails, so it can gate a conversion run.
+
+use crate::input_lines::{checked_lines, CheckedLine, CheckedLines};
+use crate::line_writer::LineWriter;
+use crate::validation::Validator;
+use crate::{CHUNK_SIZE, DEFAULT_INPUT};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+const PARSE_RULE: &str = "(line) json";
+const UTF8_RULE: &str = "(line) utf8";
+const OVERSIZED_RULE: &str = "(line) oversized";
+
+struct ValidateArgs {
+    validator: Validator,
+    quarantine: Option<String>,
+    max_failure_rate: f64,
+    samples: usize,
+    report: String,
+    inputs: Vec<String>,
+}
+
+fn parse_args(argv: impl Iterator<Item = String>) -> Result<ValidateArgs, Box<dyn Error>> {
+    let mut schema = None;
+    let mut rules = None;
+    let mut json_schema = None;
+    let mut quarantine = None;
+    let mut max_failure_rate = 0.0;
+    let mut samples = 20;
+    let mut report = "validation_report.json".to_string();
+    let mut inputs = Vec::new();
+
+    let mut iter = argv;
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--schema" => schema = Some(value("--schema")?),
+            "--rules" => rules = Some(value("--rules")?),
+            "--json-schema" => json_schema = Some(value("--json-schema")?),
+            "--quarantine" => quarantine = Some(value("--quarantine")?),
+            "--max-failure-rate" => {
+                let r = value("--max-failure-rate")?;
+                max_failure_rate = r
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|r| (0.0..=1.0).contains(r))
+                    .ok_or(format!("Invalid --max-failure-rate {} (0 <= R <= 1)", r))?;
+            }
+            "--samples" => {
+                let n = value("--samples")?;
+                samples = n.parse().map_err(|_| format!("Invalid --samples {}", n))?;
+            }
+            "--report" => report = value("--report")?,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag).into()),
+            _ => inputs.push(arg),
+        }
+    }
+
+    if inputs.is_empty() {
+        inputs.push(DEFAULT_INPUT.to_string());
+    }
+    Ok(ValidateArgs {
+        validator: Validator::load(schema.as_deref(), rules.as_deref(), json_schema.as_deref())?,
+        quarantine,
+        max_failure_rate,
+        samples,
+        report,
+        inputs,
+    })
+}
+
+/// Per-shard counts
+#[derive(Default)]
+struct ShardSummary {
+    records: usize,
+    failed: usize,
+    unparseable: usize,
+    violations: BTreeMap<String, usize>,
+}
+
+impl ShardSummary {
+    fn failure_rate(&self) -> f64 {
+        if self.records == 0 {
+            0.0
+        } else {
+            self.failed as f64 / self.records as f64
+        }
+    }
+}
+
+fn validate_file(
+    input: &str,
+    args: &ValidateArgs,
+    quarantine: &mut Option<LineWriter>,
+    samples: &mut Vec<Value>,
+) -> Result<ShardSummary, Box<dyn Error>> {
+    // The reader keeps the quarantine for the length of the file, so it can
+    // stream over-long lines to it; hand it back even when the file fails
+    let mut lines = checked_lines(input, quarantine)?;
+    let result = validate_lines(input, &mut lines, args, samples);
+    *quarantine = lines.into_quarantine();
+    result
+}
+
+fn validate_lines(
+    input: &str,
+    lines: &mut CheckedLines,
+    args: &ValidateArgs,
+    samples: &mut Vec<Value>,
+) -> Result<ShardSummary, Box<dyn Error>> {
+    let mut summary = ShardSummary::default();
+    let mut chunk: Vec<(u64, CheckedLine)> = Vec::with_capacity(CHUNK_SIZE);
+
+    loop {
+        chunk.clear();
+        let mut read = 0;
+        for line in lines.by_ref().take(CHUNK_SIZE) {
+            let (line_no, line) = line?;
+            read += 1;
+            if !matches!(&line, CheckedLine::Text(text) if text.trim().is_empty()) {
+                chunk.push((line_no, line));
+            }
+        }
+        if read == 0 {
+            break;
+        }
+
+        // Violations per line, in input order; empty when the record passes
+        let verdicts: Vec<Vec<String>> = chunk
+            .par_iter()
+            .map(|(_, line)| match line {
+                CheckedLine::Text(text) => match serde_json::from_str::<Value>(text) {
+                    Ok(record) => args.validator.check(&record),
+                    Err(_) => vec![PARSE_RULE.to_string()],
+                },
+                CheckedLine::InvalidUtf8(_) => vec![UTF8_RULE.to_string()],
+                CheckedLine::Oversized => vec![OVERSIZED_RULE.to_string()],
+            })
+            .collect();
+
+        for ((line_no, line), violations) in chunk.iter().zip(verdicts) {
+            summary.records += 1;
+            if violations.is_empty() {
+                continue;
+            }
+            summary.failed += 1;
+            if violations.iter().any(|v| v.starts_with("(line) ")) {
+                summary.unparseable += 1;
+            }
+            if let Some(out) = lines.quarantine_mut() {
+                match line {
+                    CheckedLine::Text(text) => out.write_line(text)?,
+                    CheckedLine::InvalidUtf8(bytes) => {
+                        out.write_all(bytes)?;
+                        out.write_all(b"\n")?;
+                    }
+                    CheckedLine::Oversized => {}
+                }
+            }
+            for violation in &violations {
+                *summary.violations.entry(violation.clone()).or_default() += 1;
+            }
+            if samples.len() < args.samples {
+                samples.push(json!({ "file": input, "line": line_no, "violations": violations }));
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let args = parse_args(args)?;
+    let mut quarantine = args.quarantine.as_deref().map(LineWriter::create).transpose()?;
+
+    println!("🚀 Using {} CPU cores", rayon::current_num_threads());
+    println!("📁 Input files: {}", args.inputs.len());
+    if let Some(path) = &args.quarantine {
+        println!("📁 Quarantine: {}", path);
+    }
+    println!();
+
+    let mut shards = Vec::with_capacity(args.inputs.len());
+    let mut samples = Vec::new();
+    let mut totals = ShardSummary::default();
+    let mut failed_shards = 0usize;
+
+    for input in &args.inputs {
+        println!("📖 Validating {}", input);
+        match validate_file(input, &args, &mut quarantine, &mut samples) {
+            Ok(summary) => {
+                let passed = summary.failure_rate() <= args.max_failure_rate;
+                if passed {
+                    println!("✅ Pass: {} of {} records failed", summary.failed, summary.records);
+                } else {
+                    failed_shards += 1;
+                    println!(
+                        "❌ Fail: {} of {} records failed ({:.4}% > {:.4}%)",
+                        summary.failed,
+                        summary.records,
+                        summary.failure_rate() * 100.0,
+                        args.max_failure_rate * 100.0
+                    );
+                }
+                for (rule, count) in &summary.violations {
+                    println!("   ⚠️  {}: {}", rule, count);
+                }
+                shards.push(json!({
+                    "file": input,
+                    "status": if passed { "pass" } else { "fail" },
+                    "records": summary.records,
+                    "passed": summary.records - summary.failed,
+                    "failed": summary.failed,
+                    "unparseable_lines": summary.unparseable,
+                    "failure_rate": summary.failure_rate(),
+                    "violations": summary.violations,
+                }));
+                totals.records += summary.records;
+                totals.failed += summary.failed;
+                totals.unparseable += summary.unparseable;
+                for (rule, count) in summary.violations {
+                    *totals.violations.entry(rule).or_default() += count;
+                }
+            }
+            Err(e) => {
+                failed_shards += 1;
+                eprintln!("❌ Failed to validate {}: {}", input, e);
+                shards.push(json!({ "file": input, "status": "error", "error": e.to_string() }));
+            }
+        }
+    }
+
+    if let Some(out) = quarantine {
+        out.finish()?;
+    }
+
+    let report = json!({
+        "max_failure_rate": args.max_failure_rate,
+        "shards": shards,
+        "totals": {
+            "records": totals.records,
+            "passed": totals.records - totals.failed,
+            "failed": totals.failed,
+            "unparseable_lines": totals.unparseable,
+            "failure_rate": totals.failure_rate(),
+            "violations": totals.violations,
+        },
+        "samples": samples,
+    });
+    let mut w = BufWriter::new(File::create(&args.report)?);
+    serde_json::to_writer_pretty(&mut w, &report)?;
+    w.flush()?;
+    println!("\n📝 Report written to {}", args.report);
+
+    let secs = start.elapsed().as_secs_f64();
+    println!(
+        "\n🎉 {} of {} records failed in {:.2}s ({:.0} records/sec)",
+        totals.failed,
+        totals.records,
+        secs,
+        totals.records as f64 / secs
+    );
+
+    if failed_shards > 0 {
+        return Err(format!("{} of {} shards failed validation", failed_shards, args.inputs.len()).into());
+    }
+    Ok(())
+}
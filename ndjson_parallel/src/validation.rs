@@ -0,0 +1,389 @@
+//! Record checks for the `validate` subcommand, from up to three sources:
+//!
+//! - the schema config (`--schema`): each column's value must fit its declared
+//!   type the way the converter would take it, without becoming NULL
+//! - a rules file (`--rules`):
+//!
+//! ```json
+//! {
+//!   "required": ["id", "full_name"],
+//!   "enums": { "gender": ["male", "female"] },
+//!   "formats": { "work_email": "email", "emails[].address": "email",
+//!                "linkedin_url": "url", "birth_date": "date" },
+//!   "patterns": { "id": "^[A-Za-z0-9_-]{10,}$" }
+//! }
+//! ```
+//!
+//!   Paths use the `filter` syntax (`[]` fans out over arrays). A required
+//!   path needs at least one non-null, non-blank value; enums, formats and
+//!   patterns only look at values that are present. Formats are `email`,
+//!   `url` (the scheme may be left out, as in `linkedin.com/in/x`) and `date`
+//!   (`YYYY-MM-DD`, `YYYY-MM` or `YYYY`).
+//! - a JSON Schema (`--json-schema`), of which `type`, `required`,
+//!   `properties`, `additionalProperties: false`, `items`, `enum`, `format`
+//!   (`email`, `uri`, `date`), `pattern`, `minLength`/`maxLength` and
+//!   `minimum`/`maximum` are checked; other keywords are ignored.
+//!
+//! A violation is labelled `<path> <rule>`, e.g. `gender enum` or
+//! `work_email format:email`, and counted once per record.
+
+use crate::column_builder::{fits, parse_date};
+use crate::json_path::JsonPath;
+use crate::schema_config::{ColumnDef, SchemaConfig};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Email,
+    Url,
+    Date,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Email => "email",
+            Format::Url => "url",
+            Format::Date => "date",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        let Some(s) = value.as_str() else {
+            return false;
+        };
+        match self {
+            Format::Email => is_email(s),
+            Format::Url => is_url(s),
+            Format::Date => parse_date(s).is_some(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesConfig {
+    pub required: Vec<String>,
+    pub enums: BTreeMap<String, Vec<Value>>,
+    pub formats: BTreeMap<String, Format>,
+    pub patterns: BTreeMap<String, String>,
+}
+
+enum Check {
+    Column(ColumnDef),
+    Required(JsonPath),
+    Enum(JsonPath, Vec<Value>),
+    Format(JsonPath, Format),
+    Pattern(JsonPath, Regex),
+    JsonSchema(SchemaNode),
+}
+
+pub struct Validator {
+    checks: Vec<Check>,
+}
+
+impl Validator {
+    /// Load the rule sources that were given; at least one is needed
+    pub fn load(
+        schema: Option<&str>,
+        rules: Option<&str>,
+        json_schema: Option<&str>,
+    ) -> Result<Validator, Box<dyn Error>> {
+        let mut checks = Vec::new();
+        if let Some(path) = schema {
+            checks.extend(SchemaConfig::load(path)?.columns.into_iter().map(Check::Column));
+        }
+        if let Some(path) = rules {
+            let text = fs::read_to_string(path).map_err(|e| format!("Failed to read rules {}: {}", path, e))?;
+            let config: RulesConfig =
+                serde_json::from_str(&text).map_err(|e| format!("Invalid rules {}: {}", path, e))?;
+            checks.extend(rule_checks(config).map_err(|e| format!("Invalid rules {}: {}", path, e))?);
+        }
+        if let Some(path) = json_schema {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("Failed to read JSON Schema {}: {}", path, e))?;
+            let schema: Value =
+                serde_json::from_str(&text).map_err(|e| format!("Invalid JSON Schema {}: {}", path, e))?;
+            let node = SchemaNode::parse(&schema).map_err(|e| format!("Invalid JSON Schema {}: {}", path, e))?;
+            checks.push(Check::JsonSchema(node));
+        }
+        if checks.is_empty() {
+            return Err("validate needs --schema, --rules or --json-schema".into());
+        }
+        Ok(Validator { checks })
+    }
+
+    /// The distinct violations of one record, sorted; empty when it passes
+    pub fn check(&self, record: &Value) -> Vec<String> {
+        let mut violations = Vec::new();
+        for check in &self.checks {
+            match check {
+                Check::Column(column) => {
+                    if !record.is_object() {
+                        violations.push("(record) type".to_string());
+                    } else if !fits(&column.column_type, record.get(&column.name)) {
+                        violations.push(format!("{} type", column.name));
+                    }
+                }
+                Check::Required(path) => {
+                    if !path.resolve(record).into_iter().any(has_value) {
+                        violations.push(format!("{} required", path.as_str()));
+                    }
+                }
+                Check::Enum(path, allowed) => {
+                    if present(path, record).any(|v| !allowed.contains(v)) {
+                        violations.push(format!("{} enum", path.as_str()));
+                    }
+                }
+                Check::Format(path, format) => {
+                    if present(path, record).any(|v| !format.matches(v)) {
+                        violations.push(format!("{} format:{}", path.as_str(), format.name()));
+                    }
+                }
+                Check::Pattern(path, re) => {
+                    if present(path, record).any(|v| !v.as_str().is_some_and(|s| re.is_match(s))) {
+                        violations.push(format!("{} pattern", path.as_str()));
+                    }
+                }
+                Check::JsonSchema(node) => node.check(record, "", &mut violations),
+            }
+        }
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+}
+
+fn rule_checks(config: RulesConfig) -> Result<Vec<Check>, String> {
+    let mut checks = Vec::new();
+    for path in &config.required {
+        checks.push(Check::Required(JsonPath::parse(path)?));
+    }
+    for (path, allowed) in config.enums {
+        checks.push(Check::Enum(JsonPath::parse(&path)?, allowed));
+    }
+    for (path, format) in config.formats {
+        checks.push(Check::Format(JsonPath::parse(&path)?, format));
+    }
+    for (path, pattern) in config.patterns {
+        let re = Regex::new(&pattern).map_err(|e| format!("Invalid regex for {}: {}", path, e))?;
+        checks.push(Check::Pattern(JsonPath::parse(&path)?, re));
+    }
+    Ok(checks)
+}
+
+/// Non-null values at a path
+fn present<'a>(path: &JsonPath, record: &'a Value) -> impl Iterator<Item = &'a Value> {
+    path.resolve(record).into_iter().filter(|v| !v.is_null())
+}
+
+fn has_value(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.trim().is_empty(),
+        _ => true,
+    }
+}
+
+/// `local@domain.tld` with no whitespace and a dot in the domain
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !s.chars().any(char::is_whitespace)
+        && !local.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+/// `http(s)://host/...` or a bare `host.tld/...`
+fn is_url(s: &str) -> bool {
+    if s.is_empty() || s.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let rest = match s.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") => rest,
+        Some(_) => return false,
+        None => s,
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit_once('@').map_or(host, |(_, h)| h);
+    let host = host.split(':').next().unwrap_or_default();
+    host.contains('.')
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-'))
+}
+
+/// The supported subset of one JSON Schema (sub)schema
+#[derive(Default)]
+struct SchemaNode {
+    types: Vec<String>,
+    required: Vec<String>,
+    properties: Vec<(String, SchemaNode)>,
+    closed: bool,
+    items: Option<Box<SchemaNode>>,
+    allowed: Option<Vec<Value>>,
+    format: Option<Format>,
+    pattern: Option<Regex>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+}
+
+const SCHEMA_TYPES: [&str; 7] = ["string", "integer", "number", "boolean", "object", "array", "null"];
+
+impl SchemaNode {
+    fn parse(schema: &Value) -> Result<SchemaNode, String> {
+        let mut node = SchemaNode::default();
+        let Some(obj) = schema.as_object() else {
+            // `true` and `{}` accept anything; `false` is not supported
+            return match schema {
+                Value::Bool(true) => Ok(node),
+                _ => Err(format!("expected a schema object, got {}", schema)),
+            };
+        };
+        let usize_of = |key: &str| match obj.get(key) {
+            None => Ok(None),
+            Some(v) => v.as_u64().map(|n| Some(n as usize)).ok_or(format!("\"{}\" takes an integer", key)),
+        };
+        let f64_of = |key: &str| match obj.get(key) {
+            None => Ok(None),
+            Some(v) => v.as_f64().map(Some).ok_or(format!("\"{}\" takes a number", key)),
+        };
+
+        match obj.get("type") {
+            None => {}
+            Some(Value::String(t)) => node.types.push(t.clone()),
+            Some(Value::Array(ts)) => {
+                for t in ts {
+                    node.types.push(t.as_str().ok_or("\"type\" takes strings")?.to_string());
+                }
+            }
+            Some(_) => return Err("\"type\" takes a string or an array of strings".to_string()),
+        }
+        if let Some(t) = node.types.iter().find(|t| !SCHEMA_TYPES.contains(&t.as_str())) {
+            return Err(format!("Unknown type {:?}", t));
+        }
+        if let Some(required) = obj.get("required") {
+            let required = required.as_array().ok_or("\"required\" takes an array")?;
+            for key in required {
+                node.required.push(key.as_str().ok_or("\"required\" takes strings")?.to_string());
+            }
+        }
+        if let Some(properties) = obj.get("properties") {
+            let properties = properties.as_object().ok_or("\"properties\" takes an object")?;
+            for (key, schema) in properties {
+                node.properties.push((key.clone(), SchemaNode::parse(schema)?));
+            }
+        }
+        node.closed = obj.get("additionalProperties") == Some(&Value::Bool(false));
+        if let Some(items) = obj.get("items") {
+            node.items = Some(Box::new(SchemaNode::parse(items)?));
+        }
+        if let Some(allowed) = obj.get("enum") {
+            node.allowed = Some(allowed.as_array().ok_or("\"enum\" takes an array")?.clone());
+        }
+        node.format = match obj.get("format").and_then(Value::as_str) {
+            Some("email") => Some(Format::Email),
+            Some("uri") | Some("url") => Some(Format::Url),
+            Some("date") => Some(Format::Date),
+            _ => None,
+        };
+        if let Some(pattern) = obj.get("pattern") {
+            let pattern = pattern.as_str().ok_or("\"pattern\" takes a string")?;
+            node.pattern = Some(Regex::new(pattern).map_err(|e| format!("Invalid regex: {}", e))?);
+        }
+        node.min_length = usize_of("minLength")?;
+        node.max_length = usize_of("maxLength")?;
+        node.minimum = f64_of("minimum")?;
+        node.maximum = f64_of("maximum")?;
+        Ok(node)
+    }
+
+    fn check(&self, value: &Value, path: &str, out: &mut Vec<String>) {
+        let label = |rule: &str| format!("{} {}", if path.is_empty() { "(record)" } else { path }, rule);
+
+        if !self.types.is_empty() && !self.types.iter().any(|t| type_matches(t, value)) {
+            out.push(label("type"));
+            return;
+        }
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(value) {
+                out.push(label("enum"));
+            }
+        }
+        match value {
+            Value::String(s) => {
+                if let Some(format) = self.format {
+                    if !format.matches(value) {
+                        out.push(label(&format!("format:{}", format.name())));
+                    }
+                }
+                if self.pattern.as_ref().is_some_and(|re| !re.is_match(s)) {
+                    out.push(label("pattern"));
+                }
+                let chars = s.chars().count();
+                if self.min_length.is_some_and(|min| chars < min) || self.max_length.is_some_and(|max| chars > max) {
+                    out.push(label("length"));
+                }
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                if self.minimum.is_some_and(|min| n < min) || self.maximum.is_some_and(|max| n > max) {
+                    out.push(label("range"));
+                }
+            }
+            Value::Object(obj) => {
+                let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+                for key in &self.required {
+                    if !obj.contains_key(key) {
+                        out.push(format!("{} required", child(key)));
+                    }
+                }
+                for (key, schema) in &self.properties {
+                    if let Some(v) = obj.get(key) {
+                        schema.check(v, &child(key), out);
+                    }
+                }
+                if self.closed {
+                    for key in obj.keys() {
+                        if !self.properties.iter().any(|(k, _)| k == key) {
+                            out.push(format!("{} additional", child(key)));
+                        }
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(schema) = &self.items {
+                    let path = format!("{}[]", path);
+                    for item in items {
+                        schema.check(item, &path, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn type_matches(t: &str, value: &Value) -> bool {
+    match t {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
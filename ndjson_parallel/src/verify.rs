@@ -0,0 +1,114 @@
+//! Post-write verification of a Parquet file against what parsing produced.
+//!
+//! The file is reopened and fully decoded, so a truncated or corrupted file on a
+//! flaky disk fails here instead of later:
+//! - the schema (names, types, nullability) must match the writer schema
+//! - the row count must equal the number of parsed records
+//! - every column's null count must match the NULLs parsing produced
+//!
+//! The expected counts are taken while records are parsed and appended to the
+//! column builders, not from the batches handed to the writer, so a row lost
+//! or nulled between parsing and the written batch is caught as well.
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+
+/// Rows and per-column null counts: accepted records and the NULLs appended
+/// for them while parsing, or what was decoded from the written file
+pub struct RowCounts {
+    pub rows: usize,
+    pub nulls: Vec<usize>,
+}
+
+impl RowCounts {
+    pub fn new(schema: &Schema) -> Self {
+        RowCounts {
+            rows: 0,
+            nulls: vec![0; schema.fields().len()],
+        }
+    }
+
+    pub fn merge(&mut self, other: &RowCounts) {
+        self.rows += other.rows;
+        for (total, n) in self.nulls.iter_mut().zip(&other.nulls) {
+            *total += n;
+        }
+    }
+
+    fn record(&mut self, batch: &RecordBatch) {
+        self.rows += batch.num_rows();
+        for (idx, column) in batch.columns().iter().enumerate() {
+            self.nulls[idx] += column.null_count();
+        }
+    }
+}
+
+/// Reopen `path` and compare it with `schema` and `expected`; returns every mismatch found
+pub fn verify_parquet(path: &str, schema: &Schema, expected: &RowCounts) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to reopen {}: {}", path, e))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| format!("Failed to read Parquet footer of {}: {}", path, e))?;
+
+    let written = builder.schema().clone();
+    let mut problems = Vec::new();
+    if written.fields().len() != schema.fields().len() {
+        problems.push(format!(
+            "{} columns written, {} expected",
+            written.fields().len(),
+            schema.fields().len()
+        ));
+    }
+    for (expected_field, written_field) in schema.fields().iter().zip(written.fields()) {
+        if expected_field.name() != written_field.name()
+            || expected_field.data_type() != written_field.data_type()
+            || expected_field.is_nullable() != written_field.is_nullable()
+        {
+            problems.push(format!(
+                "column {} {} written as {} {}",
+                expected_field.name(),
+                expected_field.data_type(),
+                written_field.name(),
+                written_field.data_type()
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(format!("Schema mismatch: {}", problems.join("; ")));
+    }
+
+    let footer_rows = builder.metadata().file_metadata().num_rows() as usize;
+    let reader = builder
+        .build()
+        .map_err(|e| format!("Failed to open {} for reading: {}", path, e))?;
+
+    let mut actual = RowCounts::new(schema);
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to decode {}: {}", path, e))?;
+        actual.record(&batch);
+    }
+
+    if footer_rows != expected.rows || actual.rows != expected.rows {
+        problems.push(format!(
+            "{} rows in footer, {} rows decoded, {} rows parsed",
+            footer_rows, actual.rows, expected.rows
+        ));
+    }
+    for (idx, field) in schema.fields().iter().enumerate() {
+        if actual.nulls[idx] != expected.nulls[idx] {
+            problems.push(format!(
+                "{} has {} nulls, expected {}",
+                field.name(),
+                actual.nulls[idx],
+                expected.nulls[idx]
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Content mismatch: {}", problems.join("; ")))
+    }
+}
@@ -0,0 +1,275 @@
+//! Parquet writer tuning, loaded from a JSON config file.
+//!
+//! File-level settings apply to every column; entries under `columns`
+//! override them for a single column:
+//!
+//! ```json
+//! {
+//!   "compression": "zstd",
+//!   "compression_level": 9,
+//!   "statistics": "page",
+//!   "max_row_group_size": 500000,
+//!   "columns": {
+//!     "id": { "bloom_filter": true, "dictionary": false },
+//!     "experience": { "compression": "zstd", "compression_level": 15, "statistics": "none" }
+//!   },
+//!   "sort_by": ["location_country", "job_title_role"],
+//!   "sort_memory_mb": 512
+//! }
+//! ```
+//!
+//! Bloom filters stay on for `id`, `linkedin_username` and `work_email` when
+//! `columns` configures other settings for them or lists other columns; set
+//! `"bloom_filter": false` on one of them to turn its filter off.
+//!
+//! With `sort_by` set, rows are externally sorted before writing so row-group
+//! min/max statistics can prune; runs that exceed `sort_memory_mb` are spilled
+//! to `spill_dir` (the system temp directory by default).
+//!
+//! `verify` (on by default) reopens the written file and checks it; see `verify.rs`.
+
+use arrow::datatypes::Schema;
+use parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use parquet::format::SortingColumn;
+use parquet::file::properties::{
+    EnabledStatistics, WriterProperties, DEFAULT_BLOOM_FILTER_FPP, DEFAULT_BLOOM_FILTER_NDV,
+    DEFAULT_MAX_ROW_GROUP_SIZE, DEFAULT_PAGE_SIZE,
+};
+use parquet::schema::types::ColumnPath;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// High-cardinality lookup keys that get a bloom filter unless configured otherwise
+const DEFAULT_BLOOM_COLUMNS: [&str; 3] = ["id", "linkedin_username", "work_email"];
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Brotli,
+    Lz4,
+    Lz4Raw,
+    Zstd,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticsLevel {
+    None,
+    Chunk,
+    Page,
+}
+
+/// Per-column overrides; anything left unset falls back to the file-level value
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnConfig {
+    pub compression: Option<Codec>,
+    pub compression_level: Option<i32>,
+    pub dictionary: Option<bool>,
+    pub statistics: Option<StatisticsLevel>,
+    pub bloom_filter: Option<bool>,
+    pub bloom_filter_fpp: Option<f64>,
+    pub bloom_filter_ndv: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriterConfig {
+    pub compression: Codec,
+    pub compression_level: Option<i32>,
+    pub dictionary: bool,
+    pub statistics: StatisticsLevel,
+    pub data_page_size: usize,
+    pub max_row_group_size: usize,
+    /// Flush the current row group once its encoded size reaches this many bytes
+    pub max_row_group_bytes: usize,
+    #[serde(deserialize_with = "merge_default_columns")]
+    pub columns: HashMap<String, ColumnConfig>,
+    pub sort_by: Vec<String>,
+    pub sort_memory_mb: usize,
+    pub spill_dir: Option<String>,
+    pub verify: bool,
+}
+
+fn default_columns() -> HashMap<String, ColumnConfig> {
+    DEFAULT_BLOOM_COLUMNS
+        .iter()
+        .map(|name| {
+            let column = ColumnConfig {
+                bloom_filter: Some(true),
+                ..Default::default()
+            };
+            (name.to_string(), column)
+        })
+        .collect()
+}
+
+/// Configured `columns` are merged over the defaults rather than replacing them,
+/// so a default bloom filter stays on unless `bloom_filter` is set explicitly
+fn merge_default_columns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, ColumnConfig>, D::Error> {
+    let configured = HashMap::<String, ColumnConfig>::deserialize(deserializer)?;
+    let mut columns = default_columns();
+    for (name, mut column) in configured {
+        if let Some(default) = columns.get(&name) {
+            column.bloom_filter = column.bloom_filter.or(default.bloom_filter);
+        }
+        columns.insert(name, column);
+    }
+    Ok(columns)
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        let columns = default_columns();
+
+        WriterConfig {
+            compression: Codec::Zstd,
+            compression_level: Some(3),
+            dictionary: true,
+            statistics: StatisticsLevel::Page,
+            data_page_size: DEFAULT_PAGE_SIZE,
+            max_row_group_size: DEFAULT_MAX_ROW_GROUP_SIZE,
+            max_row_group_bytes: 256 * 1024 * 1024,
+            columns,
+            sort_by: Vec::new(),
+            sort_memory_mb: 1024,
+            spill_dir: None,
+            verify: true,
+        }
+    }
+}
+
+impl WriterConfig {
+    /// Read a config file; keys missing from the file keep their defaults
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read writer config {}: {}", path, e))?;
+        let config = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid writer config {}: {}", path, e))?;
+        Ok(config)
+    }
+
+    /// `columns` entries naming a column the schema doesn't have; they have no effect.
+    /// Untouched default bloom filter entries are left out.
+    pub fn unknown_columns(&self, schema: &Schema) -> Vec<&str> {
+        let defaults = default_columns();
+        let mut unknown: Vec<&str> = self
+            .columns
+            .iter()
+            .filter(|(name, column)| schema.index_of(name).is_err() && defaults.get(*name) != Some(column))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        unknown.sort_unstable();
+        unknown
+    }
+
+    /// Directory that external sort runs are spilled to
+    pub fn spill_dir(&self) -> PathBuf {
+        match &self.spill_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::temp_dir(),
+        }
+    }
+
+    /// Build the `WriterProperties` handed to `ArrowWriter`
+    pub fn to_properties(&self, schema: &Schema) -> Result<WriterProperties, Box<dyn Error>> {
+        if self.max_row_group_size == 0 {
+            return Err("max_row_group_size must be greater than 0".into());
+        }
+        if !self.sort_by.is_empty() && self.sort_memory_mb == 0 {
+            return Err("sort_memory_mb must be greater than 0".into());
+        }
+
+        // Record the sort order in the file metadata so readers can rely on it
+        let mut sorting_columns = Vec::with_capacity(self.sort_by.len());
+        for name in &self.sort_by {
+            let idx = schema
+                .index_of(name)
+                .map_err(|_| format!("sort_by column {} is not in the schema", name))?;
+            sorting_columns.push(SortingColumn {
+                column_idx: idx as i32,
+                descending: false,
+                nulls_first: true,
+            });
+        }
+
+        let mut builder = WriterProperties::builder()
+            .set_compression(compression(self.compression, self.compression_level)?)
+            .set_dictionary_enabled(self.dictionary)
+            .set_statistics_enabled(statistics(self.statistics))
+            .set_data_page_size_limit(self.data_page_size)
+            .set_max_row_group_size(self.max_row_group_size)
+            .set_sorting_columns((!sorting_columns.is_empty()).then_some(sorting_columns));
+
+        for (name, column) in &self.columns {
+            let path = ColumnPath::from(name.as_str());
+
+            if column.compression.is_some() || column.compression_level.is_some() {
+                let codec = column.compression.unwrap_or(self.compression);
+                let level = column.compression_level.or(self.compression_level);
+                builder = builder.set_column_compression(path.clone(), compression(codec, level)?);
+            }
+            if let Some(enabled) = column.dictionary {
+                builder = builder.set_column_dictionary_enabled(path.clone(), enabled);
+            }
+            if let Some(level) = column.statistics {
+                builder = builder.set_column_statistics_enabled(path.clone(), statistics(level));
+            }
+
+            let bloom_tuned = column.bloom_filter_fpp.is_some() || column.bloom_filter_ndv.is_some();
+            if column.bloom_filter == Some(true) || (column.bloom_filter.is_none() && bloom_tuned) {
+                let fpp = column.bloom_filter_fpp.unwrap_or(DEFAULT_BLOOM_FILTER_FPP);
+                if !(fpp > 0.0 && fpp < 1.0) {
+                    return Err(format!("bloom_filter_fpp for {} must be between 0 and 1", name).into());
+                }
+                builder = builder
+                    .set_column_bloom_filter_enabled(path.clone(), true)
+                    .set_column_bloom_filter_fpp(path.clone(), fpp)
+                    .set_column_bloom_filter_ndv(
+                        path,
+                        column.bloom_filter_ndv.unwrap_or(DEFAULT_BLOOM_FILTER_NDV),
+                    );
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+fn compression(codec: Codec, level: Option<i32>) -> Result<Compression, Box<dyn Error>> {
+    let compression = match codec {
+        Codec::Uncompressed => Compression::UNCOMPRESSED,
+        Codec::Snappy => Compression::SNAPPY,
+        Codec::Lz4 => Compression::LZ4,
+        Codec::Lz4Raw => Compression::LZ4_RAW,
+        Codec::Zstd => Compression::ZSTD(match level {
+            Some(level) => ZstdLevel::try_new(level)?,
+            None => ZstdLevel::default(),
+        }),
+        Codec::Gzip => Compression::GZIP(match level {
+            Some(level) => GzipLevel::try_new(u32::try_from(level)?)?,
+            None => GzipLevel::default(),
+        }),
+        Codec::Brotli => Compression::BROTLI(match level {
+            Some(level) => BrotliLevel::try_new(u32::try_from(level)?)?,
+            None => BrotliLevel::default(),
+        }),
+    };
+    Ok(compression)
+}
+
+fn statistics(level: StatisticsLevel) -> EnabledStatistics {
+    match level {
+        StatisticsLevel::None => EnabledStatistics::None,
+        StatisticsLevel::Chunk => EnabledStatistics::Chunk,
+        StatisticsLevel::Page => EnabledStatistics::Page,
+    }
+}
@@ -0,0 +1,137 @@
+//! Presence co-occurrence for a chosen set of fields (up to 64).
+//!
+//! Each object is reduced to a bitmask of which selected fields hold a
+//! non-empty value. The masks feed a pairwise matrix (`counts[i][j]` = objects
+//! with both fields `i` and `j`; the diagonal is each field's own fill) and a
+//! count per distinct mask, e.g. "work_email + mobile_phone, no personal email".
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json, to_writer_pretty};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Result, Write},
+};
+
+pub const MAX_FIELDS: usize = 64;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CoOccurrence {
+    /// Row-major `n * n` pair counts, allocated on the first object
+    matrix: Vec<u64>,
+    /// Presence bitmask → objects with exactly that set of fields
+    patterns: HashMap<u64, u64>,
+}
+
+fn bits(mask: u64) -> impl Iterator<Item = usize> {
+    (0..MAX_FIELDS).filter(move |i| mask & (1 << i) != 0)
+}
+
+impl CoOccurrence {
+    /// Count one object whose non-empty selected fields are `mask`
+    pub fn add(&mut self, mask: u64, n: usize) {
+        if self.matrix.is_empty() {
+            self.matrix = vec![0; n * n];
+        }
+        for i in bits(mask) {
+            for j in bits(mask) {
+                self.matrix[i * n + j] += 1;
+            }
+        }
+        *self.patterns.entry(mask).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &CoOccurrence) {
+        if self.matrix.is_empty() {
+            self.matrix = other.matrix.clone();
+        } else {
+            for (a, b) in self.matrix.iter_mut().zip(&other.matrix) {
+                *a += b;
+            }
+        }
+        for (mask, count) in &other.patterns {
+            *self.patterns.entry(*mask).or_default() += count;
+        }
+    }
+
+    fn count(&self, n: usize, i: usize, j: usize) -> u64 {
+        self.matrix.get(i * n + j).copied().unwrap_or(0)
+    }
+
+    /// Patterns, most common first
+    fn sorted_patterns(&self) -> Vec<(u64, u64)> {
+        let mut patterns: Vec<(u64, u64)> = self.patterns.iter().map(|(m, c)| (*m, *c)).collect();
+        patterns.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        patterns
+    }
+}
+
+fn pattern_label(fields: &[String], mask: u64) -> String {
+    if mask == 0 {
+        return "(none)".to_string();
+    }
+    bits(mask).map(|i| fields[i].as_str()).collect::<Vec<_>>().join("+")
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { (part as f64 * 10000.0 / whole as f64).round() / 100.0 }
+}
+
+/// `.csv` writes the count matrix plus `<stem>_patterns.csv`; anything else
+/// writes one heatmap-ready JSON document
+pub fn write_co_occurrence(path: &str, fields: &[String], objects: u64, co: &CoOccurrence) -> Result<()> {
+    let n = fields.len();
+
+    if let Some(stem) = path.strip_suffix(".csv") {
+        let cell = |s: &str| {
+            if s.contains([',', '"', '\n', '\r']) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s.to_string() }
+        };
+
+        let mut w = BufWriter::new(File::create(path)?);
+        let header: Vec<String> = fields.iter().map(|f| cell(f)).collect();
+        writeln!(w, "field,{}", header.join(","))?;
+        for (i, field) in fields.iter().enumerate() {
+            let row: Vec<String> = (0..n).map(|j| co.count(n, i, j).to_string()).collect();
+            writeln!(w, "{},{}", cell(field), row.join(","))?;
+        }
+        w.flush()?;
+
+        let mut w = BufWriter::new(File::create(format!("{stem}_patterns.csv"))?);
+        writeln!(w, "pattern,count,percent")?;
+        for (mask, count) in co.sorted_patterns() {
+            writeln!(w, "{},{},{:.2}", cell(&pattern_label(fields, mask)), count, percent(count, objects))?;
+        }
+        return w.flush();
+    }
+
+    // conditional[i][j] = P(field j present | field i present)
+    let counts: Vec<Vec<u64>> = (0..n).map(|i| (0..n).map(|j| co.count(n, i, j)).collect()).collect();
+    let conditional: Vec<Vec<f64>> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let base = row[i];
+            row.iter().map(|c| if base == 0 { 0.0 } else { *c as f64 / base as f64 }).collect()
+        })
+        .collect();
+    let patterns: Vec<Value> = co
+        .sorted_patterns()
+        .into_iter()
+        .map(|(mask, count)| {
+            let present: Vec<&str> = bits(mask).map(|i| fields[i].as_str()).collect();
+            json!({ "fields": present, "count": count, "percent": percent(count, objects) })
+        })
+        .collect();
+
+    let doc = json!({
+        "fields": fields,
+        "objects": objects,
+        "present": (0..n).map(|i| co.count(n, i, i)).collect::<Vec<_>>(),
+        "counts": counts,
+        "conditional": conditional,
+        "patterns": patterns,
+    });
+    let mut w = BufWriter::new(File::create(path)?);
+    to_writer_pretty(&mut w, &doc)?;
+    w.flush()
+}
@@ -0,0 +1,112 @@
+//! Completeness score histogram and per-bucket id lists. The score itself
+//! and its config live in the shared `record_completeness` crate.
+
+use record_completeness::CompletenessConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json, to_writer_pretty};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Result, Write},
+};
+
+/// Score histogram, mergeable across accumulators and shards
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Completeness {
+    histogram: Vec<u64>,
+    sum: f64,
+}
+
+impl Completeness {
+    pub fn add(&mut self, score: f64, bucket: usize, buckets: usize) {
+        if self.histogram.is_empty() {
+            self.histogram = vec![0; buckets];
+        }
+        self.histogram[bucket] += 1;
+        self.sum += score;
+    }
+
+    pub fn merge(&mut self, other: &Completeness) {
+        if self.histogram.is_empty() {
+            self.histogram = other.histogram.clone();
+        } else {
+            for (a, b) in self.histogram.iter_mut().zip(&other.histogram) {
+                *a += b;
+            }
+        }
+        self.sum += other.sum;
+    }
+
+    pub fn scored(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let n = self.scored();
+        if n == 0 { 0.0 } else { self.sum / n as f64 }
+    }
+
+    /// One text bar per bucket, scaled to the largest bucket
+    pub fn print(&self, config: &CompletenessConfig) {
+        let n = self.scored().max(1);
+        let peak = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (bucket, count) in self.histogram.iter().enumerate() {
+            let bar = "█".repeat((count * 40 / peak) as usize);
+            println!(
+                "  {} {:>6.2}% {:>12} {bar}",
+                config.bucket_label(bucket),
+                *count as f64 * 100.0 / n as f64,
+                count
+            );
+        }
+    }
+
+    pub fn write_json(&self, path: &str, config: &CompletenessConfig) -> Result<()> {
+        let n = self.scored();
+        let buckets: Vec<Value> = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(bucket, count)| {
+                let percent = if n == 0 { 0.0 } else { (*count as f64 * 10000.0 / n as f64).round() / 100.0 };
+                json!({ "range": config.bucket_label(bucket), "count": count, "percent": percent })
+            })
+            .collect();
+        let doc = json!({
+            "scored_records": n,
+            "mean": self.mean(),
+            "weights": config.weights,
+            "buckets": buckets,
+        });
+        let mut w = BufWriter::new(File::create(path)?);
+        to_writer_pretty(&mut w, &doc)?;
+        w.flush()
+    }
+}
+
+/// One `<dir>/completeness_<range>.txt` id list per bucket
+pub struct BucketIds {
+    writers: Vec<BufWriter<File>>,
+}
+
+impl BucketIds {
+    pub fn create(dir: &str, config: &CompletenessConfig) -> Result<BucketIds> {
+        fs::create_dir_all(dir)?;
+        let writers = (0..config.buckets)
+            .map(|b| File::create(format!("{dir}/completeness_{}.txt", config.bucket_label(b))).map(BufWriter::new))
+            .collect::<Result<_>>()?;
+        Ok(BucketIds { writers })
+    }
+
+    pub fn write(&mut self, ids: &[Vec<String>]) -> Result<()> {
+        for (w, bucket) in self.writers.iter_mut().zip(ids) {
+            for id in bucket {
+                writeln!(w, "{id}")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writers.iter_mut().try_for_each(|w| w.flush())
+    }
+}
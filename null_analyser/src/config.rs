@@ -0,0 +1,259 @@
+//! Value distribution settings, loaded from a JSON config file (`--config`).
+//!
+//! ```json
+//! {
+//!   "output": "value_distributions.json",
+//!   "top_k": 100,
+//!   "sort": "count",
+//!   "normalize": { "trim": true },
+//!   "fields": [
+//!     "gender",
+//!     { "path": "job_title", "lowercase": true, "top_k": 500 },
+//!     { "path": "birth_year", "bucket": 10 },
+//!     "emails[].type"
+//!   ],
+//!   "co_occurrence": {
+//!     "fields": ["work_email", "personal_emails", "mobile_phone", "linkedin_url"],
+//!     "output": "co_occurrence.json"
+//!   },
+//!   "completeness": {
+//!     "weights": { "full_name": 1, "work_email": 3, "mobile_phone": 2 },
+//!     "buckets": 10
+//!   }
+//! }
+//! ```
+//!
+//! `normalize` sets the defaults for every field; a field entry can override
+//! `lowercase`, `trim` and `bucket` (numeric bucket width: 1987 with width 10
+//! is counted as `1980-1989`). `sort` is `count` (most frequent first) or `value`.
+//! `co_occurrence` lists fields whose joint presence is counted (see
+//! `co_occurrence.rs`); the output is CSV when it ends in `.csv`, JSON otherwise.
+//! `completeness` sets the per-record score weights and histogram buckets (see
+//! the `record_completeness` crate).
+
+use crate::co_occurrence::MAX_FIELDS;
+use record_completeness::CompletenessConfig;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Count,
+    Value,
+}
+
+impl SortOrder {
+    pub fn parse(name: &str) -> Option<SortOrder> {
+        match name {
+            "count" => Some(SortOrder::Count),
+            "value" => Some(SortOrder::Value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Normalize {
+    pub lowercase: bool,
+    pub trim: bool,
+    pub bucket: Option<f64>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FieldOverrides {
+    path: String,
+    lowercase: Option<bool>,
+    trim: Option<bool>,
+    bucket: Option<f64>,
+    top_k: Option<usize>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum FieldEntry {
+    Path(String),
+    Spec(FieldOverrides),
+}
+
+/// Resolved settings for one analysed field
+pub struct FieldSpec {
+    pub normalize: Normalize,
+    pub top_k: usize,
+}
+
+impl FieldSpec {
+    /// Counters kept per field; extra headroom keeps the reported top `k` accurate
+    pub fn capacity(&self) -> usize {
+        self.top_k * 4
+    }
+}
+
+/// Fields with value counts, co-occurrence fields by bit index, and the
+/// completeness scoring
+pub struct FieldSelection {
+    pub values: HashMap<String, FieldSpec>,
+    pub presence: HashMap<String, usize>,
+    pub completeness: CompletenessConfig,
+    /// Keep record ids per completeness bucket
+    pub collect_ids: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoOccurrenceConfig {
+    pub fields: Vec<String>,
+    pub output: String,
+}
+
+impl Default for CoOccurrenceConfig {
+    fn default() -> Self {
+        CoOccurrenceConfig {
+            fields: Vec::new(),
+            output: "co_occurrence.json".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub fields: Vec<FieldEntry>,
+    pub top_k: usize,
+    pub normalize: Normalize,
+    pub sort: SortOrder,
+    pub output: String,
+    pub co_occurrence: CoOccurrenceConfig,
+    pub completeness: CompletenessConfig,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            fields: ["gender", "location_country", "location_continent", "job_title", "version_status.status"]
+                .into_iter()
+                .map(|f| FieldEntry::Path(f.to_string()))
+                .collect(),
+            top_k: 1000,
+            normalize: Normalize::default(),
+            sort: SortOrder::Count,
+            output: "value_distributions.json".to_string(),
+            co_occurrence: CoOccurrenceConfig::default(),
+            completeness: CompletenessConfig::default(),
+        }
+    }
+}
+
+impl AnalysisConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read config {path}: {e}"))?;
+        let config: AnalysisConfig =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid config {path}: {e}"))?;
+        config.validate().map_err(|e| format!("Invalid config {path}: {e}"))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.top_k == 0 {
+            return Err("top_k must be at least 1".to_string());
+        }
+        let buckets = self.fields.iter().filter_map(|f| match f {
+            FieldEntry::Spec(spec) => spec.bucket,
+            FieldEntry::Path(_) => None,
+        });
+        for width in buckets.chain(self.normalize.bucket) {
+            if width.is_nan() || width <= 0.0 {
+                return Err(format!("bucket width must be positive, got {width}"));
+            }
+        }
+        let co_fields = &self.co_occurrence.fields;
+        if co_fields.len() > MAX_FIELDS {
+            return Err(format!("co_occurrence supports at most {MAX_FIELDS} fields, got {}", co_fields.len()));
+        }
+        if let Some((i, field)) = co_fields.iter().enumerate().find(|(i, f)| co_fields[..*i].contains(f)) {
+            return Err(format!("co_occurrence field {field} is listed twice (entry {})", i + 1));
+        }
+        self.completeness.validate()
+    }
+
+    /// Everything the per-object pass needs to know about the configured fields
+    pub fn selection(&self) -> FieldSelection {
+        FieldSelection {
+            values: self.field_specs(),
+            presence: self.co_occurrence.fields.iter().enumerate().map(|(i, f)| (f.clone(), i)).collect(),
+            completeness: self.completeness.clone(),
+            collect_ids: false,
+        }
+    }
+
+    /// Field path → resolved settings, with defaults filled in
+    pub fn field_specs(&self) -> HashMap<String, FieldSpec> {
+        self.fields
+            .iter()
+            .map(|entry| match entry {
+                FieldEntry::Path(path) => (
+                    path.clone(),
+                    FieldSpec {
+                        normalize: self.normalize.clone(),
+                        top_k: self.top_k,
+                    },
+                ),
+                FieldEntry::Spec(spec) => (
+                    spec.path.clone(),
+                    FieldSpec {
+                        normalize: Normalize {
+                            lowercase: spec.lowercase.unwrap_or(self.normalize.lowercase),
+                            trim: spec.trim.unwrap_or(self.normalize.trim),
+                            bucket: spec.bucket.or(self.normalize.bucket),
+                        },
+                        top_k: spec.top_k.filter(|k| *k > 0).unwrap_or(self.top_k),
+                    },
+                ),
+            })
+            .collect()
+    }
+}
+
+impl Normalize {
+    /// The counted form of a scalar value
+    pub fn apply(&self, v: &Value) -> String {
+        if let Some(width) = self.bucket {
+            let number = match v {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => s.trim().parse::<f64>().ok(),
+                _ => None,
+            };
+            if let Some(x) = number {
+                return bucket_label(x, width);
+            }
+        }
+
+        let mut text = match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        if self.trim {
+            text = text.trim().to_string();
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+        text
+    }
+}
+
+/// `1980-1989` for integer widths, `[0.5, 1)` otherwise
+fn bucket_label(x: f64, width: f64) -> String {
+    let lo = (x / width).floor() * width;
+    if width.fract() == 0.0 {
+        format!("{}-{}", lo as i64, (lo + width) as i64 - 1)
+    } else {
+        format!("[{lo}, {})", lo + width)
+    }
+}
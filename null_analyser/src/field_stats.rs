@@ -0,0 +1,258 @@
+use crate::sketch::HyperLogLog;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// JSON types tracked per field; numbers split into int / float
+pub const TYPE_NAMES: [&str; 6] = ["string", "int", "float", "bool", "array", "object"];
+
+/// Array length buckets: 0, 1, 2, 3-4, 5-8, ... , 513+
+const LENGTH_BUCKETS: usize = 12;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldStats {
+    /// Objects with the field at least once / with at least one non-empty value
+    pub rows_present: u64,
+    pub rows_non_empty: u64,
+    /// Last row id that counted towards `rows_present` / `rows_non_empty`
+    #[serde(skip)]
+    last_row: u64,
+    #[serde(skip)]
+    last_non_empty_row: u64,
+    /// Occurrences; nested paths count every array element
+    pub present: u64,
+    pub null: u64,
+    pub empty: u64,
+    pub non_empty: u64,
+    /// Indexed like `TYPE_NAMES`
+    pub types: [u64; 6],
+    pub min_str_len: usize,
+    pub max_str_len: usize,
+    pub array_lengths: [u64; LENGTH_BUCKETS],
+    pub num_count: u64,
+    // JSON has no infinities, so the "no numbers yet" bounds are left out
+    #[serde(skip_serializing_if = "is_infinite", default = "positive_infinity")]
+    pub num_min: f64,
+    #[serde(skip_serializing_if = "is_infinite", default = "negative_infinity")]
+    pub num_max: f64,
+    pub num_sum: f64,
+    /// Distinct scalar values (strings, numbers, bools)
+    pub distinct: HyperLogLog,
+}
+
+fn is_infinite(x: &f64) -> bool {
+    x.is_infinite()
+}
+
+fn positive_infinity() -> f64 {
+    f64::INFINITY
+}
+
+fn negative_infinity() -> f64 {
+    f64::NEG_INFINITY
+}
+
+impl Default for FieldStats {
+    fn default() -> Self {
+        FieldStats {
+            rows_present: 0,
+            rows_non_empty: 0,
+            last_row: 0,
+            last_non_empty_row: 0,
+            present: 0,
+            null: 0,
+            empty: 0,
+            non_empty: 0,
+            types: [0; 6],
+            min_str_len: usize::MAX,
+            max_str_len: 0,
+            array_lengths: [0; LENGTH_BUCKETS],
+            num_count: 0,
+            num_min: f64::INFINITY,
+            num_max: f64::NEG_INFINITY,
+            num_sum: 0.0,
+            distinct: HyperLogLog::default(),
+        }
+    }
+}
+
+/// Index into `TYPE_NAMES`, `None` for null
+pub fn type_index(v: &Value) -> Option<usize> {
+    match v {
+        Value::Null => None,
+        Value::String(_) => Some(0),
+        Value::Number(n) if n.is_i64() || n.is_u64() => Some(1),
+        Value::Number(_) => Some(2),
+        Value::Bool(_) => Some(3),
+        Value::Array(_) => Some(4),
+        Value::Object(_) => Some(5),
+    }
+}
+
+fn length_bucket(len: usize) -> usize {
+    match len {
+        0 => 0,
+        1 => 1,
+        // 2 → 2, 3-4 → 3, 5-8 → 4, ...
+        n => ((usize::BITS - (n - 1).leading_zeros()) as usize + 1).min(LENGTH_BUCKETS - 1),
+    }
+}
+
+fn length_bucket_label(bucket: usize) -> String {
+    match bucket {
+        0 => "0".to_string(),
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        b if b == LENGTH_BUCKETS - 1 => format!("{}+", (1usize << (b - 2)) + 1),
+        b => format!("{}-{}", (1usize << (b - 2)) + 1, 1usize << (b - 1)),
+    }
+}
+
+pub fn is_empty_value(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
+}
+
+impl FieldStats {
+    /// Record one occurrence; `row` is a non-zero id unique to the object within
+    /// this accumulator, so repeated occurrences in one object count as one row
+    pub fn observe(&mut self, row: u64, v: &Value) {
+        self.present += 1;
+        if self.last_row != row {
+            self.last_row = row;
+            self.rows_present += 1;
+        }
+
+        if !is_empty_value(v) && self.last_non_empty_row != row {
+            self.last_non_empty_row = row;
+            self.rows_non_empty += 1;
+        }
+
+        if is_empty_value(v) {
+            if v.is_null() {
+                self.null += 1;
+            } else {
+                self.empty += 1;
+            }
+        } else {
+            self.non_empty += 1;
+        }
+
+        if let Some(t) = type_index(v) {
+            self.types[t] += 1;
+        }
+
+        match v {
+            Value::String(s) => {
+                self.distinct.insert(s.as_bytes());
+                let len = s.chars().count();
+                self.min_str_len = self.min_str_len.min(len);
+                self.max_str_len = self.max_str_len.max(len);
+            }
+            Value::Number(n) => {
+                self.distinct.insert(n.to_string().as_bytes());
+                if let Some(x) = n.as_f64() {
+                    self.num_count += 1;
+                    self.num_min = self.num_min.min(x);
+                    self.num_max = self.num_max.max(x);
+                    self.num_sum += x;
+                }
+            }
+            Value::Array(items) => self.array_lengths[length_bucket(items.len())] += 1,
+            Value::Bool(b) => self.distinct.insert(if *b { b"true" } else { b"false" }),
+            _ => {}
+        }
+    }
+
+    pub fn merge(&mut self, other: &FieldStats) {
+        self.rows_present += other.rows_present;
+        self.rows_non_empty += other.rows_non_empty;
+        self.present += other.present;
+        self.null += other.null;
+        self.empty += other.empty;
+        self.non_empty += other.non_empty;
+        for (a, b) in self.types.iter_mut().zip(other.types) {
+            *a += b;
+        }
+        self.min_str_len = self.min_str_len.min(other.min_str_len);
+        self.max_str_len = self.max_str_len.max(other.max_str_len);
+        for (a, b) in self.array_lengths.iter_mut().zip(other.array_lengths) {
+            *a += b;
+        }
+        self.num_count += other.num_count;
+        self.num_min = self.num_min.min(other.num_min);
+        self.num_max = self.num_max.max(other.num_max);
+        self.num_sum += other.num_sum;
+        self.distinct.merge(&other.distinct);
+    }
+
+    /// Bitmask of the non-null types seen, bit `i` for `TYPE_NAMES[i]`
+    pub fn type_mask(&self) -> u8 {
+        self.types
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| **n > 0)
+            .fold(0, |mask, (i, _)| mask | (1 << i))
+    }
+
+    pub fn to_json(&self) -> Value {
+        let types: Map<String, Value> = TYPE_NAMES
+            .iter()
+            .zip(self.types)
+            .filter(|(_, n)| *n > 0)
+            .map(|(name, n)| (name.to_string(), json!(n)))
+            .collect();
+
+        let mut out = json!({
+            "rows_present": self.rows_present,
+            "rows_non_empty": self.rows_non_empty,
+            "present": self.present,
+            "null": self.null,
+            "empty": self.empty,
+            "non_empty": self.non_empty,
+            "types": types,
+        });
+
+        let distinct = self.distinct.estimate();
+        if distinct > 0 {
+            out["distinct_estimate"] = json!(distinct);
+        }
+
+        if self.types[0] > 0 {
+            out["string_length"] = json!({ "min": self.min_str_len, "max": self.max_str_len });
+        }
+        if self.types[4] > 0 {
+            // A list rather than a map so buckets stay in length order
+            let histogram: Vec<Value> = self
+                .array_lengths
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| **n > 0)
+                .map(|(b, n)| json!({ "length": length_bucket_label(b), "count": n }))
+                .collect();
+            out["array_length_histogram"] = Value::Array(histogram);
+        }
+        if self.num_count > 0 {
+            out["numeric"] = json!({
+                "min": self.num_min,
+                "max": self.num_max,
+                "mean": self.num_sum / self.num_count as f64,
+            });
+        }
+        out
+    }
+}
+
+/// `"string+int"` style label for a type mask
+pub fn mask_label(mask: u8) -> String {
+    let names: Vec<&str> = TYPE_NAMES
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    names.join("+")
+}
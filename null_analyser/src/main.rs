@@ -1,138 +1,425 @@
-use flate2::read::GzDecoder;
+mod co_occurrence;
+mod completeness;
+mod config;
+mod field_stats;
+mod report;
+mod sampling;
+mod sketch;
+mod state;
+
+use co_occurrence::{CoOccurrence, write_co_occurrence};
+use completeness::{BucketIds, Completeness};
+use config::{AnalysisConfig, FieldEntry, FieldSelection, FieldSpec, SortOrder};
 use rayon::prelude::*;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use serde_json::to_writer_pretty;
+use field_stats::{FieldStats, is_empty_value, mask_label};
+use report::{ReportFormat, write_field_report};
+use sampling::{Sampling, ShardLines};
+use sketch::SpaceSaving;
+use state::State;
 use std::{
+    collections::BTreeMap,
     collections::HashMap,
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader, BufWriter, Write, Result},
-    sync::LazyLock,
+    fs::{self, File},
+    path::Path,
+    io::{BufWriter, Result},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 
-static ANALYTIC_FIELDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
-    [
-        "gender",
-        "location_country",
-        "location_continent",
-        "job_title",
-        "version_status.status",
-    ]
-    .into_iter()
-    .collect()
-});
-
-#[derive(Default, Clone)]
-struct FieldStats {
-    present: u64,
-    null: u64,
-    empty: u64,
-    non_empty: u64,
-}
-
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 struct FileStats {
     rows: u64,
+    /// Objects the analysed rows stand for: `rows` on a full scan, scaled up when sampling
+    estimated_rows: f64,
     total_fields: u64,
     null_or_empty_fields: u64,
+    /// Keyed by full path: `gender`, `version_status.status`, `emails[].type`.
+    /// Nested paths count every occurrence, so `emails[].type` can exceed `rows`.
     per_field: HashMap<String, FieldStats>,
-    value_counts: HashMap<String, HashMap<String, u32>>,
+    /// Bounded top-k sketches for the configured fields
+    value_counts: HashMap<String, SpaceSaving>,
+    /// Joint presence of the co-occurrence fields
+    co_occurrence: CoOccurrence,
+    /// Co-occurrence fields seen non-empty in the current object
+    #[serde(skip)]
+    row_mask: u64,
+    completeness: Completeness,
+    /// Record ids per completeness bucket, only kept for `--completeness-ids`
+    #[serde(skip)]
+    bucket_ids: Vec<Vec<String>>,
 }
 
-fn is_empty_value(v: &Value) -> bool {
-    match v {
-        Value::Null => true,
-        Value::Array(a) => a.is_empty(),
-        Value::Object(o) => o.is_empty(),
-        _ => false,
+impl FileStats {
+    fn merge(&mut self, other: FileStats) {
+        self.rows += other.rows;
+        self.estimated_rows += other.estimated_rows;
+        self.total_fields += other.total_fields;
+        self.null_or_empty_fields += other.null_or_empty_fields;
+
+        for (k, v) in other.per_field {
+            self.per_field.entry(k).or_default().merge(&v);
+        }
+
+        for (field, counts) in other.value_counts {
+            match self.value_counts.get_mut(&field) {
+                Some(entry) => entry.merge(&counts),
+                None => {
+                    self.value_counts.insert(field, counts);
+                }
+            }
+        }
+        self.co_occurrence.merge(&other.co_occurrence);
+        self.completeness.merge(&other.completeness);
+
+        if self.bucket_ids.is_empty() {
+            self.bucket_ids = other.bucket_ids;
+        } else {
+            for (ids, other_ids) in self.bucket_ids.iter_mut().zip(other.bucket_ids) {
+                ids.extend(other_ids);
+            }
+        }
+    }
+
+    /// Score the whole object and file it under its completeness bucket
+    fn record_completeness(&mut self, obj: &serde_json::Map<String, Value>, fields: &FieldSelection) {
+        let config = &fields.completeness;
+        let Some(score) = config.score(obj) else {
+            return;
+        };
+        let bucket = config.bucket(score);
+        self.completeness.add(score, bucket, config.buckets);
+
+        if fields.collect_ids {
+            let id = match obj.get(&config.id_field) {
+                Some(Value::String(s)) => s.clone(),
+                Some(v) if !v.is_null() => v.to_string(),
+                _ => return,
+            };
+            if self.bucket_ids.is_empty() {
+                self.bucket_ids = vec![Vec::new(); config.buckets];
+            }
+            self.bucket_ids[bucket].push(id);
+        }
+    }
+
+    /// Record `v` under `path`, then descend into object keys (`path.key`)
+    /// and array elements (`path[]`). `self.rows` doubles as the row id.
+    fn record(&mut self, path: &str, v: &Value, fields: &FieldSelection) {
+        // Per-field presence / null / empty / type stats
+        let row = self.rows;
+        self.per_field.entry(path.to_string()).or_default().observe(row, v);
+
+        if let Some(bit) = fields.presence.get(path)
+            && !is_empty_value(v)
+        {
+            self.row_mask |= 1 << bit;
+        }
+
+        match v {
+            Value::Object(obj) => {
+                for (k, child) in obj {
+                    self.record(&format!("{path}.{k}"), child, fields);
+                }
+            }
+            Value::Array(items) => {
+                let item_path = format!("{path}[]");
+                for item in items {
+                    self.record(&item_path, item, fields);
+                }
+            }
+            // Config-driven value counts
+            Value::String(_) | Value::Number(_) | Value::Bool(_) => {
+                if let Some(spec) = fields.values.get(path) {
+                    self.value_counts
+                        .entry(path.to_string())
+                        .or_insert_with(|| SpaceSaving::new(spec.capacity()))
+                        .insert(&spec.normalize.apply(v));
+                }
+            }
+            _ => {}
+        }
     }
 }
 
+/// Top values per field with counts and percentages of the field's values.
+/// `max_overestimate` is non-zero only once a field outgrew its sketch.
 fn write_value_distributions_json(
     path: &str,
-    value_counts: &HashMap<String, HashMap<String, u32>>,
+    value_counts: &HashMap<String, SpaceSaving>,
+    per_field: &HashMap<String, FieldStats>,
+    fields: &HashMap<String, FieldSpec>,
+    sort: SortOrder,
 ) -> Result<()> {
     let file = File::create(path)?;
     let writer = BufWriter::new(file);
 
+    let distributions: serde_json::Map<String, Value> = value_counts
+        .iter()
+        .map(|(field, sketch)| {
+            let k = fields.get(field).map_or(usize::MAX, |spec| spec.top_k);
+            let mut top = sketch.top(k);
+            if sort == SortOrder::Value {
+                top.sort_by(|a, b| a.0.cmp(b.0));
+            }
+
+            let total = sketch.total();
+            let values: Vec<Value> = top
+                .into_iter()
+                .map(|(value, count, error)| {
+                    let percent = (count as f64 * 10000.0 / total as f64).round() / 100.0;
+                    json!({ "value": value, "count": count, "percent": percent, "max_overestimate": error })
+                })
+                .collect();
+            let entry = json!({
+                "total": total,
+                "distinct_estimate": per_field.get(field).map_or(0, |s| s.distinct.estimate()),
+                "exact": sketch.is_exact(),
+                "values": values,
+            });
+            (field.clone(), entry)
+        })
+        .collect();
+
     // Pretty JSON output
-    to_writer_pretty(writer, value_counts)?;
+    to_writer_pretty(writer, &distributions)?;
+
+    Ok(())
+}
+
+/// Per-field stats, with the per-shard type sets of any field whose types drift
+fn write_field_stats_json(
+    path: &str,
+    per_field: &HashMap<String, FieldStats>,
+    drift: &BTreeMap<String, BTreeMap<String, Vec<String>>>,
+) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+
+    let stats: serde_json::Map<String, Value> = per_field
+        .iter()
+        .map(|(field, s)| {
+            let mut entry = s.to_json();
+            if let Some(shards) = drift.get(field) {
+                entry["schema_drift"] = json!(shards);
+            }
+            (field.clone(), entry)
+        })
+        .collect();
+    to_writer_pretty(writer, &stats)?;
 
     Ok(())
 }
 
-fn analyze_file_parallel(path: &str) -> FileStats {
-    let file = File::open(path).unwrap();
-    let decoder = GzDecoder::new(file);
-    let reader = BufReader::new(decoder);
+/// Fields whose set of non-null types differs between shards, as
+/// field → type label (`string`, `string+int`, ...) → shards with that set
+fn schema_drift(shard_types: &HashMap<String, Vec<(String, u8)>>) -> BTreeMap<String, BTreeMap<String, Vec<String>>> {
+    let mut drift = BTreeMap::new();
+    for (field, shards) in shard_types {
+        let mut by_mask: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (shard, mask) in shards {
+            by_mask.entry(mask_label(*mask)).or_default().push(shard.clone());
+        }
+        if by_mask.len() > 1 {
+            drift.insert(field.clone(), by_mask);
+        }
+    }
+    drift
+}
 
-    reader
-        .lines()
-        .par_bridge()
-        .filter_map(Result::ok)
+fn analyze_lines(lines: impl ParallelIterator<Item = String>, fields: &FieldSelection) -> FileStats {
+    lines
         .fold(FileStats::default, |mut acc, line| {
             if let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(&line) {
                 acc.rows += 1;
 
-                for (k, v) in obj {
+                for (k, v) in &obj {
                     acc.total_fields += 1;
-
-                    // Per-field presence / null / empty stats
-                    let entry = acc.per_field.entry(k.clone()).or_default();
-                    entry.present += 1;
-
-                    if is_empty_value(&v) {
+                    if is_empty_value(v) {
                         acc.null_or_empty_fields += 1;
-                        if v.is_null() {
-                            entry.null += 1;
-                        } else {
-                            entry.empty += 1;
-                        }
-                    } else {
-                        entry.non_empty += 1;
-                    }
-
-                    // Config-driven value counts
-                    if ANALYTIC_FIELDS.contains(k.as_str()) {
-                        if let Some(value) = v.as_str() {
-                            let field_map = acc.value_counts.entry(k).or_insert_with(HashMap::new);
-
-                            *field_map.entry(value.to_string()).or_insert(0) += 1;
-                        }
                     }
+                    acc.record(k, v, fields);
+                }
+                if !fields.presence.is_empty() {
+                    let mask = std::mem::take(&mut acc.row_mask);
+                    acc.co_occurrence.add(mask, fields.presence.len());
                 }
+                acc.record_completeness(&obj, fields);
             }
             acc
         })
         .reduce(FileStats::default, |mut a, b| {
-            a.rows += b.rows;
-            a.total_fields += b.total_fields;
-            a.null_or_empty_fields += b.null_or_empty_fields;
-
-            // Merge per_field stats
-            for (k, v) in b.per_field {
-                let e = a.per_field.entry(k).or_default();
-                e.present += v.present;
-                e.null += v.null;
-                e.empty += v.empty;
-                e.non_empty += v.non_empty;
-            }
-
-            // Merge value_counts
-            for (field, counts) in b.value_counts {
-                let entry = a.value_counts.entry(field).or_insert_with(HashMap::new);
-                for (val, count) in counts {
-                    *entry.entry(val).or_insert(0) += count;
+            a.merge(b);
+            a
+        })
+}
+
+fn analyze_file_parallel(path: &str, fields: &FieldSelection, sampling: &Sampling) -> FileStats {
+    let (reader, consumed) = sampling::open_shard(path).unwrap();
+    let mut lines = ShardLines::new(reader);
+
+    // Sampled rows are scaled by lines (or bytes) seen over lines (or bytes) analysed
+    let (mut stats, scale) = match *sampling {
+        Sampling::Full => {
+            let stats = analyze_lines(lines.by_ref().par_bridge(), fields);
+            (stats, 1.0)
+        }
+        Sampling::Bernoulli { .. } => {
+            let seen = AtomicU64::new(0);
+            let kept = AtomicU64::new(0);
+            let sampled = lines.by_ref().par_bridge().filter(|line| {
+                seen.fetch_add(1, Ordering::Relaxed);
+                let keep = sampling.keep(line);
+                if keep {
+                    kept.fetch_add(1, Ordering::Relaxed);
                 }
+                keep
+            });
+            let stats = analyze_lines(sampled, fields);
+            let kept = kept.load(Ordering::Relaxed).max(1);
+            (stats, seen.load(Ordering::Relaxed) as f64 / kept as f64)
+        }
+        Sampling::Reservoir { size, seed } => {
+            let shard_seed = seed ^ sketch::hash64(path.as_bytes());
+            let (sample, seen) = sampling::reservoir(lines.by_ref(), size, shard_seed);
+            let sampled = sample.len().max(1);
+            let stats = analyze_lines(sample.into_par_iter(), fields);
+            (stats, seen as f64 / sampled as f64)
+        }
+        Sampling::Bytes { limit } => {
+            let head = lines
+                .by_ref()
+                .take_while(|_| consumed.load(Ordering::Relaxed) < limit)
+                .par_bridge();
+            let stats = analyze_lines(head, fields);
+            let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let read = consumed.load(Ordering::Relaxed).max(1);
+            (stats, (size as f64 / read as f64).max(1.0))
+        }
+    };
+
+    for event in lines.describe() {
+        println!("🧾 {event}");
+    }
+    stats.estimated_rows = stats.rows as f64 * scale;
+    stats
+}
+
+struct Args {
+    files: Vec<String>,
+    report_dir: String,
+    report_formats: Vec<ReportFormat>,
+    config: Option<String>,
+    top_k_fields: Vec<String>,
+    co_occurrence_fields: Vec<String>,
+    co_occurrence_output: Option<String>,
+    completeness_weights: Option<String>,
+    completeness_ids: Option<String>,
+    top_k: Option<usize>,
+    distributions_output: Option<String>,
+    sort: Option<SortOrder>,
+    sampling: Sampling,
+    first_shards: Option<usize>,
+    state: Option<String>,
+}
+
+fn parse_args() -> std::result::Result<Args, String> {
+    let mut args = Args {
+        files: Vec::new(),
+        report_dir: "field_report".to_string(),
+        report_formats: vec![ReportFormat::Json, ReportFormat::Csv, ReportFormat::Markdown],
+        config: None,
+        top_k_fields: Vec::new(),
+        co_occurrence_fields: Vec::new(),
+        co_occurrence_output: None,
+        completeness_weights: None,
+        completeness_ids: None,
+        top_k: None,
+        distributions_output: None,
+        sort: None,
+        sampling: Sampling::Full,
+        first_shards: None,
+        state: None,
+    };
+    let mut seed = 42u64;
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--report-dir" => args.report_dir = value("--report-dir")?,
+            "--report-format" => {
+                args.report_formats = value("--report-format")?
+                    .split(',')
+                    .map(|f| ReportFormat::parse(f.trim()).ok_or(format!("Unknown report format {f} (json, csv, md)")))
+                    .collect::<std::result::Result<_, _>>()?;
+            }
+            "--config" => args.config = Some(value("--config")?),
+            "--top-k-field" => args.top_k_fields.push(value("--top-k-field")?),
+            "--co-field" => args.co_occurrence_fields.push(value("--co-field")?),
+            "--co-output" => args.co_occurrence_output = Some(value("--co-output")?),
+            "--completeness-weights" => args.completeness_weights = Some(value("--completeness-weights")?),
+            "--completeness-ids" => args.completeness_ids = Some(value("--completeness-ids")?),
+            "--top-k" => {
+                let n = value("--top-k")?;
+                args.top_k = Some(n.parse().ok().filter(|k| *k > 0).ok_or(format!("Invalid --top-k {n}"))?);
             }
+            "--distributions-output" => args.distributions_output = Some(value("--distributions-output")?),
+            "--sort" => {
+                let name = value("--sort")?;
+                args.sort = Some(SortOrder::parse(&name).ok_or(format!("Unknown sort {name} (count, value)"))?);
+            }
+            "--sample-fraction" => {
+                let f = value("--sample-fraction")?;
+                let fraction = f
+                    .parse()
+                    .ok()
+                    .filter(|x: &f64| *x > 0.0 && *x <= 1.0)
+                    .ok_or(format!("Invalid --sample-fraction {f} (0 < F <= 1)"))?;
+                args.sampling = Sampling::Bernoulli { fraction, seed: 0 };
+            }
+            "--reservoir" => {
+                let n = value("--reservoir")?;
+                let size = n.parse().ok().filter(|n| *n > 0).ok_or(format!("Invalid --reservoir {n}"))?;
+                args.sampling = Sampling::Reservoir { size, seed: 0 };
+            }
+            "--max-mb-per-shard" => {
+                let n = value("--max-mb-per-shard")?;
+                let mb: u64 = n.parse().ok().filter(|n| *n > 0).ok_or(format!("Invalid --max-mb-per-shard {n}"))?;
+                args.sampling = Sampling::Bytes { limit: mb * 1024 * 1024 };
+            }
+            "--first-shards" => {
+                let n = value("--first-shards")?;
+                args.first_shards = Some(n.parse().ok().filter(|n| *n > 0).ok_or(format!("Invalid --first-shards {n}"))?);
+            }
+            "--state" => args.state = Some(value("--state")?),
+            "--seed" => {
+                let n = value("--seed")?;
+                seed = n.parse().map_err(|_| format!("Invalid --seed {n}"))?;
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {flag}")),
+            _ => args.files.push(arg),
+        }
+    }
 
-            a
-        })
+    match &mut args.sampling {
+        Sampling::Bernoulli { seed: s, .. } | Sampling::Reservoir { seed: s, .. } => *s = seed,
+        _ => {}
+    }
+    Ok(args)
+}
+
+/// `.../part-00001.gz` → `part-00001`
+fn shard_name(path: &str) -> String {
+    let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    name.strip_suffix(".ndjson").unwrap_or(name).to_string()
 }
 
 fn main() {
-    let files = vec![
+    let default_files = vec![
         "/media/tamil-07/1220581A2058075F/gz/gz/part-00000.gz",
         "/media/tamil-07/1220581A2058075F/gz/gz/part-00001.gz",
         // "/media/tamil-07/1220581A2058075F/gz/gz/part-00002.gz",
@@ -144,43 +431,158 @@ fn main() {
         "/media/tamil-07/1220581A2058075F/gz/gz/part-00008.gz",
     ];
 
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("❌ {e}");
+            std::process::exit(2);
+        }
+    };
+    // Shards passed on the command line replace the default list
+    let mut files: Vec<&str> = if args.files.is_empty() {
+        default_files
+    } else {
+        args.files.iter().map(String::as_str).collect()
+    };
+
+    // With --first-shards, the analysed shards stand in for all of them by size
+    let mut shard_scale = 1.0;
+    if let Some(k) = args.first_shards.filter(|k| *k < files.len()) {
+        let size = |f: &&str| fs::metadata(f).map(|m| m.len()).unwrap_or(0) as f64;
+        let all_bytes: f64 = files.iter().map(size).sum();
+        files.truncate(k);
+        let kept_bytes: f64 = files.iter().map(size).sum();
+        shard_scale = if kept_bytes > 0.0 { all_bytes / kept_bytes } else { 1.0 };
+    }
+    let sampled = args.sampling.is_sampled() || shard_scale > 1.0;
+    if sampled {
+        println!("🎲 Sampling: {}, {} shards", args.sampling.describe(), files.len());
+    }
+    fs::create_dir_all(&args.report_dir).unwrap();
+
+    // Config file first, then command-line overrides
+    let mut config = match &args.config {
+        Some(path) => AnalysisConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("❌ {e}");
+            std::process::exit(2);
+        }),
+        None => AnalysisConfig::default(),
+    };
+    config
+        .fields
+        .extend(args.top_k_fields.iter().map(|f| FieldEntry::Path(f.clone())));
+    if let Some(k) = args.top_k {
+        config.top_k = k;
+    }
+    if let Some(output) = &args.distributions_output {
+        config.output = output.clone();
+    }
+    if let Some(sort) = args.sort {
+        config.sort = sort;
+    }
+    config
+        .co_occurrence
+        .fields
+        .extend(args.co_occurrence_fields.iter().cloned());
+    if let Some(output) = &args.co_occurrence_output {
+        config.co_occurrence.output = output.clone();
+    }
+    if let Some(path) = &args.completeness_weights {
+        match record_completeness::load_weights(path) {
+            Ok(weights) => config.completeness.weights = weights,
+            Err(e) => {
+                eprintln!("❌ {e}");
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Err(e) = config.validate() {
+        eprintln!("❌ {e}");
+        std::process::exit(2);
+    }
+    let mut fields = config.selection();
+    fields.collect_ids = args.completeness_ids.is_some();
+    let mut bucket_ids = args.completeness_ids.as_ref().map(|dir| {
+        BucketIds::create(dir, &config.completeness).unwrap_or_else(|e| {
+            eprintln!("❌ Failed to create completeness id lists in {dir}: {e}");
+            std::process::exit(2);
+        })
+    });
+
+    // Cached per-shard stats from earlier runs with the same settings
+    let mut state = args
+        .state
+        .as_ref()
+        .map(|path| State::load(path, &state::settings_fingerprint(&fields, &args.sampling)));
+    let mut cached_shards = 0;
+
     let mut global = FileStats::default();
+    // field → (shard, non-null type mask) for every shard where it had a value
+    let mut shard_types: HashMap<String, Vec<(String, u8)>> = HashMap::new();
 
     for file in &files {
-        println!("\n📂 Analyzing {file}");
-        let stats = analyze_file_parallel(file);
+        // Id lists are not cached, so shards are re-read when they are requested
+        let cached = state.as_ref().filter(|_| !fields.collect_ids).and_then(|s| s.lookup(file));
+        let mut stats = match cached {
+            Some(stats) => {
+                println!("\n💾 Cached {file}");
+                cached_shards += 1;
+                stats
+            }
+            None => {
+                println!("\n📂 Analyzing {file}");
+                let stats = analyze_file_parallel(file, &fields, &args.sampling);
+                if let Some(state) = &mut state {
+                    state.store(file, &stats);
+                }
+                stats
+            }
+        };
 
         let avg_fields = stats.total_fields as f64 / stats.rows as f64;
         let avg_nulls = stats.null_or_empty_fields as f64 / stats.rows as f64;
 
         println!("Objects            : {}", stats.rows);
+        if args.sampling.is_sampled() {
+            println!("Estimated objects  : ~{:.0}", stats.estimated_rows);
+        }
         println!("Avg fields/object  : {:.2}", avg_fields);
         println!("Avg null+empty/obj : {:.2}", avg_nulls);
         println!(
             "Null+empty ratio   : {:.2}%",
             (avg_nulls / avg_fields) * 100.0
         );
+        println!("Avg completeness   : {:.3}", stats.completeness.mean());
 
-        global.rows += stats.rows;
-        global.total_fields += stats.total_fields;
-        global.null_or_empty_fields += stats.null_or_empty_fields;
-
-        for (k, v) in stats.per_field {
-            let e = global.per_field.entry(k).or_default();
-            e.present += v.present;
-            e.null += v.null;
-            e.empty += v.empty;
-            e.non_empty += v.non_empty;
+        if let Some(ids) = &mut bucket_ids {
+            // Written per shard so ids never pile up in the overall stats
+            ids.write(&std::mem::take(&mut stats.bucket_ids)).unwrap();
         }
-        for (field, counts) in stats.value_counts {
-            let entry = global
-                .value_counts
-                .entry(field)
-                .or_insert_with(HashMap::new);
-            for (val, count) in counts {
-                *entry.entry(val).or_insert(0) += count;
+
+        let report_base = format!("{}/{}", args.report_dir, shard_name(file));
+        let scale = args.sampling.is_sampled().then(|| stats.estimated_rows / stats.rows.max(1) as f64);
+        write_field_report(&report_base, file, stats.rows, &stats.per_field, &args.report_formats, scale).unwrap();
+
+        for (field, s) in &stats.per_field {
+            let mask = s.type_mask();
+            if mask != 0 {
+                shard_types.entry(field.clone()).or_default().push((file.to_string(), mask));
             }
         }
+
+        global.merge(stats);
+    }
+
+    if let (Some(state), Some(path)) = (&state, &args.state) {
+        match state.save(path) {
+            Ok(()) => println!(
+                "\n💾 State → {path}: {} shards reused, {} analysed, {} cached in total",
+                cached_shards,
+                files.len() - cached_shards,
+                state.shard_count()
+            ),
+            Err(e) => eprintln!("⚠️  Failed to write state {path}: {e}"),
+        }
     }
 
     println!("\n📊 OVERALL SUMMARY ({} files)", files.len());
@@ -188,6 +590,10 @@ fn main() {
     let avg_nulls = global.null_or_empty_fields as f64 / global.rows as f64;
 
     println!("Total objects       : {}", global.rows);
+    let estimated_rows = global.estimated_rows * shard_scale;
+    if sampled {
+        println!("Estimated objects   : ~{:.0} (scale ×{:.2})", estimated_rows, estimated_rows / global.rows.max(1) as f64);
+    }
     println!("Avg fields/object   : {:.2}", avg_fields);
     println!("Avg null+empty/obj  : {:.2}", avg_nulls);
     println!(
@@ -195,5 +601,63 @@ fn main() {
         (avg_nulls / avg_fields) * 100.0
     );
 
-    write_value_distributions_json("value_distributions.json", &global.value_counts).unwrap();
+    println!("Avg completeness    : {:.3}", global.completeness.mean());
+
+    println!("\n📈 Record completeness ({} records):", global.completeness.scored());
+    global.completeness.print(&config.completeness);
+    let completeness_path = format!("{}/completeness.json", args.report_dir);
+    global.completeness.write_json(&completeness_path, &config.completeness).unwrap();
+    if let (Some(ids), Some(dir)) = (bucket_ids, &args.completeness_ids) {
+        ids.finish().unwrap();
+        println!("🆔 Record ids per bucket → {dir}/");
+    }
+
+    let mut top_fields: Vec<&String> = global.value_counts.keys().collect();
+    top_fields.sort();
+    println!("\n🔝 Value distributions → {}:", config.output);
+    for field in top_fields {
+        let distinct = global.per_field.get(field).map_or(0, |s| s.distinct.estimate());
+        let accuracy = if global.value_counts[field].is_exact() { "exact" } else { "approximate" };
+        println!("  {field}: ~{distinct} distinct values, counts {accuracy}");
+    }
+
+    write_value_distributions_json(
+        &config.output,
+        &global.value_counts,
+        &global.per_field,
+        &fields.values,
+        config.sort,
+    )
+    .unwrap();
+
+    let drift = schema_drift(&shard_types);
+    if !drift.is_empty() {
+        println!("\n⚠️  Schema drift in {} fields:", drift.len());
+        for (field, by_mask) in &drift {
+            let sets: Vec<String> = by_mask
+                .iter()
+                .map(|(label, shards)| format!("{label} ({} shards)", shards.len()))
+                .collect();
+            println!("  {field}: {}", sets.join(", "));
+        }
+    }
+
+    let field_stats_path = format!("{}/field_stats.json", args.report_dir);
+    if let Err(e) = write_field_stats_json(&field_stats_path, &global.per_field, &drift) {
+        eprintln!("❌ Failed to write {field_stats_path}: {e}");
+        std::process::exit(1);
+    }
+    println!("\n📝 Field stats written to {field_stats_path}");
+
+    let co_fields = &config.co_occurrence.fields;
+    if !co_fields.is_empty() {
+        let output = &config.co_occurrence.output;
+        write_co_occurrence(output, co_fields, global.rows, &global.co_occurrence).unwrap();
+        println!("\n🔗 Co-occurrence of {} fields → {output}", co_fields.len());
+    }
+
+    let overall_base = format!("{}/overall", args.report_dir);
+    let scale = sampled.then(|| estimated_rows / global.rows.max(1) as f64);
+    write_field_report(&overall_base, "overall", global.rows, &global.per_field, &args.report_formats, scale).unwrap();
+    println!("\n📝 Field reports written to {}/", args.report_dir);
 }
@@ -0,0 +1,211 @@
+use crate::field_stats::FieldStats;
+use crate::sampling::wilson_interval;
+use serde_json::{Value, json, to_writer_pretty};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Result, Write},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> Option<ReportFormat> {
+        match name {
+            "json" => Some(ReportFormat::Json),
+            "csv" => Some(ReportFormat::Csv),
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// One report line. Presence and fill are per object (`rows_*` / objects);
+/// null and empty ratios are per occurrence of the field.
+struct ReportRow<'a> {
+    field: &'a str,
+    stats: &'a FieldStats,
+    presence: f64,
+    fill: f64,
+    null_ratio: f64,
+    empty_ratio: f64,
+    /// 95% intervals, only meaningful for sampled runs
+    presence_ci: (f64, f64),
+    fill_ci: (f64, f64),
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+/// Rows sorted by sparsity: lowest fill ratio first, then by field name
+fn report_rows(rows: u64, per_field: &HashMap<String, FieldStats>) -> Vec<ReportRow<'_>> {
+    let mut out: Vec<ReportRow> = per_field
+        .iter()
+        .map(|(field, stats)| ReportRow {
+            field,
+            stats,
+            presence: ratio(stats.rows_present, rows),
+            fill: ratio(stats.rows_non_empty, rows),
+            null_ratio: ratio(stats.null, stats.present),
+            empty_ratio: ratio(stats.empty, stats.present),
+            presence_ci: wilson_interval(stats.rows_present.min(rows), rows),
+            fill_ci: wilson_interval(stats.rows_non_empty.min(rows), rows),
+        })
+        .collect();
+    out.sort_by(|a, b| a.fill.total_cmp(&b.fill).then_with(|| a.field.cmp(b.field)));
+    out
+}
+
+fn csv_cell(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Write `<base>.<ext>` for each requested format. `scale` is set for sampled
+/// runs: it adds estimated object counts and 95% intervals for the ratios.
+pub fn write_field_report(
+    base: &str,
+    title: &str,
+    rows: u64,
+    per_field: &HashMap<String, FieldStats>,
+    formats: &[ReportFormat],
+    scale: Option<f64>,
+) -> Result<()> {
+    let report = report_rows(rows, per_field);
+
+    for format in formats {
+        let file = File::create(format!("{base}.{}", format.extension()))?;
+        let mut w = BufWriter::new(file);
+
+        match format {
+            ReportFormat::Json => {
+                let fields: Vec<Value> = report
+                    .iter()
+                    .map(|r| {
+                        let mut entry = json!({
+                            "field": r.field,
+                            "rows_present": r.stats.rows_present,
+                            "rows_non_empty": r.stats.rows_non_empty,
+                            "presence_ratio": r.presence,
+                            "fill_ratio": r.fill,
+                            "present": r.stats.present,
+                            "null": r.stats.null,
+                            "empty": r.stats.empty,
+                            "non_empty": r.stats.non_empty,
+                            "null_ratio": r.null_ratio,
+                            "empty_ratio": r.empty_ratio,
+                        });
+                        if let Some(scale) = scale {
+                            entry["estimated_rows_present"] = json!((r.stats.rows_present as f64 * scale).round());
+                            entry["estimated_rows_non_empty"] = json!((r.stats.rows_non_empty as f64 * scale).round());
+                            entry["presence_ci95"] = json!([r.presence_ci.0, r.presence_ci.1]);
+                            entry["fill_ci95"] = json!([r.fill_ci.0, r.fill_ci.1]);
+                        }
+                        entry
+                    })
+                    .collect();
+                let mut report = json!({ "source": title, "objects": rows, "fields": fields });
+                if let Some(scale) = scale {
+                    report["sampled"] = json!(true);
+                    report["estimated_objects"] = json!((rows as f64 * scale).round());
+                }
+                to_writer_pretty(&mut w, &report)?;
+            }
+            ReportFormat::Csv => {
+                write!(
+                    w,
+                    "field,rows_present,rows_non_empty,presence_ratio,fill_ratio,present,null,empty,non_empty,null_ratio,empty_ratio"
+                )?;
+                if scale.is_some() {
+                    write!(
+                        w,
+                        ",estimated_rows_present,estimated_rows_non_empty,presence_ci_low,presence_ci_high,fill_ci_low,fill_ci_high"
+                    )?;
+                }
+                writeln!(w)?;
+                for r in &report {
+                    write!(
+                        w,
+                        "{},{},{},{:.6},{:.6},{},{},{},{},{:.6},{:.6}",
+                        csv_cell(r.field),
+                        r.stats.rows_present,
+                        r.stats.rows_non_empty,
+                        r.presence,
+                        r.fill,
+                        r.stats.present,
+                        r.stats.null,
+                        r.stats.empty,
+                        r.stats.non_empty,
+                        r.null_ratio,
+                        r.empty_ratio
+                    )?;
+                    if let Some(scale) = scale {
+                        write!(
+                            w,
+                            ",{:.0},{:.0},{:.6},{:.6},{:.6},{:.6}",
+                            r.stats.rows_present as f64 * scale,
+                            r.stats.rows_non_empty as f64 * scale,
+                            r.presence_ci.0,
+                            r.presence_ci.1,
+                            r.fill_ci.0,
+                            r.fill_ci.1
+                        )?;
+                    }
+                    writeln!(w)?;
+                }
+            }
+            ReportFormat::Markdown => {
+                writeln!(w, "# Field report: {title}\n")?;
+                writeln!(w, "{rows} objects, {} fields, sparsest first.\n", report.len())?;
+                if let Some(scale) = scale {
+                    writeln!(
+                        w,
+                        "Sampled: estimates stand for ~{:.0} objects; ranges are 95% intervals.\n",
+                        rows as f64 * scale
+                    )?;
+                }
+                writeln!(w, "| Field | Presence % | Fill % | Null % | Empty % | Occurrences |")?;
+                writeln!(w, "|---|---:|---:|---:|---:|---:|")?;
+                for r in &report {
+                    let (presence, fill) = match scale {
+                        Some(_) => (
+                            format!("{:.2} ({:.2}–{:.2})", r.presence * 100.0, r.presence_ci.0 * 100.0, r.presence_ci.1 * 100.0),
+                            format!("{:.2} ({:.2}–{:.2})", r.fill * 100.0, r.fill_ci.0 * 100.0, r.fill_ci.1 * 100.0),
+                        ),
+                        None => (format!("{:.2}", r.presence * 100.0), format!("{:.2}", r.fill * 100.0)),
+                    };
+                    writeln!(
+                        w,
+                        "| `{}` | {} | {} | {:.2} | {:.2} | {} |",
+                        r.field.replace('|', "\\|"),
+                        presence,
+                        fill,
+                        r.null_ratio * 100.0,
+                        r.empty_ratio * 100.0,
+                        r.stats.present
+                    )?;
+                }
+            }
+        }
+        w.flush()?;
+    }
+
+    Ok(())
+}
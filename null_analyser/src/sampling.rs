@@ -0,0 +1,176 @@
+//! Row sampling for quick looks at a delivery before a full run.
+//!
+//! - `Bernoulli`: keep each line with probability `fraction`, decided by a
+//!   seeded hash of the line so reruns pick the same rows
+//! - `Reservoir`: a uniform sample of `size` lines per shard
+//! - `Bytes`: only the first `limit` compressed bytes of each shard (a head
+//!   sample, biased if shards are sorted)
+//!
+//! Every mode records the scale factor from sampled rows back to the shard,
+//! so counts can be reported as estimates. Shards are read through
+//! `ShardLines`, so a bad line is skipped rather than ending the sample early.
+
+use crate::sketch::hash64;
+use flate2::read::GzDecoder;
+use record_transform::line_reader::{Line, LineOptions, LineReader};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Result},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+#[derive(Clone, Copy)]
+pub enum Sampling {
+    Full,
+    Bernoulli { fraction: f64, seed: u64 },
+    Reservoir { size: usize, seed: u64 },
+    Bytes { limit: u64 },
+}
+
+impl Sampling {
+    pub fn is_sampled(&self) -> bool {
+        !matches!(self, Sampling::Full)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Sampling::Full => "full scan".to_string(),
+            Sampling::Bernoulli { fraction, seed } => format!("Bernoulli {:.2}% (seed {seed})", fraction * 100.0),
+            Sampling::Reservoir { size, seed } => format!("reservoir of {size} rows per shard (seed {seed})"),
+            Sampling::Bytes { limit } => format!("first {} MB of each shard", limit / (1024 * 1024)),
+        }
+    }
+
+    /// Bernoulli decision for one line
+    pub fn keep(&self, line: &str) -> bool {
+        match self {
+            Sampling::Bernoulli { fraction, seed } => {
+                let h = splitmix64(hash64(line.as_bytes()) ^ seed);
+                ((h >> 11) as f64 / (1u64 << 53) as f64) < *fraction
+            }
+            _ => true,
+        }
+    }
+}
+
+/// SplitMix64 step, used as a small seeded RNG and hash mixer
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Counts the compressed bytes pulled from the underlying file
+pub struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+pub type ShardReader = BufReader<GzDecoder<CountingReader<File>>>;
+
+/// Gzip line reader plus a live count of compressed bytes consumed
+pub fn open_shard(path: &str) -> Result<(ShardReader, Arc<AtomicU64>)> {
+    let count = Arc::new(AtomicU64::new(0));
+    let file = CountingReader {
+        inner: File::open(path)?,
+        count: count.clone(),
+    };
+    Ok((BufReader::new(GzDecoder::new(file)), count))
+}
+
+/// Lines of a shard, read with `record_transform::line_reader`: lines with
+/// invalid UTF-8 or over its length limit are skipped and counted, so the
+/// rest of the shard is still read. A read error ends the shard and is kept
+/// for `describe`.
+pub struct ShardLines {
+    reader: LineReader<ShardReader>,
+    error: Option<io::Error>,
+}
+
+impl ShardLines {
+    pub fn new(reader: ShardReader) -> Self {
+        ShardLines {
+            reader: LineReader::new(reader, LineOptions::default()),
+            error: None,
+        }
+    }
+
+    /// Skipped lines and the read error that ended the shard, if any
+    pub fn describe(&self) -> Vec<String> {
+        let mut events = self.reader.counts().describe();
+        if let Some(e) = &self.error {
+            events.push(format!("read stopped after line {}: {e}", self.reader.counts().lines));
+        }
+        events
+    }
+}
+
+impl Iterator for ShardLines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.error.is_some() {
+            return None;
+        }
+        loop {
+            match self.reader.next_line() {
+                Ok(Some(Line::Text(text))) => return Some(text.to_string()),
+                // Only in passthrough mode, which isn't used here
+                Ok(Some(Line::Bytes(_))) => continue,
+                Ok(None) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Uniform sample of `size` lines (Algorithm R); returns the sample and the
+/// number of lines seen
+pub fn reservoir(lines: impl Iterator<Item = String>, size: usize, seed: u64) -> (Vec<String>, u64) {
+    let mut sample = Vec::with_capacity(size);
+    let mut state = seed;
+    let mut seen = 0u64;
+
+    for line in lines {
+        seen += 1;
+        if sample.len() < size {
+            sample.push(line);
+        } else {
+            state = splitmix64(state);
+            let slot = state % seen;
+            if (slot as usize) < size {
+                sample[slot as usize] = line;
+            }
+        }
+    }
+    (sample, seen)
+}
+
+/// Wilson score 95% interval for `successes` out of `n`
+pub fn wilson_interval(successes: u64, n: u64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let z = 1.96f64;
+    let n = n as f64;
+    let p = successes as f64 / n;
+    let denom = 1.0 + z * z / n;
+    let center = (p + z * z / (2.0 * n)) / denom;
+    let half = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denom;
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
@@ -0,0 +1,195 @@
+//! Bounded-memory sketches, mergeable across rayon accumulators and shards.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+/// FNV-1a with a murmur3 finaliser. Stable across runs and platforms, unlike
+/// `DefaultHasher`, so sketches stay comparable between runs.
+pub fn hash64(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+/// 2^12 registers: 4 KB per field, ~1.6% standard error
+const HLL_PRECISION: u32 = 12;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// HyperLogLog distinct-count estimator; registers are allocated on first insert
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HyperLogLog {
+    #[serde(with = "hex_registers")]
+    registers: Vec<u8>,
+}
+
+/// Registers as one hex string: far smaller than a JSON array of numbers
+mod hex_registers {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(registers: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        let hex: String = registers.iter().map(|r| format!("{r:02x}")).collect();
+        s.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(d)?;
+        if hex.len() % 2 != 0 {
+            return Err(serde::de::Error::custom("odd-length register string"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+impl HyperLogLog {
+    pub fn insert(&mut self, bytes: &[u8]) {
+        if self.registers.is_empty() {
+            self.registers = vec![0; HLL_REGISTERS];
+        }
+        let h = hash64(bytes);
+        let index = (h >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((h << HLL_PRECISION).leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        if other.registers.is_empty() {
+            return;
+        }
+        if self.registers.is_empty() {
+            self.registers = other.registers.clone();
+            return;
+        }
+        for (a, b) in self.registers.iter_mut().zip(&other.registers) {
+            *a = (*a).max(*b);
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        if self.registers.is_empty() {
+            return 0;
+        }
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        // Linear counting is more accurate while many registers are still empty
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            raw.round() as u64
+        }
+    }
+}
+
+/// Space-Saving heavy hitters with batched evictions.
+///
+/// Tracks up to `2 * capacity` counters; when full, only the `capacity`
+/// largest are kept and `floor` rises to the largest evicted count. A new
+/// value starts at `floor + 1`, so reported counts overestimate by at most
+/// their `error`. While a field has fewer distinct values than `capacity`
+/// nothing is evicted and the counts are exact.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpaceSaving {
+    capacity: usize,
+    floor: u64,
+    /// Every inserted value, tracked or not
+    total: u64,
+    counters: HashMap<String, (u64, u64)>,
+}
+
+impl SpaceSaving {
+    pub fn new(capacity: usize) -> Self {
+        SpaceSaving {
+            capacity: capacity.max(1),
+            floor: 0,
+            total: 0,
+            counters: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: &str) {
+        self.total += 1;
+        if let Some((count, _)) = self.counters.get_mut(value) {
+            *count += 1;
+            return;
+        }
+        self.counters.insert(value.to_string(), (self.floor + 1, self.floor));
+        if self.counters.len() >= 2 * self.capacity {
+            self.compact();
+        }
+    }
+
+    /// Keep the `capacity` largest counters
+    fn compact(&mut self) {
+        if self.counters.len() <= self.capacity {
+            return;
+        }
+        let mut entries: Vec<(String, (u64, u64))> = self.counters.drain().collect();
+        entries.sort_unstable_by_key(|(_, (count, _))| std::cmp::Reverse(*count));
+        self.floor = self.floor.max(entries[self.capacity].1.0);
+        entries.truncate(self.capacity);
+        self.counters = entries.into_iter().collect();
+    }
+
+    pub fn merge(&mut self, other: &SpaceSaving) {
+        // A value missing from one side may have been evicted there with up to `floor` hits
+        for (value, (count, error)) in self.counters.iter_mut() {
+            match other.counters.get(value) {
+                Some((c, e)) => {
+                    *count += c;
+                    *error += e;
+                }
+                None => {
+                    *count += other.floor;
+                    *error += other.floor;
+                }
+            }
+        }
+        for (value, (count, error)) in &other.counters {
+            if !self.counters.contains_key(value) {
+                self.counters.insert(value.clone(), (count + self.floor, error + self.floor));
+            }
+        }
+        self.floor += other.floor;
+        self.total += other.total;
+        self.capacity = self.capacity.max(other.capacity);
+        if self.counters.len() >= 2 * self.capacity {
+            self.compact();
+        }
+    }
+
+    /// Up to `k` values, most frequent first, as `(value, count, max overestimate)`
+    pub fn top(&self, k: usize) -> Vec<(&str, u64, u64)> {
+        let mut entries: Vec<(&str, u64, u64)> = self
+            .counters
+            .iter()
+            .map(|(v, (c, e))| (v.as_str(), *c, *e))
+            .collect();
+        entries.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries.truncate(k);
+        entries
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// True when no value was ever evicted, i.e. the counts are exact
+    pub fn is_exact(&self) -> bool {
+        self.floor == 0
+    }
+}
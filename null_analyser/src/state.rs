@@ -0,0 +1,149 @@
+//! Incremental runs: per-shard `FileStats` cached in a gzipped JSON state file
+//! (`--state`).
+//!
+//! Each shard is keyed by its path, size and modification time; a shard whose
+//! key still matches is merged from the cache instead of being re-read. The
+//! cache is only reused when the analysis settings (value-count fields,
+//! normalisation, top-k, co-occurrence fields, completeness weights,
+//! sampling) match the run that wrote it, because the
+//! cached sketches depend on them.
+
+use crate::FileStats;
+use crate::config::FieldSelection;
+use crate::sampling::Sampling;
+use crate::sketch::hash64;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Result, Write},
+    time::UNIX_EPOCH,
+};
+
+/// Bumped whenever the cached `FileStats` layout or how they are counted changes
+/// (2: co-occurrence, 3: completeness, 4: bad lines skipped instead of ending the shard)
+const STATE_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+struct ShardKey {
+    size: u64,
+    /// Nanoseconds since the Unix epoch
+    modified: u64,
+}
+
+impl ShardKey {
+    fn of(path: &str) -> Option<ShardKey> {
+        let meta = fs::metadata(path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(ShardKey {
+            size: meta.len(),
+            modified: modified.as_nanos() as u64,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedShard {
+    key: ShardKey,
+    stats: FileStats,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct State {
+    version: u32,
+    settings: String,
+    shards: HashMap<String, CachedShard>,
+}
+
+/// Stable fingerprint of everything that shapes the cached stats
+pub fn settings_fingerprint(fields: &FieldSelection, sampling: &Sampling) -> String {
+    let mut specs: Vec<String> = fields
+        .values
+        .iter()
+        .map(|(path, spec)| {
+            let n = &spec.normalize;
+            format!("{path}|{}|{}|{:?}|{}", n.lowercase, n.trim, n.bucket, spec.top_k)
+        })
+        .collect();
+    specs.sort();
+    let mut presence: Vec<(&String, &usize)> = fields.presence.iter().collect();
+    presence.sort_by_key(|(_, bit)| **bit);
+    let presence: Vec<&str> = presence.iter().map(|(path, _)| path.as_str()).collect();
+    let completeness = &fields.completeness;
+    let text = format!(
+        "{}\n{}\n{:?}/{}\n{}",
+        specs.join("\n"),
+        presence.join(","),
+        completeness.weights,
+        completeness.buckets,
+        sampling.describe()
+    );
+    format!("{:016x}", hash64(text.as_bytes()))
+}
+
+impl State {
+    /// Load the state file; a missing, unreadable or outdated file gives an
+    /// empty cache and every shard is analysed again
+    pub fn load(path: &str, settings: &str) -> State {
+        let empty = State {
+            version: STATE_VERSION,
+            settings: settings.to_string(),
+            shards: HashMap::new(),
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return empty,
+        };
+        // Check the version before the layout, so a file from an older version
+        // is invalidated cleanly rather than failing to parse
+        let state = serde_json::from_reader::<_, serde_json::Value>(BufReader::new(GzDecoder::new(file)))
+            .and_then(|value| match value.get("version").and_then(|v| v.as_u64()) {
+                Some(version) if version == u64::from(STATE_VERSION) => {
+                    serde_json::from_value::<State>(value).map(Some)
+                }
+                _ => Ok(None),
+            });
+        match state {
+            Ok(Some(state)) if state.settings == settings => state,
+            Ok(Some(_)) => {
+                println!("♻️  State {path} was written with different settings, re-analysing all shards");
+                empty
+            }
+            Ok(None) => {
+                println!("♻️  State {path} was written by another version, re-analysing all shards");
+                empty
+            }
+            Err(e) => {
+                eprintln!("⚠️  Ignoring unreadable state {path}: {e}");
+                empty
+            }
+        }
+    }
+
+    /// Cached stats for `path`, if the shard is unchanged since they were stored
+    pub fn lookup(&self, path: &str) -> Option<FileStats> {
+        let cached = self.shards.get(path)?;
+        (ShardKey::of(path)? == cached.key).then(|| cached.stats.clone())
+    }
+
+    pub fn store(&mut self, path: &str, stats: &FileStats) {
+        if let Some(key) = ShardKey::of(path) {
+            let stats = stats.clone();
+            self.shards.insert(path.to_string(), CachedShard { key, stats });
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Write to a temporary file first so an interrupted run keeps the old state
+    pub fn save(&self, path: &str) -> Result<()> {
+        let tmp = format!("{path}.tmp");
+        let mut w = GzEncoder::new(BufWriter::new(File::create(&tmp)?), Compression::default());
+        serde_json::to_writer(&mut w, self)?;
+        w.finish()?.flush()?;
+        fs::rename(&tmp, path)
+    }
+}
@@ -1,13 +1,21 @@
-use crossbeam_channel::{bounded, Receiver, Sender};
-use duckdb::{params, Connection, Result};
+//! Pipelined NDJSON → Parquet conversion through DuckDB.
+//!
+//! A `pipeline::Pipeline` reads the input in chunks of `CHUNK_SIZE` lines on
+//! one thread, parses each chunk with rayon and inserts the rows into DuckDB
+//! on the main thread, in input order. Channels hold `CHANNEL_BUFFER` chunks,
+//! so reading waits when parsing or inserting falls behind. Any failure stops
+//! the pipeline and is reported with the stage it came from, followed by the
+//! time each stage spent working and waiting.
+
+use duckdb::Connection;
 use flate2::read::GzDecoder;
+use pipeline::{Pipeline, PipelineOptions};
 use rayon::prelude::*;
 use serde_json::Value;
 use std::{
+    error::Error,
     fs::File,
-    io::{BufRead, BufReader},
-    sync::atomic::{AtomicUsize, Ordering},
-    thread,
+    io::{BufRead, BufReader, ErrorKind},
     time::Instant,
 };
 
@@ -29,7 +37,7 @@ type Row = (
     Option<String>,
 );
 
-fn main() -> Result<()> {
+fn main() -> Result<(), Box<dyn Error>> {
     let input = "/media/tamil-07/New Volume1/torrents/gz/part-00001.gz";
     let output = "part-00001.parquet";
 
@@ -38,62 +46,8 @@ fn main() -> Result<()> {
     println!("📖 Starting pipelined processing...\n");
 
     let start_time = Instant::now();
-    let total_rows = AtomicUsize::new(0);
 
-    let (line_sender, line_receiver): (Sender<Vec<String>>, Receiver<Vec<String>>) =
-        bounded(CHANNEL_BUFFER);
-    let (row_sender, row_receiver): (Sender<Vec<Row>>, Receiver<Vec<Row>>) =
-        bounded(CHANNEL_BUFFER);
-
-    // ==================== READER THREAD ====================
-    let reader_handle = thread::spawn(move || {
-        let file = File::open(input).expect("Failed to open input file");
-        let decoder = GzDecoder::new(file);
-        let reader = BufReader::with_capacity(16 * 1024 * 1024, decoder);
-
-        let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
-        let mut chunks_sent = 0;
-
-        for line in reader.lines().flatten() {
-            chunk.push(line);
-
-            if chunk.len() >= CHUNK_SIZE {
-                line_sender.send(chunk).expect("Failed to send chunk");
-                chunk = Vec::with_capacity(CHUNK_SIZE);
-                chunks_sent += 1;
-            }
-        }
-
-        if !chunk.is_empty() {
-            line_sender.send(chunk).expect("Failed to send final chunk");
-            chunks_sent += 1;
-        }
-
-        drop(line_sender);
-        chunks_sent
-    });
-
-    // ==================== PARSER THREADS (via rayon) ====================
-    let parser_handle = thread::spawn(move || {
-        let mut batches_sent = 0;
-
-        for lines_chunk in line_receiver {
-            let parsed_rows: Vec<Row> = lines_chunk
-                .par_iter()
-                .filter_map(|line| parse_json_line(line))
-                .collect();
-
-            if !parsed_rows.is_empty() {
-                row_sender.send(parsed_rows).expect("Failed to send rows");
-                batches_sent += 1;
-            }
-        }
-
-        drop(row_sender);
-        batches_sent
-    });
-
-    // ==================== WRITER THREAD (main) - OPTIMIZED ====================
+    // ==================== WRITER SETUP - OPTIMIZED ====================
     let mut conn = Connection::open_in_memory()?;
 
     // Optimize DuckDB for bulk loading
@@ -119,13 +73,55 @@ fn main() -> Result<()> {
         "#,
     )?;
 
+    let mut total_rows = 0usize;
     let mut batch_num = 0;
     let mut last_report_time = Instant::now();
     let mut rows_since_last_report = 0;
 
-    for parsed_batch in row_receiver {
+    let options = PipelineOptions {
+        capacity: CHANNEL_BUFFER,
+        ordered: true,
+    };
+    let metrics = Pipeline::source(options, "read", move |out| {
+        // ==================== READER THREAD ====================
+        let file = File::open(input).map_err(|e| format!("Failed to open input file {}: {}", input, e))?;
+        let decoder = GzDecoder::new(file);
+        let reader = BufReader::with_capacity(16 * 1024 * 1024, decoder);
+
+        let mut chunk: Vec<String> = Vec::with_capacity(CHUNK_SIZE);
+        for line in reader.lines() {
+            match line {
+                Ok(line) => chunk.push(line),
+                // Invalid UTF-8 only spoils that line
+                Err(e) if e.kind() == ErrorKind::InvalidData => continue,
+                Err(e) => return Err(e.into()),
+            }
+
+            if chunk.len() >= CHUNK_SIZE {
+                out.emit(chunk)?;
+                chunk = Vec::with_capacity(CHUNK_SIZE);
+            }
+        }
+
+        if !chunk.is_empty() {
+            out.emit(chunk)?;
+        }
+        Ok(())
+    })
+    // ==================== PARSER (via rayon) ====================
+    .map("parse", 1, |lines_chunk: Vec<String>| {
+        Ok(lines_chunk
+            .par_iter()
+            .filter_map(|line| parse_json_line(line))
+            .collect::<Vec<Row>>())
+    })
+    // ==================== WRITER (main thread) ====================
+    .sink("insert", |parsed_batch| {
+        if parsed_batch.is_empty() {
+            return Ok(());
+        }
         let batch_len = parsed_batch.len();
-        total_rows.fetch_add(batch_len, Ordering::Relaxed);
+        total_rows += batch_len;
         rows_since_last_report += batch_len;
 
         // Use optimized bulk insert
@@ -133,39 +129,39 @@ fn main() -> Result<()> {
 
         batch_num += 1;
 
-        let current_total = total_rows.load(Ordering::Relaxed);
         if rows_since_last_report >= 100_000 {
             let elapsed = last_report_time.elapsed();
             let rows_per_sec = rows_since_last_report as f64 / elapsed.as_secs_f64();
-            
+
             println!(
                 "  ✅ Processed {:>7} rows | Batch #{:>2} | ⏱️  {:.2}s | 🚀 {:.0} rows/sec",
-                current_total,
+                total_rows,
                 batch_num,
                 elapsed.as_secs_f64(),
                 rows_per_sec
             );
-            
+
             last_report_time = Instant::now();
             rows_since_last_report = 0;
         }
-    }
+        Ok(())
+    })?;
 
-    let chunks_read = reader_handle.join().expect("Reader thread panicked");
-    let batches_parsed = parser_handle.join().expect("Parser thread panicked");
-
-    let final_count = total_rows.load(Ordering::Relaxed);
     let total_elapsed = start_time.elapsed();
 
     println!("\n📊 Processing complete:");
-    println!("   Total rows: {}", final_count);
-    println!("   Chunks read: {}", chunks_read);
-    println!("   Batches parsed: {}", batches_parsed);
+    println!("   Total rows: {}", total_rows);
+    println!("   Chunks read: {}", metrics.stages[0].items);
+    println!("   Batches parsed: {}", metrics.stages[1].items);
     println!("   Total time: {:.2}s", total_elapsed.as_secs_f64());
     println!(
         "   Throughput: {:.0} rows/sec",
-        final_count as f64 / total_elapsed.as_secs_f64()
+        total_rows as f64 / total_elapsed.as_secs_f64()
     );
+    println!("\n⏱️  Stage timings:");
+    for line in metrics.describe() {
+        println!("   {}", line);
+    }
 
     println!("\n💾 Writing Parquet file...");
     let parquet_start = Instant::now();
@@ -208,7 +204,7 @@ fn parse_json_line(line: &str) -> Option<Row> {
 }
 
 /// Optimized bulk insert using VALUES clause
-fn insert_batch_optimized(conn: &mut Connection, batch: &[Row]) -> Result<()> {
+fn insert_batch_optimized(conn: &mut Connection, batch: &[Row]) -> duckdb::Result<()> {
     if batch.is_empty() {
         return Ok(());
     }
@@ -0,0 +1,548 @@
+//! Bounded multi-stage pipelines: a source thread, any number of map stages
+//! with their own worker threads, and a sink on the calling thread. Stages
+//! are connected by bounded channels, so a slow stage holds back the ones
+//! before it instead of letting items pile up in memory.
+//!
+//! ```text
+//! let metrics = Pipeline::source(PipelineOptions::default(), "read", |out| {
+//!     for chunk in chunks {
+//!         out.emit(chunk)?;
+//!     }
+//!     Ok(())
+//! })
+//! .map("parse", 4, |chunk: Vec<String>| Ok(parse(&chunk)))
+//! .sink("write", |rows| write(&rows))?;
+//! ```
+//!
+//! - Errors: the first error returned (or panic raised) by any stage stops
+//!   every stage, and `sink` returns it with the name of the stage.
+//! - Ordering: with `ordered`, the sink gets items in the order the source
+//!   emitted them, however many workers a stage has; otherwise in the order
+//!   they finish. Ordered pipelines keep at most `4 × capacity` items in
+//!   flight, so the reorder buffer is bounded as well.
+//! - Metrics: per stage, the items handled, the time spent in the stage's
+//!   own function and the time spent waiting for input or for room downstream.
+
+use crossbeam_channel::{bounded, select, Receiver, Sender};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineOptions {
+    /// Items each channel between two stages can hold
+    pub capacity: usize,
+    pub ordered: bool,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        PipelineOptions {
+            capacity: 16,
+            ordered: true,
+        }
+    }
+}
+
+/// The first error raised in a pipeline and the stage that raised it
+#[derive(Debug)]
+pub struct PipelineError {
+    pub stage: String,
+    pub error: BoxError,
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} stage failed: {}", self.stage, self.error)
+    }
+}
+
+impl Error for PipelineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// Returned by `Emitter::emit` once the pipeline has stopped; a source can
+/// pass it on with `?`
+#[derive(Debug)]
+pub struct Stopped;
+
+impl fmt::Display for Stopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pipeline stopped")
+    }
+}
+
+impl Error for Stopped {}
+
+#[derive(Clone, Debug, Default)]
+pub struct StageMetrics {
+    pub name: String,
+    pub workers: usize,
+    pub items: u64,
+    /// Time in the stage's function, summed over its workers
+    pub busy: Duration,
+    pub waiting_input: Duration,
+    pub waiting_output: Duration,
+}
+
+impl StageMetrics {
+    fn new(name: &str, workers: usize) -> Self {
+        StageMetrics {
+            name: name.to_string(),
+            workers,
+            ..StageMetrics::default()
+        }
+    }
+
+    fn merge(&mut self, other: &StageMetrics) {
+        self.items += other.items;
+        self.busy += other.busy;
+        self.waiting_input += other.waiting_input;
+        self.waiting_output += other.waiting_output;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PipelineMetrics {
+    pub stages: Vec<StageMetrics>,
+    pub elapsed: Duration,
+}
+
+impl PipelineMetrics {
+    /// One line per stage, e.g.
+    /// `parse (4 workers): 1204 items, busy 3.21s, waited 0.02s for input, 1.50s for output`
+    pub fn describe(&self) -> Vec<String> {
+        self.stages
+            .iter()
+            .map(|s| {
+                let workers = if s.workers > 1 {
+                    format!(" ({} workers)", s.workers)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "{}{}: {} items, busy {:.2}s, waited {:.2}s for input, {:.2}s for output",
+                    s.name,
+                    workers,
+                    s.items,
+                    s.busy.as_secs_f64(),
+                    s.waiting_input.as_secs_f64(),
+                    s.waiting_output.as_secs_f64()
+                )
+            })
+            .collect()
+    }
+}
+
+/// Stop flag and first error, shared by every stage. Dropping `stop_tx`
+/// disconnects `stop_rx`, which wakes every stage blocked on a channel.
+struct Shared {
+    stop: AtomicBool,
+    stop_tx: Mutex<Option<Sender<()>>>,
+    stop_rx: Receiver<()>,
+    error: Mutex<Option<PipelineError>>,
+}
+
+impl Shared {
+    fn new() -> Self {
+        let (stop_tx, stop_rx) = bounded(0);
+        Shared {
+            stop: AtomicBool::new(false),
+            stop_tx: Mutex::new(Some(stop_tx)),
+            stop_rx,
+            error: Mutex::new(None),
+        }
+    }
+
+    fn fail(&self, stage: &str, error: BoxError) {
+        let mut slot = self.error.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.is_none() {
+            *slot = Some(PipelineError {
+                stage: stage.to_string(),
+                error,
+            });
+        }
+        drop(slot);
+        self.stop.store(true, Ordering::SeqCst);
+        self.stop_tx.lock().unwrap_or_else(PoisonError::into_inner).take();
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+}
+
+fn panic_error(payload: Box<dyn Any + Send>) -> BoxError {
+    let msg = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("panicked: {}", msg).into()
+}
+
+fn record(cell: &Mutex<StageMetrics>, metrics: &StageMetrics) {
+    cell.lock().unwrap_or_else(PoisonError::into_inner).merge(metrics);
+}
+
+/// Handed to the source function to send items down the pipeline
+pub struct Emitter<T> {
+    tx: Sender<(u64, T)>,
+    tickets: Option<Sender<()>>,
+    shared: Arc<Shared>,
+    next: u64,
+    metrics: StageMetrics,
+}
+
+impl<T> Emitter<T> {
+    /// Send an item, waiting while the next stage is full
+    pub fn emit(&mut self, item: T) -> Result<(), Stopped> {
+        if self.shared.stopped() {
+            return Err(Stopped);
+        }
+        let wait = Instant::now();
+        let stop = &self.shared.stop_rx;
+        if let Some(tickets) = &self.tickets {
+            select! {
+                send(tickets, ()) -> sent => sent.map_err(|_| Stopped)?,
+                recv(stop) -> _ => return Err(Stopped),
+            }
+        }
+        select! {
+            send(self.tx, (self.next, item)) -> sent => sent.map_err(|_| Stopped)?,
+            recv(stop) -> _ => return Err(Stopped),
+        }
+        self.metrics.waiting_output += wait.elapsed();
+        self.metrics.items += 1;
+        self.next += 1;
+        Ok(())
+    }
+}
+
+/// A pipeline whose last stage produces `T`; finish it with `sink`
+pub struct Pipeline<T> {
+    options: PipelineOptions,
+    shared: Arc<Shared>,
+    rx: Receiver<(u64, T)>,
+    /// One ticket per item in flight in an ordered pipeline
+    tickets: Option<Receiver<()>>,
+    handles: Vec<(String, JoinHandle<()>)>,
+    metrics: Vec<Arc<Mutex<StageMetrics>>>,
+    start: Instant,
+}
+
+impl<T: Send + 'static> Pipeline<T> {
+    /// Start a pipeline with a source running on its own thread
+    pub fn source<F>(options: PipelineOptions, name: &str, f: F) -> Pipeline<T>
+    where
+        F: FnOnce(&mut Emitter<T>) -> Result<(), BoxError> + Send + 'static,
+    {
+        let options = PipelineOptions {
+            capacity: options.capacity.max(1),
+            ..options
+        };
+        let (tx, rx) = bounded(options.capacity);
+        let (ticket_tx, ticket_rx) = match options.ordered {
+            true => {
+                let (tx, rx) = bounded(options.capacity * 4);
+                (Some(tx), Some(rx))
+            }
+            false => (None, None),
+        };
+        let start = Instant::now();
+        let shared = Arc::new(Shared::new());
+        let cell = Arc::new(Mutex::new(StageMetrics::new(name, 1)));
+
+        let handle = {
+            let shared = Arc::clone(&shared);
+            let cell = Arc::clone(&cell);
+            let name = name.to_string();
+            thread::spawn(move || {
+                let mut emitter = Emitter {
+                    tx,
+                    tickets: ticket_tx,
+                    shared: Arc::clone(&shared),
+                    next: 0,
+                    metrics: StageMetrics::default(),
+                };
+                let started = Instant::now();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| f(&mut emitter)));
+                emitter.metrics.busy = started.elapsed().saturating_sub(emitter.metrics.waiting_output);
+                record(&cell, &emitter.metrics);
+                match result {
+                    Ok(Ok(())) => {}
+                    // Stopped by another stage, which recorded the error
+                    Ok(Err(e)) if e.is::<Stopped>() => {}
+                    Ok(Err(e)) => shared.fail(&name, e),
+                    Err(payload) => shared.fail(&name, panic_error(payload)),
+                }
+            })
+        };
+
+        Pipeline {
+            options,
+            shared,
+            rx,
+            tickets: ticket_rx,
+            handles: vec![(name.to_string(), handle)],
+            metrics: vec![cell],
+            start,
+        }
+    }
+
+    /// Add a stage applying `f` to every item on `workers` threads
+    pub fn map<U, F>(self, name: &str, workers: usize, f: F) -> Pipeline<U>
+    where
+        U: Send + 'static,
+        F: Fn(T) -> Result<U, BoxError> + Send + Sync + 'static,
+    {
+        let workers = workers.max(1);
+        let (tx, rx) = bounded(self.options.capacity);
+        let f = Arc::new(f);
+        let cell = Arc::new(Mutex::new(StageMetrics::new(name, workers)));
+
+        let mut handles = self.handles;
+        for _ in 0..workers {
+            let input = self.rx.clone();
+            let tx = tx.clone();
+            let f = Arc::clone(&f);
+            let shared = Arc::clone(&self.shared);
+            let cell = Arc::clone(&cell);
+            let stage = name.to_string();
+            let handle = thread::spawn(move || {
+                let mut metrics = StageMetrics::default();
+                loop {
+                    let wait = Instant::now();
+                    let received = select! {
+                        recv(input) -> received => received.ok(),
+                        recv(shared.stop_rx) -> _ => None,
+                    };
+                    let Some((seq, item)) = received else {
+                        break;
+                    };
+                    metrics.waiting_input += wait.elapsed();
+                    if shared.stopped() {
+                        break;
+                    }
+
+                    let busy = Instant::now();
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| f(item)));
+                    metrics.busy += busy.elapsed();
+                    let out = match result {
+                        Ok(Ok(out)) => out,
+                        Ok(Err(e)) => {
+                            shared.fail(&stage, e);
+                            break;
+                        }
+                        Err(payload) => {
+                            shared.fail(&stage, panic_error(payload));
+                            break;
+                        }
+                    };
+                    metrics.items += 1;
+
+                    let wait = Instant::now();
+                    let sent = select! {
+                        send(tx, (seq, out)) -> sent => sent.is_ok(),
+                        recv(shared.stop_rx) -> _ => false,
+                    };
+                    if !sent {
+                        break;
+                    }
+                    metrics.waiting_output += wait.elapsed();
+                }
+                record(&cell, &metrics);
+            });
+            handles.push((name.to_string(), handle));
+        }
+
+        let mut metrics = self.metrics;
+        metrics.push(cell);
+        Pipeline {
+            options: self.options,
+            shared: self.shared,
+            rx,
+            tickets: self.tickets,
+            handles,
+            metrics,
+            start: self.start,
+        }
+    }
+
+    /// Run `f` on every item on the calling thread until the pipeline is
+    /// drained or a stage fails, then wait for every stage to finish
+    pub fn sink<F>(self, name: &str, mut f: F) -> Result<PipelineMetrics, PipelineError>
+    where
+        F: FnMut(T) -> Result<(), BoxError>,
+    {
+        let Pipeline {
+            options,
+            shared,
+            rx,
+            tickets,
+            handles,
+            metrics: cells,
+            start,
+        } = self;
+        let mut metrics = StageMetrics::new(name, 1);
+        let mut deliver = |item: T, metrics: &mut StageMetrics| {
+            let busy = Instant::now();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(item)));
+            metrics.busy += busy.elapsed();
+            match result {
+                Ok(Ok(())) => {
+                    metrics.items += 1;
+                    true
+                }
+                Ok(Err(e)) => {
+                    shared.fail(name, e);
+                    false
+                }
+                Err(payload) => {
+                    shared.fail(name, panic_error(payload));
+                    false
+                }
+            }
+        };
+
+        let mut pending = BTreeMap::new();
+        let mut next = 0u64;
+        'receive: loop {
+            // A failed stage may never deliver the item `next` is waiting for,
+            // so stop waiting as soon as the pipeline stops
+            let wait = Instant::now();
+            let received = select! {
+                recv(rx) -> received => received.ok(),
+                recv(shared.stop_rx) -> _ => None,
+            };
+            let Some((seq, item)) = received else {
+                break;
+            };
+            metrics.waiting_input += wait.elapsed();
+            if shared.stopped() {
+                break;
+            }
+            if !options.ordered {
+                if !deliver(item, &mut metrics) {
+                    break;
+                }
+                continue;
+            }
+            pending.insert(seq, item);
+            while let Some(item) = pending.remove(&next) {
+                next += 1;
+                if let Some(tickets) = &tickets {
+                    let _ = tickets.try_recv();
+                }
+                if !deliver(item, &mut metrics) {
+                    break 'receive;
+                }
+            }
+        }
+
+        // Unblock stages still sending, then wait for all of them
+        drop(rx);
+        drop(tickets);
+        drop(pending);
+        for (stage, handle) in handles {
+            if let Err(payload) = handle.join() {
+                shared.fail(&stage, panic_error(payload));
+            }
+        }
+
+        if let Some(error) = shared.error.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            return Err(error);
+        }
+        let mut stages: Vec<StageMetrics> = cells
+            .iter()
+            .map(|cell| cell.lock().unwrap_or_else(PoisonError::into_inner).clone())
+            .collect();
+        stages.push(metrics);
+        Ok(PipelineMetrics {
+            stages,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(options: PipelineOptions, count: u64) -> Pipeline<u64> {
+        Pipeline::source(options, "read", move |out| {
+            for n in 0..count {
+                out.emit(n)?;
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn ordered_output_with_several_workers() {
+        let mut seen = Vec::new();
+        let metrics = numbers(PipelineOptions::default(), 1000)
+            .map("double", 4, |n| Ok(n * 2))
+            .sink("collect", |n| {
+                seen.push(n);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, (0..1000).map(|n| n * 2).collect::<Vec<_>>());
+        assert_eq!(metrics.stages.len(), 3);
+        assert!(metrics.stages.iter().all(|s| s.items == 1000));
+    }
+
+    #[test]
+    fn failing_ordered_map_stops_the_pipeline() {
+        for ordered in [true, false] {
+            let options = PipelineOptions {
+                ordered,
+                ..PipelineOptions::default()
+            };
+            let result = numbers(options, 100_000)
+                .map("parse", 4, |n| {
+                    if n == 5 {
+                        thread::sleep(Duration::from_millis(50));
+                        return Err("bad item".into());
+                    }
+                    Ok(n)
+                })
+                .sink("collect", |_| Ok(()));
+            let error = result.unwrap_err();
+            assert_eq!(error.stage, "parse");
+            assert_eq!(error.error.to_string(), "bad item");
+        }
+    }
+
+    #[test]
+    fn panic_and_sink_errors_are_reported() {
+        let error = numbers(PipelineOptions::default(), 100)
+            .map("parse", 2, |n| {
+                if n == 10 {
+                    panic!("boom");
+                }
+                Ok(n)
+            })
+            .sink("collect", |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(error.stage, "parse");
+        assert!(error.error.to_string().contains("boom"));
+
+        let error = numbers(PipelineOptions::default(), 100_000)
+            .map("parse", 2, Ok)
+            .sink("collect", |n| if n == 3 { Err("full".into()) } else { Ok(()) })
+            .unwrap_err();
+        assert_eq!(error.stage, "collect");
+    }
+}
@@ -0,0 +1,107 @@
+//! Per-record completeness: the share of a record's fields that hold a value.
+//! Shared by null_analyser (score histogram) and ndjson_parallel
+//! (`filter --min-completeness`), so both measure a record the same way.
+//!
+//! Without weights the score is filled top-level fields / top-level fields in
+//! the record. With `weights` (`{"work_email": 3, "mobile_phone": 2, ...}`)
+//! only the listed fields count and a missing field counts as empty, so the
+//! score stays meaningful on cleaned data where empty fields were dropped.
+//! Null, blank strings and empty arrays/objects are empty.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompletenessConfig {
+    pub weights: BTreeMap<String, f64>,
+    /// Histogram buckets of equal width over [0, 1]
+    pub buckets: usize,
+    /// Field written to the per-bucket id lists
+    pub id_field: String,
+}
+
+impl Default for CompletenessConfig {
+    fn default() -> Self {
+        CompletenessConfig {
+            weights: BTreeMap::new(),
+            buckets: 10,
+            id_field: "id".to_string(),
+        }
+    }
+}
+
+/// Weights from a JSON file holding an object of field → non-negative weight
+pub fn load_weights(path: &str) -> Result<BTreeMap<String, f64>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read completeness weights {}: {}", path, e))?;
+    let weights: BTreeMap<String, f64> =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid completeness weights {}: {}", path, e))?;
+    validate_weights(&weights).map_err(|e| format!("Invalid completeness weights {}: {}", path, e))?;
+    Ok(weights)
+}
+
+fn validate_weights(weights: &BTreeMap<String, f64>) -> Result<(), String> {
+    if let Some((field, w)) = weights.iter().find(|(_, w)| !(**w >= 0.0 && w.is_finite())) {
+        return Err(format!("weight for {} must be a non-negative number, got {}", field, w));
+    }
+    if !weights.is_empty() && weights.values().sum::<f64>() <= 0.0 {
+        return Err("weights must not all be zero".to_string());
+    }
+    Ok(())
+}
+
+impl CompletenessConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=1000).contains(&self.buckets) {
+            return Err(format!("completeness buckets must be between 1 and 1000, got {}", self.buckets));
+        }
+        validate_weights(&self.weights).map_err(|e| format!("completeness {}", e))
+    }
+
+    /// Score of one record, or None for a record with nothing to score
+    pub fn score(&self, obj: &Map<String, Value>) -> Option<f64> {
+        let (filled, total) = if self.weights.is_empty() {
+            let filled = obj.values().filter(|v| is_filled(v)).count();
+            (filled as f64, obj.len() as f64)
+        } else {
+            let filled: f64 = self
+                .weights
+                .iter()
+                .filter(|(field, _)| obj.get(*field).is_some_and(is_filled))
+                .map(|(_, w)| w)
+                .sum();
+            (filled, self.weights.values().sum())
+        };
+        (total > 0.0).then(|| filled / total)
+    }
+
+    /// Bucket index; a score of exactly 1 goes in the top bucket
+    pub fn bucket(&self, score: f64) -> usize {
+        ((score * self.buckets as f64) as usize).min(self.buckets - 1)
+    }
+
+    /// `0.30-0.40`; with more than 100 buckets an extra decimal per power of
+    /// ten (`0.125-0.126`), so every bucket, and its id list file, has its own label
+    pub fn bucket_label(&self, bucket: usize) -> String {
+        let width = 1.0 / self.buckets as f64;
+        let precision = (self.buckets - 1).to_string().len().max(2);
+        format!(
+            "{:.p$}-{:.p$}",
+            bucket as f64 * width,
+            (bucket + 1) as f64 * width,
+            p = precision
+        )
+    }
+}
+
+pub fn is_filled(v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        _ => true,
+    }
+}
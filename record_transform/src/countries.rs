@@ -0,0 +1,466 @@
+//! ISO-3166-1 countries and the spellings seen for them in the data.
+
+/// (alpha-2, alpha-3, short name), by alpha-2
+pub const COUNTRIES: &[(&str, &str, &str)] = &[
+    ("AD", "AND", "Andorra"),
+    ("AE", "ARE", "United Arab Emirates"),
+    ("AF", "AFG", "Afghanistan"),
+    ("AG", "ATG", "Antigua and Barbuda"),
+    ("AI", "AIA", "Anguilla"),
+    ("AL", "ALB", "Albania"),
+    ("AM", "ARM", "Armenia"),
+    ("AO", "AGO", "Angola"),
+    ("AQ", "ATA", "Antarctica"),
+    ("AR", "ARG", "Argentina"),
+    ("AS", "ASM", "American Samoa"),
+    ("AT", "AUT", "Austria"),
+    ("AU", "AUS", "Australia"),
+    ("AW", "ABW", "Aruba"),
+    ("AX", "ALA", "Åland Islands"),
+    ("AZ", "AZE", "Azerbaijan"),
+    ("BA", "BIH", "Bosnia and Herzegovina"),
+    ("BB", "BRB", "Barbados"),
+    ("BD", "BGD", "Bangladesh"),
+    ("BE", "BEL", "Belgium"),
+    ("BF", "BFA", "Burkina Faso"),
+    ("BG", "BGR", "Bulgaria"),
+    ("BH", "BHR", "Bahrain"),
+    ("BI", "BDI", "Burundi"),
+    ("BJ", "BEN", "Benin"),
+    ("BL", "BLM", "Saint Barthélemy"),
+    ("BM", "BMU", "Bermuda"),
+    ("BN", "BRN", "Brunei Darussalam"),
+    ("BO", "BOL", "Bolivia"),
+    ("BQ", "BES", "Bonaire, Sint Eustatius and Saba"),
+    ("BR", "BRA", "Brazil"),
+    ("BS", "BHS", "Bahamas"),
+    ("BT", "BTN", "Bhutan"),
+    ("BV", "BVT", "Bouvet Island"),
+    ("BW", "BWA", "Botswana"),
+    ("BY", "BLR", "Belarus"),
+    ("BZ", "BLZ", "Belize"),
+    ("CA", "CAN", "Canada"),
+    ("CC", "CCK", "Cocos (Keeling) Islands"),
+    ("CD", "COD", "Congo, The Democratic Republic of the"),
+    ("CF", "CAF", "Central African Republic"),
+    ("CG", "COG", "Congo"),
+    ("CH", "CHE", "Switzerland"),
+    ("CI", "CIV", "Côte d'Ivoire"),
+    ("CK", "COK", "Cook Islands"),
+    ("CL", "CHL", "Chile"),
+    ("CM", "CMR", "Cameroon"),
+    ("CN", "CHN", "China"),
+    ("CO", "COL", "Colombia"),
+    ("CR", "CRI", "Costa Rica"),
+    ("CU", "CUB", "Cuba"),
+    ("CV", "CPV", "Cabo Verde"),
+    ("CW", "CUW", "Curaçao"),
+    ("CX", "CXR", "Christmas Island"),
+    ("CY", "CYP", "Cyprus"),
+    ("CZ", "CZE", "Czechia"),
+    ("DE", "DEU", "Germany"),
+    ("DJ", "DJI", "Djibouti"),
+    ("DK", "DNK", "Denmark"),
+    ("DM", "DMA", "Dominica"),
+    ("DO", "DOM", "Dominican Republic"),
+    ("DZ", "DZA", "Algeria"),
+    ("EC", "ECU", "Ecuador"),
+    ("EE", "EST", "Estonia"),
+    ("EG", "EGY", "Egypt"),
+    ("EH", "ESH", "Western Sahara"),
+    ("ER", "ERI", "Eritrea"),
+    ("ES", "ESP", "Spain"),
+    ("ET", "ETH", "Ethiopia"),
+    ("FI", "FIN", "Finland"),
+    ("FJ", "FJI", "Fiji"),
+    ("FK", "FLK", "Falkland Islands (Malvinas)"),
+    ("FM", "FSM", "Micronesia, Federated States of"),
+    ("FO", "FRO", "Faroe Islands"),
+    ("FR", "FRA", "France"),
+    ("GA", "GAB", "Gabon"),
+    ("GB", "GBR", "United Kingdom"),
+    ("GD", "GRD", "Grenada"),
+    ("GE", "GEO", "Georgia"),
+    ("GF", "GUF", "French Guiana"),
+    ("GG", "GGY", "Guernsey"),
+    ("GH", "GHA", "Ghana"),
+    ("GI", "GIB", "Gibraltar"),
+    ("GL", "GRL", "Greenland"),
+    ("GM", "GMB", "Gambia"),
+    ("GN", "GIN", "Guinea"),
+    ("GP", "GLP", "Guadeloupe"),
+    ("GQ", "GNQ", "Equatorial Guinea"),
+    ("GR", "GRC", "Greece"),
+    ("GS", "SGS", "South Georgia and the South Sandwich Islands"),
+    ("GT", "GTM", "Guatemala"),
+    ("GU", "GUM", "Guam"),
+    ("GW", "GNB", "Guinea-Bissau"),
+    ("GY", "GUY", "Guyana"),
+    ("HK", "HKG", "Hong Kong"),
+    ("HM", "HMD", "Heard Island and McDonald Islands"),
+    ("HN", "HND", "Honduras"),
+    ("HR", "HRV", "Croatia"),
+    ("HT", "HTI", "Haiti"),
+    ("HU", "HUN", "Hungary"),
+    ("ID", "IDN", "Indonesia"),
+    ("IE", "IRL", "Ireland"),
+    ("IL", "ISR", "Israel"),
+    ("IM", "IMN", "Isle of Man"),
+    ("IN", "IND", "India"),
+    ("IO", "IOT", "British Indian Ocean Territory"),
+    ("IQ", "IRQ", "Iraq"),
+    ("IR", "IRN", "Iran"),
+    ("IS", "ISL", "Iceland"),
+    ("IT", "ITA", "Italy"),
+    ("JE", "JEY", "Jersey"),
+    ("JM", "JAM", "Jamaica"),
+    ("JO", "JOR", "Jordan"),
+    ("JP", "JPN", "Japan"),
+    ("KE", "KEN", "Kenya"),
+    ("KG", "KGZ", "Kyrgyzstan"),
+    ("KH", "KHM", "Cambodia"),
+    ("KI", "KIR", "Kiribati"),
+    ("KM", "COM", "Comoros"),
+    ("KN", "KNA", "Saint Kitts and Nevis"),
+    ("KP", "PRK", "North Korea"),
+    ("KR", "KOR", "South Korea"),
+    ("KW", "KWT", "Kuwait"),
+    ("KY", "CYM", "Cayman Islands"),
+    ("KZ", "KAZ", "Kazakhstan"),
+    ("LA", "LAO", "Laos"),
+    ("LB", "LBN", "Lebanon"),
+    ("LC", "LCA", "Saint Lucia"),
+    ("LI", "LIE", "Liechtenstein"),
+    ("LK", "LKA", "Sri Lanka"),
+    ("LR", "LBR", "Liberia"),
+    ("LS", "LSO", "Lesotho"),
+    ("LT", "LTU", "Lithuania"),
+    ("LU", "LUX", "Luxembourg"),
+    ("LV", "LVA", "Latvia"),
+    ("LY", "LBY", "Libya"),
+    ("MA", "MAR", "Morocco"),
+    ("MC", "MCO", "Monaco"),
+    ("MD", "MDA", "Moldova"),
+    ("ME", "MNE", "Montenegro"),
+    ("MF", "MAF", "Saint Martin (French part)"),
+    ("MG", "MDG", "Madagascar"),
+    ("MH", "MHL", "Marshall Islands"),
+    ("MK", "MKD", "North Macedonia"),
+    ("ML", "MLI", "Mali"),
+    ("MM", "MMR", "Myanmar"),
+    ("MN", "MNG", "Mongolia"),
+    ("MO", "MAC", "Macao"),
+    ("MP", "MNP", "Northern Mariana Islands"),
+    ("MQ", "MTQ", "Martinique"),
+    ("MR", "MRT", "Mauritania"),
+    ("MS", "MSR", "Montserrat"),
+    ("MT", "MLT", "Malta"),
+    ("MU", "MUS", "Mauritius"),
+    ("MV", "MDV", "Maldives"),
+    ("MW", "MWI", "Malawi"),
+    ("MX", "MEX", "Mexico"),
+    ("MY", "MYS", "Malaysia"),
+    ("MZ", "MOZ", "Mozambique"),
+    ("NA", "NAM", "Namibia"),
+    ("NC", "NCL", "New Caledonia"),
+    ("NE", "NER", "Niger"),
+    ("NF", "NFK", "Norfolk Island"),
+    ("NG", "NGA", "Nigeria"),
+    ("NI", "NIC", "Nicaragua"),
+    ("NL", "NLD", "Netherlands"),
+    ("NO", "NOR", "Norway"),
+    ("NP", "NPL", "Nepal"),
+    ("NR", "NRU", "Nauru"),
+    ("NU", "NIU", "Niue"),
+    ("NZ", "NZL", "New Zealand"),
+    ("OM", "OMN", "Oman"),
+    ("PA", "PAN", "Panama"),
+    ("PE", "PER", "Peru"),
+    ("PF", "PYF", "French Polynesia"),
+    ("PG", "PNG", "Papua New Guinea"),
+    ("PH", "PHL", "Philippines"),
+    ("PK", "PAK", "Pakistan"),
+    ("PL", "POL", "Poland"),
+    ("PM", "SPM", "Saint Pierre and Miquelon"),
+    ("PN", "PCN", "Pitcairn"),
+    ("PR", "PRI", "Puerto Rico"),
+    ("PS", "PSE", "Palestine, State of"),
+    ("PT", "PRT", "Portugal"),
+    ("PW", "PLW", "Palau"),
+    ("PY", "PRY", "Paraguay"),
+    ("QA", "QAT", "Qatar"),
+    ("RE", "REU", "Réunion"),
+    ("RO", "ROU", "Romania"),
+    ("RS", "SRB", "Serbia"),
+    ("RU", "RUS", "Russian Federation"),
+    ("RW", "RWA", "Rwanda"),
+    ("SA", "SAU", "Saudi Arabia"),
+    ("SB", "SLB", "Solomon Islands"),
+    ("SC", "SYC", "Seychelles"),
+    ("SD", "SDN", "Sudan"),
+    ("SE", "SWE", "Sweden"),
+    ("SG", "SGP", "Singapore"),
+    ("SH", "SHN", "Saint Helena, Ascension and Tristan da Cunha"),
+    ("SI", "SVN", "Slovenia"),
+    ("SJ", "SJM", "Svalbard and Jan Mayen"),
+    ("SK", "SVK", "Slovakia"),
+    ("SL", "SLE", "Sierra Leone"),
+    ("SM", "SMR", "San Marino"),
+    ("SN", "SEN", "Senegal"),
+    ("SO", "SOM", "Somalia"),
+    ("SR", "SUR", "Suriname"),
+    ("SS", "SSD", "South Sudan"),
+    ("ST", "STP", "Sao Tome and Principe"),
+    ("SV", "SLV", "El Salvador"),
+    ("SX", "SXM", "Sint Maarten (Dutch part)"),
+    ("SY", "SYR", "Syria"),
+    ("SZ", "SWZ", "Eswatini"),
+    ("TC", "TCA", "Turks and Caicos Islands"),
+    ("TD", "TCD", "Chad"),
+    ("TF", "ATF", "French Southern Territories"),
+    ("TG", "TGO", "Togo"),
+    ("TH", "THA", "Thailand"),
+    ("TJ", "TJK", "Tajikistan"),
+    ("TK", "TKL", "Tokelau"),
+    ("TL", "TLS", "Timor-Leste"),
+    ("TM", "TKM", "Turkmenistan"),
+    ("TN", "TUN", "Tunisia"),
+    ("TO", "TON", "Tonga"),
+    ("TR", "TUR", "Türkiye"),
+    ("TT", "TTO", "Trinidad and Tobago"),
+    ("TV", "TUV", "Tuvalu"),
+    ("TW", "TWN", "Taiwan"),
+    ("TZ", "TZA", "Tanzania"),
+    ("UA", "UKR", "Ukraine"),
+    ("UG", "UGA", "Uganda"),
+    ("UM", "UMI", "United States Minor Outlying Islands"),
+    ("US", "USA", "United States"),
+    ("UY", "URY", "Uruguay"),
+    ("UZ", "UZB", "Uzbekistan"),
+    ("VA", "VAT", "Holy See (Vatican City State)"),
+    ("VC", "VCT", "Saint Vincent and the Grenadines"),
+    ("VE", "VEN", "Venezuela"),
+    ("VG", "VGB", "Virgin Islands, British"),
+    ("VI", "VIR", "Virgin Islands, U.S."),
+    ("VN", "VNM", "Vietnam"),
+    ("VU", "VUT", "Vanuatu"),
+    ("WF", "WLF", "Wallis and Futuna"),
+    ("WS", "WSM", "Samoa"),
+    ("YE", "YEM", "Yemen"),
+    ("YT", "MYT", "Mayotte"),
+    ("ZA", "ZAF", "South Africa"),
+    ("ZM", "ZMB", "Zambia"),
+    ("ZW", "ZWE", "Zimbabwe"),
+];
+
+/// Other names, lowercase, beyond the short names above
+pub const ALIASES: &[(&str, &str)] = &[
+    ("america", "US"),
+    ("arab republic of egypt", "EG"),
+    ("argentine republic", "AR"),
+    ("bolivarian republic of venezuela", "VE"),
+    ("bolivia, plurinational state of", "BO"),
+    ("britain", "GB"),
+    ("british virgin islands", "VG"),
+    ("brunei", "BN"),
+    ("burma", "MM"),
+    ("cape verde", "CV"),
+    ("commonwealth of dominica", "DM"),
+    ("commonwealth of the bahamas", "BS"),
+    ("commonwealth of the northern mariana islands", "MP"),
+    ("cote d'ivoire", "CI"),
+    ("czech republic", "CZ"),
+    ("democratic people's republic of korea", "KP"),
+    ("democratic republic of sao tome and principe", "ST"),
+    ("democratic republic of the congo", "CD"),
+    ("democratic republic of timor-leste", "TL"),
+    ("democratic socialist republic of sri lanka", "LK"),
+    ("dr congo", "CD"),
+    ("east timor", "TL"),
+    ("eastern republic of uruguay", "UY"),
+    ("england", "GB"),
+    ("federal democratic republic of ethiopia", "ET"),
+    ("federal democratic republic of nepal", "NP"),
+    ("federal republic of germany", "DE"),
+    ("federal republic of nigeria", "NG"),
+    ("federal republic of somalia", "SO"),
+    ("federated states of micronesia", "FM"),
+    ("federative republic of brazil", "BR"),
+    ("french republic", "FR"),
+    ("gabonese republic", "GA"),
+    ("grand duchy of luxembourg", "LU"),
+    ("great britain", "GB"),
+    ("hashemite kingdom of jordan", "JO"),
+    ("hellenic republic", "GR"),
+    ("holland", "NL"),
+    ("hong kong special administrative region of china", "HK"),
+    ("independent state of papua new guinea", "PG"),
+    ("independent state of samoa", "WS"),
+    ("iran, islamic republic of", "IR"),
+    ("islamic republic of afghanistan", "AF"),
+    ("islamic republic of iran", "IR"),
+    ("islamic republic of mauritania", "MR"),
+    ("islamic republic of pakistan", "PK"),
+    ("italian republic", "IT"),
+    ("ivory coast", "CI"),
+    ("kingdom of bahrain", "BH"),
+    ("kingdom of belgium", "BE"),
+    ("kingdom of bhutan", "BT"),
+    ("kingdom of cambodia", "KH"),
+    ("kingdom of denmark", "DK"),
+    ("kingdom of eswatini", "SZ"),
+    ("kingdom of lesotho", "LS"),
+    ("kingdom of morocco", "MA"),
+    ("kingdom of norway", "NO"),
+    ("kingdom of saudi arabia", "SA"),
+    ("kingdom of spain", "ES"),
+    ("kingdom of sweden", "SE"),
+    ("kingdom of thailand", "TH"),
+    ("kingdom of the netherlands", "NL"),
+    ("kingdom of tonga", "TO"),
+    ("korea", "KR"),
+    ("korea, democratic people's republic of", "KP"),
+    ("korea, republic of", "KR"),
+    ("kyrgyz republic", "KG"),
+    ("lao people's democratic republic", "LA"),
+    ("lebanese republic", "LB"),
+    ("macao special administrative region of china", "MO"),
+    ("macau", "MO"),
+    ("macedonia", "MK"),
+    ("micronesia", "FM"),
+    ("moldova, republic of", "MD"),
+    ("northern ireland", "GB"),
+    ("palestine", "PS"),
+    ("people's democratic republic of algeria", "DZ"),
+    ("people's republic of bangladesh", "BD"),
+    ("people's republic of china", "CN"),
+    ("plurinational state of bolivia", "BO"),
+    ("portuguese republic", "PT"),
+    ("principality of andorra", "AD"),
+    ("principality of liechtenstein", "LI"),
+    ("principality of monaco", "MC"),
+    ("republic of albania", "AL"),
+    ("republic of angola", "AO"),
+    ("republic of armenia", "AM"),
+    ("republic of austria", "AT"),
+    ("republic of azerbaijan", "AZ"),
+    ("republic of belarus", "BY"),
+    ("republic of benin", "BJ"),
+    ("republic of bosnia and herzegovina", "BA"),
+    ("republic of botswana", "BW"),
+    ("republic of bulgaria", "BG"),
+    ("republic of burundi", "BI"),
+    ("republic of cabo verde", "CV"),
+    ("republic of cameroon", "CM"),
+    ("republic of chad", "TD"),
+    ("republic of chile", "CL"),
+    ("republic of colombia", "CO"),
+    ("republic of costa rica", "CR"),
+    ("republic of croatia", "HR"),
+    ("republic of cuba", "CU"),
+    ("republic of cyprus", "CY"),
+    ("republic of côte d'ivoire", "CI"),
+    ("republic of djibouti", "DJ"),
+    ("republic of ecuador", "EC"),
+    ("republic of el salvador", "SV"),
+    ("republic of equatorial guinea", "GQ"),
+    ("republic of estonia", "EE"),
+    ("republic of fiji", "FJ"),
+    ("republic of finland", "FI"),
+    ("republic of ghana", "GH"),
+    ("republic of guatemala", "GT"),
+    ("republic of guinea", "GN"),
+    ("republic of guinea-bissau", "GW"),
+    ("republic of guyana", "GY"),
+    ("republic of haiti", "HT"),
+    ("republic of honduras", "HN"),
+    ("republic of iceland", "IS"),
+    ("republic of india", "IN"),
+    ("republic of indonesia", "ID"),
+    ("republic of iraq", "IQ"),
+    ("republic of kazakhstan", "KZ"),
+    ("republic of kenya", "KE"),
+    ("republic of kiribati", "KI"),
+    ("republic of latvia", "LV"),
+    ("republic of liberia", "LR"),
+    ("republic of lithuania", "LT"),
+    ("republic of madagascar", "MG"),
+    ("republic of malawi", "MW"),
+    ("republic of maldives", "MV"),
+    ("republic of mali", "ML"),
+    ("republic of malta", "MT"),
+    ("republic of mauritius", "MU"),
+    ("republic of moldova", "MD"),
+    ("republic of mozambique", "MZ"),
+    ("republic of myanmar", "MM"),
+    ("republic of namibia", "NA"),
+    ("republic of nauru", "NR"),
+    ("republic of nicaragua", "NI"),
+    ("republic of north macedonia", "MK"),
+    ("republic of palau", "PW"),
+    ("republic of panama", "PA"),
+    ("republic of paraguay", "PY"),
+    ("republic of peru", "PE"),
+    ("republic of poland", "PL"),
+    ("republic of san marino", "SM"),
+    ("republic of senegal", "SN"),
+    ("republic of serbia", "RS"),
+    ("republic of seychelles", "SC"),
+    ("republic of sierra leone", "SL"),
+    ("republic of singapore", "SG"),
+    ("republic of slovenia", "SI"),
+    ("republic of south africa", "ZA"),
+    ("republic of south sudan", "SS"),
+    ("republic of suriname", "SR"),
+    ("republic of tajikistan", "TJ"),
+    ("republic of the congo", "CG"),
+    ("republic of the gambia", "GM"),
+    ("republic of the marshall islands", "MH"),
+    ("republic of the niger", "NE"),
+    ("republic of the philippines", "PH"),
+    ("republic of the sudan", "SD"),
+    ("republic of trinidad and tobago", "TT"),
+    ("republic of tunisia", "TN"),
+    ("republic of türkiye", "TR"),
+    ("republic of uganda", "UG"),
+    ("republic of uzbekistan", "UZ"),
+    ("republic of vanuatu", "VU"),
+    ("republic of yemen", "YE"),
+    ("republic of zambia", "ZM"),
+    ("republic of zimbabwe", "ZW"),
+    ("russia", "RU"),
+    ("rwandese republic", "RW"),
+    ("scotland", "GB"),
+    ("slovak republic", "SK"),
+    ("socialist republic of viet nam", "VN"),
+    ("state of israel", "IL"),
+    ("state of kuwait", "KW"),
+    ("state of qatar", "QA"),
+    ("sultanate of oman", "OM"),
+    ("swaziland", "SZ"),
+    ("swiss confederation", "CH"),
+    ("syrian arab republic", "SY"),
+    ("taiwan, province of china", "TW"),
+    ("tanzania, united republic of", "TZ"),
+    ("the netherlands", "NL"),
+    ("the state of eritrea", "ER"),
+    ("the state of palestine", "PS"),
+    ("togolese republic", "TG"),
+    ("turkey", "TR"),
+    ("u.k.", "GB"),
+    ("u.s.", "US"),
+    ("u.s.a.", "US"),
+    ("uae", "AE"),
+    ("uk", "GB"),
+    ("union of the comoros", "KM"),
+    ("united kingdom of great britain and northern ireland", "GB"),
+    ("united mexican states", "MX"),
+    ("united republic of tanzania", "TZ"),
+    ("united states of america", "US"),
+    ("usa", "US"),
+    ("vatican", "VA"),
+    ("venezuela, bolivarian republic of", "VE"),
+    ("viet nam", "VN"),
+    ("virgin islands of the united states", "VI"),
+    ("wales", "GB"),
+];
@@ -0,0 +1,14 @@
+//! Per-record transforms shared by gz_cleaner, gz_to_parquet and
+//! ndjson_parallel. Each stage works on a parsed JSON object, so it runs the
+//! same way whatever the tool does with the record afterwards.
+//!
+//! `line_reader` is the step before parsing: it reads NDJSON lines with
+//! BOM, CRLF, invalid UTF-8 and over-long line handling.
+
+mod countries;
+pub mod line_reader;
+pub mod normalise;
+pub mod path;
+pub mod pii;
+pub mod stages;
+pub mod transform;
@@ -0,0 +1,269 @@
+//! Line reader for NDJSON shards that doesn't give up on a bad line.
+//!
+//! - a UTF-8 byte order mark at the start of a line is stripped
+//! - `\r\n` line endings become `\n`
+//! - invalid UTF-8 is rejected (the line is skipped), repaired with U+FFFD
+//!   (`lossy`) or handed over as raw bytes (`passthrough`)
+//! - a line longer than `max_line_bytes` is skipped without being buffered
+//!   beyond the limit
+//!
+//! Rejected and over-long lines are streamed to an optional quarantine
+//! writer, and every event is counted per reader.
+
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::mem;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 64 MB; the largest records in the dataset are well under 1 MB
+pub const DEFAULT_MAX_LINE_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Utf8Mode {
+    #[default]
+    Reject,
+    Lossy,
+    Passthrough,
+}
+
+impl Utf8Mode {
+    pub fn parse(text: &str) -> Result<Utf8Mode, String> {
+        match text {
+            "reject" => Ok(Utf8Mode::Reject),
+            "lossy" => Ok(Utf8Mode::Lossy),
+            "passthrough" => Ok(Utf8Mode::Passthrough),
+            _ => Err(format!("Invalid UTF-8 mode {} (reject, lossy or passthrough)", text)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LineOptions {
+    pub utf8: Utf8Mode,
+    pub max_line_bytes: usize,
+}
+
+impl Default for LineOptions {
+    fn default() -> Self {
+        LineOptions {
+            utf8: Utf8Mode::default(),
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+        }
+    }
+}
+
+/// Events seen by one reader; merge across files
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LineCounts {
+    pub lines: u64,
+    pub boms: u64,
+    pub crlf: u64,
+    pub utf8_rejected: u64,
+    pub utf8_repaired: u64,
+    pub utf8_passed: u64,
+    pub oversized: u64,
+    pub quarantined: u64,
+}
+
+impl LineCounts {
+    pub fn merge(&mut self, other: &LineCounts) {
+        self.lines += other.lines;
+        self.boms += other.boms;
+        self.crlf += other.crlf;
+        self.utf8_rejected += other.utf8_rejected;
+        self.utf8_repaired += other.utf8_repaired;
+        self.utf8_passed += other.utf8_passed;
+        self.oversized += other.oversized;
+        self.quarantined += other.quarantined;
+    }
+
+    /// One line per event that happened, e.g. `3 BOMs stripped`
+    pub fn describe(&self) -> Vec<String> {
+        [
+            (self.boms, "BOMs stripped"),
+            (self.crlf, "CRLF line endings normalised"),
+            (self.utf8_rejected, "invalid UTF-8 lines rejected"),
+            (self.utf8_repaired, "invalid UTF-8 lines repaired"),
+            (self.utf8_passed, "invalid UTF-8 lines passed through"),
+            (self.oversized, "over-long lines skipped"),
+            (self.quarantined, "lines quarantined"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, event)| format!("{} {}", n, event))
+        .collect()
+    }
+}
+
+/// A line without its terminator
+pub enum Line<'a> {
+    Text(&'a str),
+    /// Invalid UTF-8 in `passthrough` mode
+    Bytes(&'a [u8]),
+}
+
+enum Raw {
+    Line,
+    Oversized,
+    Eof,
+}
+
+pub struct LineReader<R, Q = io::Sink> {
+    inner: R,
+    options: LineOptions,
+    quarantine: Option<Q>,
+    line: Vec<u8>,
+    text: String,
+    counts: LineCounts,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(inner: R, options: LineOptions) -> Self {
+        LineReader::with_quarantine(inner, options, None)
+    }
+}
+
+impl<R: BufRead, Q: Write> LineReader<R, Q> {
+    /// Also write rejected and over-long lines to `quarantine`, one per line
+    pub fn with_quarantine(inner: R, options: LineOptions, quarantine: Option<Q>) -> Self {
+        LineReader {
+            inner,
+            options,
+            quarantine,
+            line: Vec::new(),
+            text: String::new(),
+            counts: LineCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> &LineCounts {
+        &self.counts
+    }
+
+    /// The quarantine writer, so a compressing one can be finished
+    pub fn into_quarantine(self) -> Option<Q> {
+        self.quarantine
+    }
+
+    /// The quarantine writer, for callers that quarantine lines of their own
+    pub fn quarantine_mut(&mut self) -> Option<&mut Q> {
+        self.quarantine.as_mut()
+    }
+
+    /// The next line that isn't rejected, or None at the end of input
+    pub fn next_line(&mut self) -> io::Result<Option<Line<'_>>> {
+        // The last line may have been moved into `text`; take its buffer back
+        if self.line.capacity() < self.text.capacity() {
+            self.line = mem::take(&mut self.text).into_bytes();
+        }
+
+        let valid = loop {
+            self.line.clear();
+            match self.read_raw()? {
+                Raw::Eof => return Ok(None),
+                Raw::Oversized => {
+                    self.counts.lines += 1;
+                    self.counts.oversized += 1;
+                    continue;
+                }
+                Raw::Line => self.counts.lines += 1,
+            }
+            if self.line.last() == Some(&b'\r') {
+                self.line.pop();
+                self.counts.crlf += 1;
+            }
+            if self.line.starts_with(BOM) {
+                self.line.drain(..BOM.len());
+                self.counts.boms += 1;
+            }
+            match String::from_utf8(mem::take(&mut self.line)) {
+                Ok(text) => {
+                    self.text = text;
+                    break true;
+                }
+                Err(e) => self.line = e.into_bytes(),
+            }
+            match self.options.utf8 {
+                Utf8Mode::Reject => {
+                    self.counts.utf8_rejected += 1;
+                    if let Some(quarantine) = &mut self.quarantine {
+                        quarantine.write_all(&self.line)?;
+                        quarantine.write_all(b"\n")?;
+                        self.counts.quarantined += 1;
+                    }
+                }
+                Utf8Mode::Lossy => {
+                    self.counts.utf8_repaired += 1;
+                    self.text = String::from_utf8_lossy(&self.line).into_owned();
+                    self.line.clear();
+                    break true;
+                }
+                Utf8Mode::Passthrough => {
+                    self.counts.utf8_passed += 1;
+                    break false;
+                }
+            }
+        };
+
+        if valid {
+            Ok(Some(Line::Text(&self.text)))
+        } else {
+            Ok(Some(Line::Bytes(&self.line)))
+        }
+    }
+
+    /// Read up to the next `\n` into `line`; past `max_line_bytes` the rest
+    /// goes straight to the quarantine instead
+    fn read_raw(&mut self) -> io::Result<Raw> {
+        let mut read_any = false;
+        let mut oversized = false;
+        loop {
+            let available = match self.inner.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                if !read_any {
+                    return Ok(Raw::Eof);
+                }
+                break;
+            }
+            read_any = true;
+
+            let (chunk, done) = match available.iter().position(|&b| b == b'\n') {
+                Some(end) => (&available[..end], true),
+                None => (available, false),
+            };
+            if !oversized && self.line.len() + chunk.len() > self.options.max_line_bytes {
+                oversized = true;
+                if let Some(quarantine) = &mut self.quarantine {
+                    quarantine.write_all(&self.line)?;
+                }
+                self.line.clear();
+            }
+            if oversized {
+                if let Some(quarantine) = &mut self.quarantine {
+                    quarantine.write_all(chunk)?;
+                }
+            } else {
+                self.line.extend_from_slice(chunk);
+            }
+
+            let used = chunk.len() + usize::from(done);
+            self.inner.consume(used);
+            if done {
+                break;
+            }
+        }
+
+        if !oversized {
+            return Ok(Raw::Line);
+        }
+        if let Some(quarantine) = &mut self.quarantine {
+            quarantine.write_all(b"\n")?;
+            self.counts.quarantined += 1;
+        }
+        Ok(Raw::Oversized)
+    }
+}
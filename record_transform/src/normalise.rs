@@ -0,0 +1,401 @@
+//! Value normalisation, run before PII policies, cleaning or conversion:
+//!
+//! ```json
+//! {
+//!   "default_region": "US",
+//!   "emails": ["work_email", "emails.address", "personal_emails"],
+//!   "phones": ["mobile_phone", "phone_numbers"],
+//!   "social_urls": { "linkedin_url": "linkedin_username", "github_url": "github_username" },
+//!   "countries": ["location_country", "job_company_location_country", "countries"],
+//!   "country_format": "alpha2"
+//! }
+//! ```
+//!
+//! - emails are trimmed and lowercased (`mailto:` dropped)
+//! - phones become E.164; numbers without a `+` or international prefix are
+//!   read as national numbers of `default_region`
+//! - LinkedIn, Facebook, GitHub and Twitter/X URLs become `host/path`
+//!   (`linkedin.com/in/john-smith`) and the username is written to the field
+//!   named next to the URL, in the same object
+//! - country names, alpha-2 and alpha-3 codes map to ISO-3166 `alpha2`,
+//!   `alpha3` or the short `name`
+//!
+//! Keys missing from the config keep the defaults above (`social_urls` also
+//! covers `facebook_url` and `twitter_url`). A value that can't be normalised
+//! is left as it was and counted.
+
+use crate::countries::{ALIASES, COUNTRIES};
+use crate::path::FieldPath;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CountryFormat {
+    Alpha2,
+    Alpha3,
+    Name,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct NormaliseConfig {
+    pub default_region: String,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub social_urls: BTreeMap<String, Option<String>>,
+    pub countries: Vec<String>,
+    pub country_format: CountryFormat,
+}
+
+impl Default for NormaliseConfig {
+    fn default() -> Self {
+        let social = ["linkedin", "facebook", "github", "twitter"];
+        NormaliseConfig {
+            default_region: "US".to_string(),
+            emails: vec!["work_email".into(), "emails.address".into(), "personal_emails".into()],
+            phones: vec!["mobile_phone".into(), "phone_numbers".into()],
+            social_urls: social
+                .iter()
+                .map(|s| (format!("{}_url", s), Some(format!("{}_username", s))))
+                .collect(),
+            countries: vec![
+                "location_country".into(),
+                "job_company_location_country".into(),
+                "countries".into(),
+            ],
+            country_format: CountryFormat::Alpha2,
+        }
+    }
+}
+
+/// Dialling rules for a default region: calling code, trunk prefix dropped
+/// from national numbers, valid national number lengths, international prefix
+struct Region {
+    code: &'static str,
+    calling_code: &'static str,
+    trunk_prefix: &'static str,
+    national_lengths: &'static [usize],
+    international_prefix: &'static str,
+}
+
+const REGIONS: &[Region] = &[
+    Region { code: "US", calling_code: "1", trunk_prefix: "1", national_lengths: &[10], international_prefix: "011" },
+    Region { code: "CA", calling_code: "1", trunk_prefix: "1", national_lengths: &[10], international_prefix: "011" },
+    Region { code: "GB", calling_code: "44", trunk_prefix: "0", national_lengths: &[9, 10], international_prefix: "00" },
+    Region { code: "IE", calling_code: "353", trunk_prefix: "0", national_lengths: &[7, 8, 9], international_prefix: "00" },
+    Region { code: "IN", calling_code: "91", trunk_prefix: "0", national_lengths: &[10], international_prefix: "00" },
+    Region { code: "AU", calling_code: "61", trunk_prefix: "0", national_lengths: &[9], international_prefix: "0011" },
+    Region { code: "NZ", calling_code: "64", trunk_prefix: "0", national_lengths: &[8, 9, 10], international_prefix: "00" },
+    Region { code: "DE", calling_code: "49", trunk_prefix: "0", national_lengths: &[7, 8, 9, 10, 11], international_prefix: "00" },
+    Region { code: "FR", calling_code: "33", trunk_prefix: "0", national_lengths: &[9], international_prefix: "00" },
+    Region { code: "ES", calling_code: "34", trunk_prefix: "", national_lengths: &[9], international_prefix: "00" },
+    Region { code: "IT", calling_code: "39", trunk_prefix: "", national_lengths: &[9, 10], international_prefix: "00" },
+    Region { code: "NL", calling_code: "31", trunk_prefix: "0", national_lengths: &[9], international_prefix: "00" },
+    Region { code: "BR", calling_code: "55", trunk_prefix: "0", national_lengths: &[10, 11], international_prefix: "00" },
+    Region { code: "MX", calling_code: "52", trunk_prefix: "", national_lengths: &[10], international_prefix: "00" },
+    Region { code: "ZA", calling_code: "27", trunk_prefix: "0", national_lengths: &[9], international_prefix: "00" },
+    Region { code: "SG", calling_code: "65", trunk_prefix: "", national_lengths: &[8], international_prefix: "000" },
+    Region { code: "PH", calling_code: "63", trunk_prefix: "0", national_lengths: &[10], international_prefix: "00" },
+    Region { code: "AE", calling_code: "971", trunk_prefix: "0", national_lengths: &[8, 9], international_prefix: "00" },
+    Region { code: "JP", calling_code: "81", trunk_prefix: "0", national_lengths: &[9, 10], international_prefix: "010" },
+    Region { code: "CN", calling_code: "86", trunk_prefix: "0", national_lengths: &[10, 11], international_prefix: "00" },
+];
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Email,
+    Phone,
+    SocialUrl,
+    Country,
+}
+
+struct Rule {
+    path: FieldPath,
+    kind: Kind,
+    username_field: Option<String>,
+}
+
+/// Per field: values changed and values that could not be normalised
+#[derive(Default, Clone, Debug)]
+pub struct NormaliseCounts(Vec<(u64, u64)>);
+
+impl NormaliseCounts {
+    pub fn merge(&mut self, other: &NormaliseCounts) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), (0, 0));
+        }
+        for (total, (changed, failed)) in self.0.iter_mut().zip(&other.0) {
+            total.0 += changed;
+            total.1 += failed;
+        }
+    }
+
+    fn add(&mut self, rule: usize, changed: u64, failed: u64) {
+        if changed == 0 && failed == 0 {
+            return;
+        }
+        if self.0.len() <= rule {
+            self.0.resize(rule + 1, (0, 0));
+        }
+        self.0[rule].0 += changed;
+        self.0[rule].1 += failed;
+    }
+}
+
+pub struct Normaliser {
+    rules: Vec<Rule>,
+    region: &'static Region,
+    countries: HashMap<String, usize>,
+    country_format: CountryFormat,
+}
+
+/// What happened to one value
+enum Outcome {
+    Unchanged,
+    Changed(String),
+    Failed,
+}
+
+impl Normaliser {
+    pub fn load(path: &str) -> Result<Normaliser, Box<dyn Error>> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read normalise config {}: {}", path, e))?;
+        let config: NormaliseConfig =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid normalise config {}: {}", path, e))?;
+        Normaliser::new(config)
+    }
+
+    pub fn new(config: NormaliseConfig) -> Result<Normaliser, Box<dyn Error>> {
+        let region_code = config.default_region.to_uppercase();
+        let region = REGIONS.iter().find(|r| r.code == region_code).ok_or_else(|| {
+            let known: Vec<&str> = REGIONS.iter().map(|r| r.code).collect();
+            format!("Unknown default_region {} (known: {})", config.default_region, known.join(", "))
+        })?;
+
+        let parse = |path: &str| FieldPath::parse(path).map_err(|e| format!("Invalid normalise config: {}", e));
+        let mut rules = Vec::new();
+        for (paths, kind) in [(&config.emails, Kind::Email), (&config.phones, Kind::Phone), (&config.countries, Kind::Country)] {
+            for path in paths {
+                rules.push(Rule {
+                    path: parse(path)?,
+                    kind,
+                    username_field: None,
+                });
+            }
+        }
+        for (path, username_field) in config.social_urls {
+            rules.push(Rule {
+                path: parse(&path)?,
+                kind: Kind::SocialUrl,
+                username_field,
+            });
+        }
+
+        let mut countries = HashMap::new();
+        for (i, (alpha2, alpha3, name)) in COUNTRIES.iter().enumerate() {
+            countries.insert(alpha2.to_lowercase(), i);
+            countries.insert(alpha3.to_lowercase(), i);
+            countries.insert(name.to_lowercase(), i);
+        }
+        for (alias, alpha2) in ALIASES {
+            if let Some(i) = COUNTRIES.iter().position(|c| c.0 == *alpha2) {
+                countries.insert(alias.to_string(), i);
+            }
+        }
+
+        Ok(Normaliser {
+            rules,
+            region,
+            countries,
+            country_format: config.country_format,
+        })
+    }
+
+    /// One line per field, e.g. `mobile_phone: 1204 changed, 17 unnormalisable`
+    pub fn describe(&self, counts: &NormaliseCounts) -> Vec<String> {
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let (changed, failed) = counts.0.get(i).copied().unwrap_or((0, 0));
+                format!("{}: {} changed, {} unnormalisable", rule.path.as_str(), changed, failed)
+            })
+            .collect()
+    }
+
+    pub fn apply(&self, record: &mut Map<String, Value>, counts: &mut NormaliseCounts) {
+        for (i, rule) in self.rules.iter().enumerate() {
+            let (mut changed, mut failed) = (0, 0);
+            rule.path.visit(record, &mut |object, key| {
+                let mut username = None;
+                let mut normalise = |s: &str| {
+                    let outcome = self.normalise(rule.kind, s, &mut username);
+                    match &outcome {
+                        Outcome::Changed(_) => changed += 1,
+                        Outcome::Failed => failed += 1,
+                        Outcome::Unchanged => {}
+                    }
+                    outcome
+                };
+                match object.get_mut(key) {
+                    Some(Value::String(s)) => {
+                        if let Outcome::Changed(new) = normalise(s) {
+                            *s = new;
+                        }
+                    }
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            if let Value::String(s) = item {
+                                if let Outcome::Changed(new) = normalise(s) {
+                                    *s = new;
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                // Only a single URL value has an unambiguous username
+                if let (Some(field), Some(name), Some(Value::String(_))) =
+                    (&rule.username_field, username, object.get(key))
+                {
+                    object.insert(field.clone(), Value::String(name));
+                }
+            });
+            counts.add(i, changed, failed);
+        }
+    }
+
+    fn normalise(&self, kind: Kind, raw: &str, username: &mut Option<String>) -> Outcome {
+        if raw.trim().is_empty() {
+            return Outcome::Unchanged;
+        }
+        let new = match kind {
+            Kind::Email => normalise_email(raw),
+            Kind::Phone => self.e164(raw),
+            Kind::Country => self.country(raw),
+            Kind::SocialUrl => canonical_social_url(raw).map(|(url, name)| {
+                *username = Some(name);
+                url
+            }),
+        };
+        match new {
+            Some(new) if new == raw => Outcome::Unchanged,
+            Some(new) => Outcome::Changed(new),
+            None => Outcome::Failed,
+        }
+    }
+
+    fn country(&self, raw: &str) -> Option<String> {
+        let key = raw.trim().to_lowercase();
+        let key = key.strip_prefix("the ").unwrap_or(&key);
+        let (alpha2, alpha3, name) = COUNTRIES[*self.countries.get(key)?];
+        Some(match self.country_format {
+            CountryFormat::Alpha2 => alpha2.to_string(),
+            CountryFormat::Alpha3 => alpha3.to_string(),
+            CountryFormat::Name => name.to_string(),
+        })
+    }
+
+    /// `+<calling code><number>`, or None when the digits can't be a valid number
+    fn e164(&self, raw: &str) -> Option<String> {
+        let lower = raw.trim().to_lowercase();
+        // Extensions aren't part of E.164
+        let number = ["ext", "x", "#"]
+            .iter()
+            .filter_map(|sep| lower.find(sep))
+            .min()
+            .map_or(lower.as_str(), |i| &lower[..i]);
+        if !number
+            .chars()
+            .all(|c| c.is_ascii_digit() || " -.()/+\u{a0}".contains(c))
+        {
+            return None;
+        }
+        let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+        let region = self.region;
+
+        let international = if number.trim_start().starts_with('+') {
+            Some(digits.as_str())
+        } else {
+            digits.strip_prefix(region.international_prefix)
+        };
+        if let Some(full) = international {
+            return (8..=15).contains(&full.len()).then(|| format!("+{}", full));
+        }
+
+        let national = match digits.strip_prefix(region.trunk_prefix) {
+            Some(rest) if !region.trunk_prefix.is_empty() && region.national_lengths.contains(&rest.len()) => rest,
+            _ => digits.as_str(),
+        };
+        if region.national_lengths.contains(&national.len()) {
+            return Some(format!("+{}{}", region.calling_code, national));
+        }
+        // Calling code written without the `+`
+        match national.strip_prefix(region.calling_code) {
+            Some(rest) if region.national_lengths.contains(&rest.len()) => Some(format!("+{}", national)),
+            _ => None,
+        }
+    }
+}
+
+fn normalise_email(raw: &str) -> Option<String> {
+    let email = raw.trim();
+    let email = email.strip_prefix("mailto:").unwrap_or(email).to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let valid = !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace);
+    valid.then_some(email)
+}
+
+/// (`host/path`, username) for a LinkedIn, Facebook, GitHub or Twitter/X profile URL
+fn canonical_social_url(raw: &str) -> Option<(String, String)> {
+    let url = raw.trim().to_lowercase();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(&url);
+    let (location, query) = match url.split_once('?') {
+        Some((location, query)) => (location, query),
+        None => (url, ""),
+    };
+    let location = location.split('#').next().unwrap_or_default();
+    let mut parts = location.split('/').filter(|p| !p.is_empty());
+    let host = parts.next()?;
+    let path: Vec<&str> = parts.collect();
+
+    // Drop `www.`, `m.` and country subdomains (`uk.linkedin.com`)
+    let site = ["linkedin.com", "facebook.com", "fb.com", "github.com", "twitter.com", "x.com"]
+        .into_iter()
+        .find(|site| host == *site || host.ends_with(&format!(".{}", site)))?;
+
+    let (site, username) = match site {
+        "linkedin.com" => match path.as_slice() {
+            ["in" | "pub", name, ..] => ("linkedin.com/in", name.to_string()),
+            _ => return None,
+        },
+        "facebook.com" | "fb.com" => match path.as_slice() {
+            ["profile.php", ..] => {
+                let id = query.split('&').find_map(|kv| kv.strip_prefix("id="))?;
+                ("facebook.com", id.to_string())
+            }
+            ["people", _, id, ..] => ("facebook.com", id.to_string()),
+            ["pages" | "groups" | "events" | "sharer.php", ..] => return None,
+            [name, ..] => ("facebook.com", name.to_string()),
+            [] => return None,
+        },
+        "github.com" => ("github.com", path.first()?.to_string()),
+        _ => ("twitter.com", path.first()?.trim_start_matches('@').to_string()),
+    };
+    if username.is_empty() {
+        return None;
+    }
+    Some((format!("{}/{}", site, username), username))
+}
@@ -0,0 +1,98 @@
+//! Dotted field paths (`work_email`, `emails.address`) that step through
+//! arrays, so `emails.address` names the `address` of every element of
+//! `emails`.
+
+use serde_json::{Map, Value};
+
+#[derive(Clone, Debug)]
+pub struct FieldPath {
+    text: String,
+    segments: Vec<String>,
+}
+
+impl FieldPath {
+    pub fn parse(text: &str) -> Result<FieldPath, String> {
+        let segments: Vec<String> = text.split('.').map(str::to_string).collect();
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(format!("Invalid field path {:?}", text));
+        }
+        Ok(FieldPath {
+            text: text.to_string(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The value at a path that doesn't cross arrays
+    pub fn get<'a>(&self, record: &'a Map<String, Value>) -> Option<&'a Value> {
+        let (last, parents) = self.segments.split_last()?;
+        let mut object = record;
+        for segment in parents {
+            object = object.get(segment)?.as_object()?;
+        }
+        object.get(last)
+    }
+
+    /// Remove and return the value at a path that doesn't cross arrays
+    pub fn take(&self, record: &mut Map<String, Value>) -> Option<Value> {
+        let (last, parents) = self.segments.split_last()?;
+        let mut object = record;
+        for segment in parents {
+            object = object.get_mut(segment)?.as_object_mut()?;
+        }
+        object.remove(last)
+    }
+
+    /// Set the value at a path, creating missing objects along the way;
+    /// false when a non-object value is in the way
+    pub fn insert(&self, record: &mut Map<String, Value>, value: Value) -> bool {
+        let Some((last, parents)) = self.segments.split_last() else {
+            return false;
+        };
+        let mut object = record;
+        for segment in parents {
+            let next = object
+                .entry(segment.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            match next.as_object_mut() {
+                Some(next) => object = next,
+                None => return false,
+            }
+        }
+        object.insert(last.clone(), value);
+        true
+    }
+
+    /// Call `f` with every object the path reaches and the key of the last
+    /// segment in it. The key may be absent from the object.
+    pub fn visit<F: FnMut(&mut Map<String, Value>, &str)>(&self, record: &mut Map<String, Value>, f: &mut F) {
+        visit_object(record, &self.segments, f);
+    }
+}
+
+fn visit_object<F: FnMut(&mut Map<String, Value>, &str)>(object: &mut Map<String, Value>, segments: &[String], f: &mut F) {
+    match segments {
+        [] => {}
+        [last] => f(object, last),
+        [first, rest @ ..] => {
+            if let Some(value) = object.get_mut(first) {
+                visit_value(value, rest, f);
+            }
+        }
+    }
+}
+
+fn visit_value<F: FnMut(&mut Map<String, Value>, &str)>(value: &mut Value, segments: &[String], f: &mut F) {
+    match value {
+        Value::Object(object) => visit_object(object, segments, f),
+        Value::Array(items) => {
+            for item in items {
+                visit_value(item, segments, f);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,417 @@
+//! PII policies, declared per field in a JSON config:
+//!
+//! ```json
+//! {
+//!   "key_env": "PII_HMAC_KEY",
+//!   "fields": {
+//!     "work_email": { "policy": "mask_email" },
+//!     "emails.address": { "policy": "hmac", "ignore_case": true },
+//!     "mobile_phone": { "policy": "mask_phone", "keep_last": 4 },
+//!     "phone_numbers": { "policy": "hmac" },
+//!     "street_addresses": { "policy": "drop" },
+//!     "location_street_address": { "policy": "redact" },
+//!     "birth_date": { "policy": "truncate", "to": "year" }
+//!   }
+//! }
+//! ```
+//!
+//! Field paths are dotted and step through arrays, so `emails.address` is the
+//! `address` of every element of `emails`. A policy on an array or object
+//! applies to every string inside it (`drop` removes the whole field).
+//!
+//! `hmac` replaces a value with the hex HMAC-SHA256 of it under a secret key,
+//! so the same email hashes the same way in every extract and can still be
+//! joined on. The key comes from the `key_file` or, by default, the
+//! `PII_HMAC_KEY` environment variable; it never goes in the config itself.
+//!
+//! Policies fail closed: a value a policy can't handle (a number under
+//! `mask_phone`, a date like `12/04/1987` under `truncate`) is set to null
+//! rather than passed on, and counted as unprocessable. That includes a mask
+//! that would star nothing: a phone with no more digits than `keep_last`
+//! ("N/A", "ext 12") or an email whose local part is a single character.
+
+use crate::path::FieldPath;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DatePart {
+    Year,
+    Month,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "policy", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Policy {
+    /// Remove the field
+    Drop,
+    /// Replace every value with a fixed string
+    Redact {
+        #[serde(default = "default_replacement")]
+        replacement: String,
+    },
+    /// Keyed hash; `ignore_case` lowercases first so `A@x.com` joins `a@x.com`
+    Hmac {
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    /// Keep a date's year (`1987-04-12` → `1987`) or month, or the first `chars`
+    Truncate { to: Option<DatePart>, chars: Option<usize> },
+    /// `john.smith@acme.com` → `j*********@acme.com`
+    MaskEmail {
+        #[serde(default = "default_true")]
+        keep_domain: bool,
+    },
+    /// `+1 (415) 555-0100` → `+* (***) ***-0100`
+    MaskPhone {
+        #[serde(default = "default_keep_last")]
+        keep_last: usize,
+    },
+}
+
+fn default_replacement() -> String {
+    "REDACTED".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_keep_last() -> usize {
+    4
+}
+
+impl Policy {
+    fn verb(&self) -> &'static str {
+        match self {
+            Policy::Drop => "dropped",
+            Policy::Redact { .. } => "redacted",
+            Policy::Hmac { .. } => "hashed",
+            Policy::Truncate { .. } => "truncated",
+            Policy::MaskEmail { .. } | Policy::MaskPhone { .. } => "masked",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PiiConfig {
+    pub key_env: String,
+    pub key_file: Option<String>,
+    pub fields: BTreeMap<String, Policy>,
+}
+
+impl Default for PiiConfig {
+    fn default() -> Self {
+        PiiConfig {
+            key_env: "PII_HMAC_KEY".to_string(),
+            key_file: None,
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+struct Rule {
+    path: FieldPath,
+    policy: Policy,
+}
+
+/// The loaded policies, ready to apply to records
+pub struct PiiTransform {
+    rules: Vec<Rule>,
+    mac: Option<Hmac<Sha256>>,
+}
+
+/// What a policy did to the values under one field
+#[derive(Default, Clone, Copy, Debug)]
+struct FieldCounts {
+    changed: u64,
+    /// Values the policy couldn't handle, set to null instead
+    unprocessable: u64,
+}
+
+impl FieldCounts {
+    fn merge(&mut self, other: &FieldCounts) {
+        self.changed += other.changed;
+        self.unprocessable += other.unprocessable;
+    }
+}
+
+/// Values changed per field, in config order; merge across threads and files
+#[derive(Default, Clone, Debug)]
+pub struct PiiCounts(Vec<FieldCounts>);
+
+impl PiiCounts {
+    pub fn merge(&mut self, other: &PiiCounts) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), FieldCounts::default());
+        }
+        for (total, n) in self.0.iter_mut().zip(&other.0) {
+            total.merge(n);
+        }
+    }
+
+    fn add(&mut self, rule: usize, n: FieldCounts) {
+        if n.changed == 0 && n.unprocessable == 0 {
+            return;
+        }
+        if self.0.len() <= rule {
+            self.0.resize(rule + 1, FieldCounts::default());
+        }
+        self.0[rule].merge(&n);
+    }
+}
+
+/// The outcome of a policy on one string
+enum Replacement {
+    Keep,
+    New(String),
+    Unprocessable,
+}
+
+impl PiiTransform {
+    pub fn load(path: &str) -> Result<PiiTransform, Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read PII config {}: {}", path, e))?;
+        let config: PiiConfig =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid PII config {}: {}", path, e))?;
+        PiiTransform::new(config)
+    }
+
+    pub fn new(config: PiiConfig) -> Result<PiiTransform, Box<dyn Error>> {
+        let mut rules = Vec::with_capacity(config.fields.len());
+        for (path, policy) in config.fields {
+            if let Policy::Truncate { to, chars } = &policy {
+                if to.is_some() == chars.is_some() {
+                    return Err(format!("truncate for {} needs exactly one of \"to\" or \"chars\"", path).into());
+                }
+            }
+            let path = FieldPath::parse(&path).map_err(|e| format!("Invalid PII config: {}", e))?;
+            rules.push(Rule { path, policy });
+        }
+
+        let mac = if rules.iter().any(|r| matches!(r.policy, Policy::Hmac { .. })) {
+            let key = match &config.key_file {
+                Some(file) => fs::read_to_string(file).map_err(|e| format!("Failed to read HMAC key {}: {}", file, e))?,
+                None => std::env::var(&config.key_env)
+                    .map_err(|_| format!("HMAC key variable {} is not set", config.key_env))?,
+            };
+            let key = key.trim_end_matches(['\r', '\n']);
+            if key.is_empty() {
+                return Err("HMAC key is empty".into());
+            }
+            Some(Hmac::<Sha256>::new_from_slice(key.as_bytes()).map_err(|e| format!("Invalid HMAC key: {}", e))?)
+        } else {
+            None
+        };
+
+        Ok(PiiTransform { rules, mac })
+    }
+
+    /// One line per field, e.g. `work_email: 1204 masked` or
+    /// `birth_date: 1198 truncated, 6 unprocessable set to null`
+    pub fn describe(&self, counts: &PiiCounts) -> Vec<String> {
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let n = counts.0.get(i).copied().unwrap_or_default();
+                let mut line = format!("{}: {} {}", rule.path.as_str(), n.changed, rule.policy.verb());
+                if n.unprocessable > 0 {
+                    line.push_str(&format!(", {} unprocessable set to null", n.unprocessable));
+                }
+                line
+            })
+            .collect()
+    }
+
+    pub fn apply(&self, record: &mut Map<String, Value>, counts: &mut PiiCounts) {
+        for (i, rule) in self.rules.iter().enumerate() {
+            let mut n = FieldCounts::default();
+            rule.path.visit(record, &mut |object, key| match &rule.policy {
+                Policy::Drop => n.changed += object.remove(key).map_or(0, |_| 1),
+                policy => {
+                    if let Some(value) = object.get_mut(key) {
+                        self.transform(value, policy, &mut n);
+                    }
+                }
+            });
+            counts.add(i, n);
+        }
+    }
+
+    /// Apply `policy` to every value under `value`
+    fn transform(&self, value: &mut Value, policy: &Policy, counts: &mut FieldCounts) {
+        let replacement = match &mut *value {
+            Value::Array(items) => {
+                for item in items {
+                    self.transform(item, policy, counts);
+                }
+                return;
+            }
+            Value::Object(object) => {
+                for item in object.values_mut() {
+                    self.transform(item, policy, counts);
+                }
+                return;
+            }
+            Value::Null => return,
+            Value::String(s) => self.transform_str(s, policy),
+            other => match policy {
+                Policy::Redact { .. } | Policy::Hmac { .. } => self.transform_str(&other.to_string(), policy),
+                _ => Replacement::Unprocessable,
+            },
+        };
+        match replacement {
+            Replacement::Keep => {}
+            Replacement::New(new) => {
+                *value = Value::String(new);
+                counts.changed += 1;
+            }
+            Replacement::Unprocessable => {
+                *value = Value::Null;
+                counts.unprocessable += 1;
+            }
+        }
+    }
+
+    fn transform_str(&self, s: &str, policy: &Policy) -> Replacement {
+        let new = match policy {
+            Policy::Drop => return Replacement::Keep,
+            Policy::Redact { replacement } => replacement.clone(),
+            Policy::Hmac { ignore_case } => {
+                let Some(mut mac) = self.mac.clone() else {
+                    return Replacement::Unprocessable;
+                };
+                let text = s.trim();
+                if *ignore_case {
+                    mac.update(text.to_lowercase().as_bytes());
+                } else {
+                    mac.update(text.as_bytes());
+                }
+                hex(&mac.finalize().into_bytes())
+            }
+            Policy::Truncate { to: Some(part), .. } => match truncate_date(s, *part) {
+                Some(new) => new,
+                None => return Replacement::Unprocessable,
+            },
+            Policy::Truncate { chars, .. } => s.chars().take(chars.unwrap_or(0)).collect(),
+            Policy::MaskEmail { keep_domain } => match mask_email(s, *keep_domain) {
+                Some(new) => new,
+                None => return Replacement::Unprocessable,
+            },
+            Policy::MaskPhone { keep_last } => match mask_phone(s, *keep_last) {
+                Some(new) => new,
+                None => return Replacement::Unprocessable,
+            },
+        };
+        if new == s {
+            Replacement::Keep
+        } else {
+            Replacement::New(new)
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    out
+}
+
+/// `YYYY...` → `YYYY`, `YYYY-MM...` → `YYYY-MM`; None if the text doesn't start that way
+fn truncate_date(s: &str, part: DatePart) -> Option<String> {
+    let s = s.trim();
+    let len = match part {
+        DatePart::Year => 4,
+        DatePart::Month => 7,
+    };
+    let head = s.get(..len)?;
+    let well_formed = head
+        .char_indices()
+        .all(|(i, c)| if i == 4 { c == '-' } else { c.is_ascii_digit() });
+    well_formed.then(|| head.to_string())
+}
+
+/// Keep the first character, star the rest, keep punctuation where it was;
+/// None if there was nothing to star
+fn mask_word(word: &str) -> Option<String> {
+    let mut starred = false;
+    let masked = word
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i == 0 || !c.is_alphanumeric() {
+                c
+            } else {
+                starred = true;
+                '*'
+            }
+        })
+        .collect();
+    starred.then_some(masked)
+}
+
+/// None if the local part (or, without an `@`, the whole value) has nothing to star
+fn mask_email(s: &str, keep_domain: bool) -> Option<String> {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return mask_word(s);
+    };
+    if local.chars().count() < 2 {
+        return None;
+    }
+    let local: String = local
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i == 0 { c } else { '*' })
+        .collect();
+    if keep_domain {
+        return Some(format!("{}@{}", local, domain));
+    }
+    // Mask every label but the top-level domain
+    let labels: Vec<&str> = domain.split('.').collect();
+    let last = labels.len() - 1;
+    let domain: Vec<String> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| match mask_word(label) {
+            Some(masked) if i != last => masked,
+            _ => label.to_string(),
+        })
+        .collect();
+    Some(format!("{}@{}", local, domain.join(".")))
+}
+
+/// Star every digit except the last `keep_last`; separators and `+` stay put.
+/// None if no digit would be starred
+fn mask_phone(s: &str, keep_last: usize) -> Option<String> {
+    let digits = s.chars().filter(char::is_ascii_digit).count();
+    if digits <= keep_last {
+        return None;
+    }
+    let mut seen = 0;
+    let masked = s
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen + keep_last > digits {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect();
+    Some(masked)
+}
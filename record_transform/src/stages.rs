@@ -0,0 +1,89 @@
+//! The stages a tool runs on every parsed record, in order: normalisation,
+//! the configured transform steps, then PII policies (so derived fields come
+//! from normalised values, and hashes and masks see the final record).
+
+use crate::normalise::{NormaliseCounts, Normaliser};
+use crate::pii::{PiiCounts, PiiTransform};
+use crate::transform::{Transform, TransformCounts};
+use serde_json::{Map, Value};
+use std::error::Error;
+
+#[derive(Default)]
+pub struct Stages {
+    pub normalise: Option<Normaliser>,
+    pub transform: Option<Transform>,
+    pub pii: Option<PiiTransform>,
+}
+
+/// Per-stage counts; merge across threads and files
+#[derive(Default, Clone, Debug)]
+pub struct StageCounts {
+    normalise: NormaliseCounts,
+    transform: TransformCounts,
+    pii: PiiCounts,
+}
+
+impl StageCounts {
+    pub fn merge(&mut self, other: &StageCounts) {
+        self.normalise.merge(&other.normalise);
+        self.transform.merge(&other.transform);
+        self.pii.merge(&other.pii);
+    }
+}
+
+impl Stages {
+    /// Load the configs that were given; None for a stage that isn't used
+    pub fn load(normalise: Option<&str>, transform: Option<&str>, pii: Option<&str>) -> Result<Stages, Box<dyn Error>> {
+        Ok(Stages {
+            normalise: normalise.map(Normaliser::load).transpose()?,
+            transform: transform.map(Transform::load).transpose()?,
+            pii: pii.map(PiiTransform::load).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.normalise.is_none() && self.transform.is_none() && self.pii.is_none()
+    }
+
+    /// Names of the enabled stages, in the order they run
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.normalise.is_some() {
+            names.push("normalise");
+        }
+        if self.transform.is_some() {
+            names.push("transform");
+        }
+        if self.pii.is_some() {
+            names.push("pii");
+        }
+        names
+    }
+
+    pub fn apply(&self, record: &mut Map<String, Value>, counts: &mut StageCounts) {
+        if let Some(normalise) = &self.normalise {
+            normalise.apply(record, &mut counts.normalise);
+        }
+        if let Some(transform) = &self.transform {
+            transform.apply(record, &mut counts.transform);
+        }
+        if let Some(pii) = &self.pii {
+            pii.apply(record, &mut counts.pii);
+        }
+    }
+
+    /// One line per field of every stage, e.g. `pii work_email: 1204 masked`
+    pub fn describe(&self, counts: &StageCounts) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(normalise) = &self.normalise {
+            lines.extend(normalise.describe(&counts.normalise).into_iter().map(|l| format!("normalise {}", l)));
+        }
+        if let Some(transform) = &self.transform {
+            lines.extend(transform.describe(&counts.transform).into_iter().map(|l| format!("transform {}", l)));
+        }
+        if let Some(pii) = &self.pii {
+            lines.extend(pii.describe(&counts.pii).into_iter().map(|l| format!("pii {}", l)));
+        }
+        lines
+    }
+}
@@ -0,0 +1,376 @@
+//! Declarative per-record operations, loaded from a JSON config and applied
+//! in the order they are listed:
+//!
+//! ```json
+//! {
+//!   "steps": [
+//!     { "rename": { "work_email": "email", "emails.type": "kind" } },
+//!     { "move": { "version_status.status": "status" } },
+//!     { "derive": { "field": "email_domain", "from": "email", "fn": "email_domain" } },
+//!     { "derive": { "field": "birth_year", "from": "birth_date", "fn": "year" } },
+//!     { "cast": { "birth_year": "int", "linkedin_connections": "int" } },
+//!     { "drop": ["street_addresses", "experience.summary"] },
+//!     { "keep": ["id", "full_name", "email", "email_domain", "birth_year", "status"] }
+//!   ]
+//! }
+//! ```
+//!
+//! - `rename` gives the last key of a path a new name in the same object
+//! - `move` takes the value at one path and puts it at another, creating
+//!   objects along the way
+//! - `derive` writes `fn(from)` to `field` unless it already holds a value
+//!   (`"overwrite": true` replaces it); `fn` is `copy`, `lowercase`,
+//!   `email_domain` or `year` (`1987-04-12` → 1987)
+//! - `cast` converts to `string`, `int`, `float`, `bool` or `json` (JSON text)
+//! - `drop` removes paths, `keep` removes everything else
+//!
+//! `rename`, `cast` and `drop` step through arrays like every other stage;
+//! `move`, `derive` and `keep` paths must not cross arrays. A value that
+//! can't be derived or cast is left as it was and counted as failed.
+
+use crate::path::FieldPath;
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DeriveFn {
+    Copy,
+    Lowercase,
+    EmailDomain,
+    Year,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CastType {
+    String,
+    Int,
+    Float,
+    Bool,
+    Json,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DeriveConfig {
+    pub field: String,
+    pub from: String,
+    #[serde(rename = "fn")]
+    pub function: DeriveFn,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum StepConfig {
+    Rename(BTreeMap<String, String>),
+    Move(BTreeMap<String, String>),
+    Derive(DeriveConfig),
+    Cast(BTreeMap<String, CastType>),
+    Drop(Vec<String>),
+    Keep(Vec<String>),
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransformConfig {
+    pub steps: Vec<StepConfig>,
+}
+
+enum Step {
+    Rename(Vec<(FieldPath, String)>),
+    Move(Vec<(FieldPath, FieldPath)>),
+    Derive {
+        field: FieldPath,
+        from: FieldPath,
+        function: DeriveFn,
+        overwrite: bool,
+    },
+    Cast(Vec<(FieldPath, CastType)>),
+    Drop(Vec<FieldPath>),
+    Keep(Vec<FieldPath>),
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::Rename(_) => "rename",
+            Step::Move(_) => "move",
+            Step::Derive { .. } => "derive",
+            Step::Cast(_) => "cast",
+            Step::Drop(_) => "drop",
+            Step::Keep(_) => "keep",
+        }
+    }
+}
+
+/// Per step: values changed and values that failed
+#[derive(Default, Clone, Debug)]
+pub struct TransformCounts(Vec<(u64, u64)>);
+
+impl TransformCounts {
+    pub fn merge(&mut self, other: &TransformCounts) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), (0, 0));
+        }
+        for (total, (changed, failed)) in self.0.iter_mut().zip(&other.0) {
+            total.0 += changed;
+            total.1 += failed;
+        }
+    }
+
+    fn add(&mut self, step: usize, changed: u64, failed: u64) {
+        if changed == 0 && failed == 0 {
+            return;
+        }
+        if self.0.len() <= step {
+            self.0.resize(step + 1, (0, 0));
+        }
+        self.0[step].0 += changed;
+        self.0[step].1 += failed;
+    }
+}
+
+pub struct Transform {
+    steps: Vec<Step>,
+}
+
+impl Transform {
+    pub fn load(path: &str) -> Result<Transform, Box<dyn Error>> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read transform config {}: {}", path, e))?;
+        let config: TransformConfig =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid transform config {}: {}", path, e))?;
+        Transform::new(config)
+    }
+
+    pub fn new(config: TransformConfig) -> Result<Transform, Box<dyn Error>> {
+        let parse = |path: &str| FieldPath::parse(path).map_err(|e| format!("Invalid transform config: {}", e));
+        let mut steps = Vec::with_capacity(config.steps.len());
+        for step in config.steps {
+            steps.push(match step {
+                StepConfig::Rename(pairs) => Step::Rename(
+                    pairs
+                        .into_iter()
+                        .map(|(from, to)| {
+                            if to.is_empty() || to.contains('.') {
+                                return Err(format!("rename target {:?} must be a plain key", to));
+                            }
+                            Ok((parse(&from)?, to))
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                StepConfig::Move(pairs) => Step::Move(
+                    pairs
+                        .into_iter()
+                        .map(|(from, to)| Ok::<_, String>((parse(&from)?, parse(&to)?)))
+                        .collect::<Result<_, _>>()?,
+                ),
+                StepConfig::Derive(derive) => Step::Derive {
+                    field: parse(&derive.field)?,
+                    from: parse(&derive.from)?,
+                    function: derive.function,
+                    overwrite: derive.overwrite,
+                },
+                StepConfig::Cast(casts) => Step::Cast(
+                    casts
+                        .into_iter()
+                        .map(|(path, to)| Ok::<_, String>((parse(&path)?, to)))
+                        .collect::<Result<_, _>>()?,
+                ),
+                StepConfig::Drop(paths) => Step::Drop(paths.iter().map(|p| parse(p)).collect::<Result<_, _>>()?),
+                StepConfig::Keep(paths) => Step::Keep(paths.iter().map(|p| parse(p)).collect::<Result<_, _>>()?),
+            });
+        }
+        Ok(Transform { steps })
+    }
+
+    /// One line per step, e.g. `2 derive: 1204 changed, 3 failed`
+    pub fn describe(&self, counts: &TransformCounts) -> Vec<String> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let (changed, failed) = counts.0.get(i).copied().unwrap_or((0, 0));
+                format!("{} {}: {} changed, {} failed", i + 1, step.name(), changed, failed)
+            })
+            .collect()
+    }
+
+    pub fn apply(&self, record: &mut Map<String, Value>, counts: &mut TransformCounts) {
+        for (i, step) in self.steps.iter().enumerate() {
+            let (changed, failed) = apply_step(step, record);
+            counts.add(i, changed, failed);
+        }
+    }
+}
+
+/// (changed, failed) for one step on one record
+fn apply_step(step: &Step, record: &mut Map<String, Value>) -> (u64, u64) {
+    let (mut changed, mut failed) = (0, 0);
+    match step {
+        Step::Rename(pairs) => {
+            for (from, to) in pairs {
+                from.visit(record, &mut |object, key| {
+                    if let Some(value) = object.remove(key) {
+                        object.insert(to.clone(), value);
+                        changed += 1;
+                    }
+                });
+            }
+        }
+        Step::Move(pairs) => {
+            for (from, to) in pairs {
+                if let Some(value) = from.take(record) {
+                    if to.insert(record, value) {
+                        changed += 1;
+                    } else {
+                        failed += 1;
+                    }
+                }
+            }
+        }
+        Step::Derive {
+            field,
+            from,
+            function,
+            overwrite,
+        } => {
+            let present = field.get(record).is_some_and(|v| !v.is_null());
+            if present && !overwrite {
+                return (0, 0);
+            }
+            let derived = match from.get(record) {
+                None | Some(Value::Null) => return (0, 0),
+                Some(source) => derive(*function, source),
+            };
+            match derived.map(|value| field.insert(record, value)) {
+                Some(true) => changed += 1,
+                _ => failed += 1,
+            }
+        }
+        Step::Cast(casts) => {
+            for (path, to) in casts {
+                path.visit(record, &mut |object, key| {
+                    if let Some(value) = object.get_mut(key) {
+                        let (c, f) = cast_all(value, *to);
+                        changed += c;
+                        failed += f;
+                    }
+                });
+            }
+        }
+        Step::Drop(paths) => {
+            for path in paths {
+                path.visit(record, &mut |object, key| {
+                    if object.remove(key).is_some() {
+                        changed += 1;
+                    }
+                });
+            }
+        }
+        Step::Keep(paths) => {
+            let mut kept = Map::new();
+            for path in paths {
+                if let Some(value) = path.take(record) {
+                    path.insert(&mut kept, value);
+                }
+            }
+            changed += record.len() as u64;
+            *record = kept;
+        }
+    }
+    (changed, failed)
+}
+
+fn derive(function: DeriveFn, source: &Value) -> Option<Value> {
+    match function {
+        DeriveFn::Copy => Some(source.clone()),
+        DeriveFn::Lowercase => Some(Value::String(source.as_str()?.to_lowercase())),
+        DeriveFn::EmailDomain => {
+            let (_, domain) = source.as_str()?.trim().rsplit_once('@')?;
+            (!domain.is_empty()).then(|| Value::String(domain.to_lowercase()))
+        }
+        DeriveFn::Year => match source {
+            Value::Number(n) => n.as_i64().map(Value::from),
+            Value::String(s) => {
+                let year = s.trim().get(..4)?;
+                if !year.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                year.parse::<i64>().ok().map(Value::from)
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Cast a value, or every element of an array unless casting to JSON text
+fn cast_all(value: &mut Value, to: CastType) -> (u64, u64) {
+    if let (Value::Array(items), false) = (&mut *value, matches!(to, CastType::Json)) {
+        return items.iter_mut().fold((0, 0), |(c, f), item| {
+            let (ci, fi) = cast_all(item, to);
+            (c + ci, f + fi)
+        });
+    }
+    match cast(value, to) {
+        Some(new) if new == *value => (0, 0),
+        Some(new) => {
+            *value = new;
+            (1, 0)
+        }
+        None => (0, 1),
+    }
+}
+
+/// The cast value, or None when it doesn't convert; nulls stay null
+fn cast(value: &Value, to: CastType) -> Option<Value> {
+    if value.is_null() {
+        return Some(Value::Null);
+    }
+    match to {
+        CastType::String => match value {
+            Value::String(_) => Some(value.clone()),
+            Value::Number(n) => Some(Value::String(n.to_string())),
+            Value::Bool(b) => Some(Value::String(b.to_string())),
+            _ => None,
+        },
+        CastType::Int => match value {
+            Value::Number(n) if n.is_i64() || n.is_u64() => Some(value.clone()),
+            Value::Number(n) => {
+                let f = n.as_f64()?;
+                (f.fract() == 0.0 && f.abs() < i64::MAX as f64).then(|| Value::from(f as i64))
+            }
+            Value::String(s) => s.trim().parse::<i64>().ok().map(Value::from),
+            _ => None,
+        },
+        CastType::Float => match value {
+            Value::Number(n) => Number::from_f64(n.as_f64()?).map(Value::Number),
+            Value::String(s) => Number::from_f64(s.trim().parse().ok()?).map(Value::Number),
+            _ => None,
+        },
+        CastType::Bool => match value {
+            Value::Bool(_) => Some(value.clone()),
+            Value::Number(n) => match n.as_i64()? {
+                0 => Some(Value::Bool(false)),
+                1 => Some(Value::Bool(true)),
+                _ => None,
+            },
+            Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(Value::Bool(true)),
+                "false" | "no" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        },
+        CastType::Json => match value {
+            Value::String(_) => Some(value.clone()),
+            other => Some(Value::String(other.to_string())),
+        },
+    }
+}